@@ -1,9 +1,9 @@
 use bioma_actor::prelude::*;
 use futures::StreamExt;
-use object_store::local::LocalFileSystem;
 use object_store::{path::Path, ObjectStore};
 use rand::{distributions::Alphanumeric, Rng};
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use tracing::{error, info};
 
 fn generate_random_bytes(size: usize) -> Vec<u8> {
@@ -27,7 +27,7 @@ impl Actor for RandomObjectSaver {
 
     async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), SystemActorError> {
         info!("{} Started", ctx.id());
-        let store = ctx.engine().local_store()?;
+        let store = ctx.engine().store();
 
         // Generate random objects of 1 to 3 MBs
         for i in 0..self.num_objects {
@@ -51,8 +51,8 @@ impl Actor for RandomObjectSaver {
 pub enum RandomObjectLoaderError {
     #[error("System error: {0}")]
     System(#[from] SystemActorError),
-    #[error("LocalFileSystem not initialized")]
-    LocalFileSystemNotInitialized,
+    #[error("Object store not initialized")]
+    StoreNotInitialized,
 }
 
 impl ActorError for RandomObjectLoaderError {}
@@ -62,7 +62,7 @@ struct RandomObjectLoader {
     prefix: std::path::PathBuf,
     num_objects: usize,
     #[serde(skip)]
-    store: Option<LocalFileSystem>,
+    store: Option<Arc<dyn ObjectStore>>,
 }
 
 impl Message<ObjectSaved> for RandomObjectLoader {
@@ -70,7 +70,7 @@ impl Message<ObjectSaved> for RandomObjectLoader {
 
     async fn handle(&mut self, ctx: &mut ActorContext<Self>, msg: &ObjectSaved) -> Result<(), RandomObjectLoaderError> {
         let Some(store) = &self.store else {
-            return Err(RandomObjectLoaderError::LocalFileSystemNotInitialized);
+            return Err(RandomObjectLoaderError::StoreNotInitialized);
         };
         self.num_objects -= 1;
         info!("{} Received: {:?}", ctx.id(), msg);
@@ -85,7 +85,7 @@ impl Actor for RandomObjectLoader {
     type Error = RandomObjectLoaderError;
 
     async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), RandomObjectLoaderError> {
-        self.store = Some(ctx.engine().local_store()?);
+        self.store = Some(ctx.engine().store());
 
         let mut stream = ctx.recv().await?;
         while let Some(Ok(frame)) = stream.next().await {
@@ -1,15 +1,22 @@
+use crate::encoding::{deserialize_content, Encoding, StoredFrame};
 use crate::engine::{Engine, Record};
+use crate::error::{variant_name, ErrorKind, ErrorReply};
+use crate::factory::ActorHandle;
+use crate::rate_limit::{RateLimit, RateLimitStatus, RateLimiter};
+use crate::retry::{CircuitBreakerConfig, RetryPolicy};
 use futures::{future, Stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use surrealdb::sql;
 use surrealdb::RecordIdKey;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
 // use std::any::type_name;
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::future::Future;
 use std::pin::Pin;
-use std::time::{Duration, SystemTime};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 use std::{borrow::Cow, sync::atomic::AtomicU64};
 use surrealdb::{sql::Id, value::RecordId, Action, Notification};
 use tracing::{debug, error, trace};
@@ -17,11 +24,47 @@ use tracing::{debug, error, trace};
 // Constants for database table names
 const DB_TABLE_ACTOR: &str = "actor";
 const DB_TABLE_MESSAGE: &str = "message";
-const DB_TABLE_REPLY: &str = "reply";
+pub(crate) const DB_TABLE_REPLY: &str = "reply";
+const DB_TABLE_MESSAGE_CHUNK: &str = "message_chunk";
 const DB_TABLE_HEALTH: &str = "health";
+const DB_TABLE_ACTIVATION: &str = "activation";
+const DB_TABLE_LEASE: &str = "lease";
+const DB_TABLE_IDEMPOTENCY: &str = "idempotency";
+const DB_TABLE_HIERARCHY: &str = "hierarchy";
+
+/// Time a passivating actor keeps listening for late messages before stopping
+const PASSIVATION_GRACE: Duration = Duration::from_millis(100);
 
 /// Implement this trait to define custom actor error types
-pub trait ActorError: std::error::Error + Debug + Send + Sync + From<SystemActorError> {}
+///
+/// Errors are replied to the sender as an `ErrorReply`, built from the methods below.
+/// Override `data` with the serialized error to let senders decode it back into this type:
+///
+/// ```rust
+/// impl ActorError for CounterError {
+///     fn data(&self) -> Option<serde_json::Value> {
+///         serde_json::to_value(self).ok()
+///     }
+/// }
+/// ```
+pub trait ActorError: std::error::Error + Debug + Send + Sync + From<SystemActorError> {
+    /// Machine-readable code of the error, the name of its variant by default
+    ///
+    /// The default is read from the `Debug` output, override it for errors without a derived `Debug`.
+    fn code(&self) -> Cow<'static, str> {
+        variant_name(self)
+    }
+
+    /// How the sender should treat the error, permanent by default
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Permanent
+    }
+
+    /// Details the sender can decode the error from, none by default
+    fn data(&self) -> Option<Value> {
+        None
+    }
+}
 
 /// Enumerates the types of errors that can occur in Actor framework
 #[derive(thiserror::Error, Debug)]
@@ -87,6 +130,13 @@ pub enum SystemActorError {
     #[error("Message reply error: {0}")]
     MessageReply(Cow<'static, str>),
 
+    /// Error replied by the actor handling a message.
+    ///
+    /// Carries the code, kind and data of the receiver's error, which
+    /// `SystemActorError::reply_as` can decode into the receiver's error type.
+    #[error("Error reply: {0}")]
+    ErrorReply(ErrorReply),
+
     /// Timeout while waiting for a message reply.
     ///
     /// This error occurs when a reply to a message isn't received within
@@ -142,6 +192,89 @@ pub enum SystemActorError {
     #[error("Actor tag not found: {0}")]
     ActorTagNotFound(Cow<'static, str>),
 
+    /// Invalid config for an actor tag.
+    ///
+    /// This occurs when the config passed to the registry doesn't match
+    /// what the factory registered for the tag expects.
+    #[error("Invalid config for actor tag {0}: {1}")]
+    InvalidActorConfig(Cow<'static, str>, Cow<'static, str>),
+
+    /// A factory failed to spawn an actor.
+    ///
+    /// This occurs when the factory registered for a tag returns an error
+    /// while spawning an actor from the registry.
+    #[error("Failed to spawn actor {0} with tag {1}: {2}")]
+    ActorSpawn(ActorId, Cow<'static, str>, Box<SystemActorError>),
+
+    /// The actor's lease is held by another owner.
+    ///
+    /// This occurs when spawning an actor with a `LeaseConfig` while another
+    /// process owns the actor ID and keeps renewing its lease.
+    #[error("Actor lease held by another owner: {0}")]
+    LeaseHeld(ActorId),
+
+    /// A reply that was no longer needed.
+    ///
+    /// Occurs for the targets of a scatter-gather request that hadn't replied
+    /// by the time the gather strategy had its answer. The target isn't told, and still replies.
+    #[error("Reply cancelled: {0}")]
+    ReplyCancelled(ActorId),
+
+    /// The circuit breaker of the target is open.
+    ///
+    /// This occurs when sending with a `CircuitBreakerConfig` to an actor that
+    /// kept timing out, until its cool-down is over.
+    #[error("Circuit open for actor: {0}")]
+    CircuitOpen(ActorId),
+
+    /// The receiver's rate limit was exceeded.
+    ///
+    /// This occurs when sending to an actor spawned with a `RateLimit` while its bucket
+    /// is empty, or while its queue of delayed messages is full.
+    #[error("Rate limit exceeded for actor: {0}")]
+    RateLimited(ActorId),
+
+    /// A router had no worker to route a message to.
+    ///
+    /// This occurs when sending to a `Router` whose pool was resized to zero workers.
+    #[error("Router has no workers: {0}")]
+    NoWorkers(ActorId),
+
+    /// A schema migration failed.
+    ///
+    /// Carries the component and version of the migration, and the errors of its statements.
+    /// The migration's transaction is rolled back, so the schema stays at the previous version.
+    #[error("Migration {1} of {0} failed: {2}")]
+    MigrationFailed(Cow<'static, str>, u32, String),
+
+    /// A message rejected by an `Interceptor`.
+    ///
+    /// Interceptors can return this error from `on_send` or `on_recv`
+    /// to stop a message from being stored or handled.
+    #[error("Message rejected: {0}")]
+    MessageRejected(Cow<'static, str>),
+
+    /// Error encoding or decoding the content of a frame.
+    ///
+    /// Occurs when the content of a message or reply doesn't match
+    /// the `Encoding` of its frame, or can't be serialized with it.
+    #[error("Encoding error: {0}")]
+    Encoding(Cow<'static, str>),
+
+    /// Error encrypting or decrypting a stored payload.
+    ///
+    /// Occurs when the key provider doesn't know the key of a payload,
+    /// or when an encrypted payload is read without encryption configured.
+    #[error("Encryption error: {0}")]
+    Encryption(Cow<'static, str>),
+
+    /// An operation the context of a concurrently handled message can't do.
+    ///
+    /// This occurs when calling `ActorContext::spawn_child` from a handler of
+    /// `ActorContext::handle_concurrently`, instead of the actor's own context.
+    #[error("Not available while handling messages concurrently: {0}")]
+    SharedContext(Cow<'static, str>),
+
     /// Error when attempting to communicate with an unhealthy actor.
     ///
     /// This occurs when trying to send a message to an actor that hasn't
@@ -150,7 +283,26 @@ pub enum SystemActorError {
     UnhealthyActor(ActorId),
 }
 
-impl ActorError for SystemActorError {}
+impl ActorError for SystemActorError {
+    fn code(&self) -> Cow<'static, str> {
+        match self {
+            // Keep the code of errors relayed from another actor
+            SystemActorError::ErrorReply(reply) => reply.code.clone(),
+            error => variant_name(error),
+        }
+    }
+
+    fn kind(&self) -> ErrorKind {
+        self.error_kind()
+    }
+
+    fn data(&self) -> Option<Value> {
+        match self {
+            SystemActorError::ErrorReply(reply) => reply.data.clone(),
+            _ => None,
+        }
+    }
+}
 
 /// The message frame that is sent between actors
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -164,11 +316,71 @@ pub struct FrameMessage {
     /// Receiver
     pub rx: RecordId,
     /// Message content
-    #[serde(default)]
+    #[serde(default, deserialize_with = "deserialize_content", skip_serializing_if = "Value::is_null")]
     pub msg: Value,
+    /// Object store path of the message content, when it was too large to be stored inline
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload: Option<Cow<'static, str>>,
+    /// Whether the message content is streamed in chunks, see `ActorContext::send_stream`
+    #[serde(default)]
+    pub stream: bool,
+    /// How long the receiver waits for each message of a streamed request, from the sender's `SendOptions`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream_timeout: Option<sql::Duration>,
+    /// How the message content is encoded
+    #[serde(default, skip_serializing_if = "Encoding::is_json")]
+    pub encoding: Encoding,
+    /// Whether the sender may cancel the message before it's handled, see `ActorContext::send_all`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cancellable: bool,
+    /// Engine the message was received from, to reply to messages that can't be decoded
+    #[serde(skip)]
+    engine: Option<Engine>,
+}
+
+/// Loads the content of a message frame from the object store, if it was offloaded
+async fn load_message_payload(engine: &Engine, mut frame: FrameMessage) -> Result<FrameMessage, SystemActorError> {
+    frame.msg = match &frame.payload {
+        Some(path) => engine.load_payload(path).await?,
+        None => engine.decrypt(std::mem::take(&mut frame.msg), &frame.id.to_string(), "msg")?,
+    };
+    frame.engine = Some(engine.clone());
+    Ok(frame)
+}
+
+/// Loads the content of a reply frame from the object store, if it was offloaded, and shows it to the interceptors
+async fn load_reply_payload(engine: &Engine, mut reply: FrameReply) -> Result<FrameReply, SystemActorError> {
+    if let Some(path) = &reply.payload {
+        reply.msg = engine.load_payload(path).await?;
+    }
+    engine.interceptors().on_reply(&reply);
+    Ok(reply)
+}
+
+/// Object store path of a payload moved out of a record of a table
+fn payload_path(table: &str, key: impl std::fmt::Display) -> String {
+    format!("payloads/{}/{}", table, key)
+}
+
+/// Content of a message about to be sent
+struct FrameContent {
+    msg: Value,
+    /// Object store path the content was already moved to, when forwarding an offloaded message
+    payload: Option<Cow<'static, str>>,
+}
+
+impl From<Value> for FrameContent {
+    fn from(msg: Value) -> Self {
+        Self { msg, payload: None }
+    }
 }
 
 impl FrameMessage {
+    /// The message id, also the id of its replies
+    pub fn id(&self) -> &RecordId {
+        &self.id
+    }
+
     /// Check if this frame matches a specific message type
     /// and deserialize it into the message type.
     ///
@@ -179,23 +391,55 @@ impl FrameMessage {
     /// # Returns
     ///
     /// * `Some(M)` if the frame's name matches the type name of `M` and deserialization succeeds.
-    /// * `None` if the frame's name doesn't match or deserialization fails.
+    /// * `None` if the frame's name doesn't match or deserialization fails,
+    ///   in which case the sender gets an `Encoding` error as reply.
     pub fn is<M>(&self) -> Option<M>
     where
         M: Clone + Serialize + for<'de> Deserialize<'de> + Send + Sync,
     {
-        if self.name == std::any::type_name::<M>() {
-            serde_json::from_value(self.msg.clone()).ok()
-        } else {
-            None
+        if self.stream || self.name != std::any::type_name::<M>() {
+            return None;
+        }
+        match self.encoding.decode(self.msg.clone()) {
+            Ok(message) => Some(message),
+            Err(e) => {
+                let e = match e {
+                    SystemActorError::Encoding(_) => e,
+                    e => SystemActorError::Encoding(e.to_string().into()),
+                };
+                error!("[{}] msg-decode-error {} {} {}", self.rx, self.name, self.id.key(), e);
+                // Frames not received from an engine have no one to reply to
+                if let Some(engine) = self.engine.as_ref().filter(|_| tokio::runtime::Handle::try_current().is_ok()) {
+                    reply_error(engine.clone(), self.clone(), &e);
+                }
+                None
+            }
         }
     }
+
+    /// The frame as stored in the database
+    fn stored(mut self) -> StoredFrame<Self> {
+        let msg = std::mem::take(&mut self.msg);
+        let encoding = self.encoding;
+        StoredFrame::new(self, msg, encoding)
+    }
+
+    /// Check if this frame is a stream of messages of a specific type,
+    /// sent with `ActorContext::send_stream`.
+    ///
+    /// The messages are handled with `StreamMessage::reply_stream`.
+    pub fn is_stream<M>(&self) -> bool
+    where
+        M: Clone + Serialize + for<'de> Deserialize<'de> + Send + Sync,
+    {
+        self.stream && self.name == std::any::type_name::<M>()
+    }
 }
 
 /// Identifier for a reply message that may be part of a stream.
 ///
 /// Reply IDs contain both a base message ID and an optional chunk number
-/// to support streaming replies.
+/// to support streaming replies. They also identify the chunks of streamed messages.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ReplyId {
     /// Base message identifier
@@ -219,6 +463,19 @@ impl ReplyId {
 
     /// Converts the ReplyId to a SurrealDB record ID
     pub fn to_record_id(&self) -> surrealdb::RecordId {
+        self.to_record_id_in(DB_TABLE_REPLY)
+    }
+
+    /// Where the frame is stored in the given table, which its encrypted payloads are bound to
+    pub(crate) fn location(&self, table: &str) -> String {
+        match self.chunk {
+            Some(chunk) => format!("{}:{}#{}", table, self.id, chunk),
+            None => format!("{}:{}#final", table, self.id),
+        }
+    }
+
+    /// Converts the ReplyId to a SurrealDB record ID in the given table
+    fn to_record_id_in(&self, table: &str) -> surrealdb::RecordId {
         let mut obj = surrealdb::Object::new();
         obj.insert(
             "id".to_string(),
@@ -236,7 +493,7 @@ impl ReplyId {
         );
 
         let key = RecordIdKey::from(obj);
-        surrealdb::RecordId::from_table_key(DB_TABLE_REPLY, key)
+        surrealdb::RecordId::from_table_key(table, key)
     }
 }
 
@@ -256,14 +513,37 @@ pub struct FrameReply {
     /// Receiver
     pub rx: RecordId,
     /// Message content
-    #[serde(default)]
+    #[serde(default, deserialize_with = "deserialize_content", skip_serializing_if = "Value::is_null")]
     pub msg: Value,
     /// Error message
     #[serde(default)]
     pub err: Value,
+    /// Object store path of the reply content, when it was too large to be stored inline
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload: Option<Cow<'static, str>>,
+    /// How the reply content is encoded
+    #[serde(default, skip_serializing_if = "Encoding::is_json")]
+    pub encoding: Encoding,
 }
 
 impl FrameReply {
+    /// The frame as stored in the database
+    fn stored(mut self) -> StoredFrame<Self> {
+        let msg = std::mem::take(&mut self.msg);
+        let encoding = self.encoding;
+        StoredFrame::new(self, msg, encoding)
+    }
+
+    /// The id key of the message this is a reply to
+    pub fn message_id(&self) -> &str {
+        &self.id.id
+    }
+
+    /// The chunk number of the reply, `None` for the final reply
+    pub fn chunk(&self) -> Option<u64> {
+        self.id.chunk
+    }
+
     /// Creates a new reply frame for a chunk in a streaming response
     pub fn new_chunk(
         id: String,
@@ -273,7 +553,16 @@ impl FrameReply {
         rx: surrealdb::RecordId,
         msg: Value,
     ) -> Self {
-        Self { id: ReplyId::new_chunk(id, chunk_num), name, tx, rx, msg, err: Value::Null }
+        Self {
+            id: ReplyId::new_chunk(id, chunk_num),
+            name,
+            tx,
+            rx,
+            msg,
+            err: Value::Null,
+            payload: None,
+            encoding: Encoding::default(),
+        }
     }
 
     /// Creates a new error reply frame for a chunk in a streaming response
@@ -285,12 +574,57 @@ impl FrameReply {
         rx: surrealdb::RecordId,
         err: Value,
     ) -> Self {
-        Self { id: ReplyId::new_chunk(id, chunk_num), name, tx, rx, msg: Value::Null, err }
+        Self {
+            id: ReplyId::new_chunk(id, chunk_num),
+            name,
+            tx,
+            rx,
+            msg: Value::Null,
+            err,
+            payload: None,
+            encoding: Encoding::default(),
+        }
+    }
+
+    /// Encrypts the content and error of the reply before it's stored in the given table
+    fn encrypt(mut self, engine: &Engine, table: &str) -> Result<Self, SystemActorError> {
+        let location = self.id.location(table);
+        self.msg = engine.encrypt(std::mem::take(&mut self.msg), &location, "msg")?;
+        self.err = engine.encrypt(std::mem::take(&mut self.err), &location, "err")?;
+        Ok(self)
+    }
+
+    /// Decrypts the content and error of a reply stored in the given table
+    pub(crate) fn decrypt(mut self, engine: &Engine, table: &str) -> Result<Self, SystemActorError> {
+        let location = self.id.location(table);
+        self.msg = engine.decrypt(std::mem::take(&mut self.msg), &location, "msg")?;
+        self.err = engine.decrypt(std::mem::take(&mut self.err), &location, "err")?;
+        Ok(self)
+    }
+
+    /// Encrypts the reply, or replaces its content with the encryption error
+    fn encrypt_or_error(self, engine: &Engine, table: &str) -> Self {
+        match self.clone().encrypt(engine, table) {
+            Ok(reply) => reply,
+            Err(e) => {
+                error!("[{}] reply-encrypt-error {} {} {}", self.tx, self.name, self.id.id, e);
+                FrameReply { msg: Value::Null, err: ErrorReply::new(&e).to_value(), payload: None, ..self }
+            }
+        }
     }
 
     /// Creates a new reply frame for a final response
     pub fn new_final(id: String, name: Cow<'static, str>, tx: surrealdb::RecordId, rx: surrealdb::RecordId) -> Self {
-        Self { id: ReplyId::new_final(id), name, tx, rx, msg: Value::Null, err: Value::Null }
+        Self {
+            id: ReplyId::new_final(id),
+            name,
+            tx,
+            rx,
+            msg: Value::Null,
+            err: Value::Null,
+            payload: None,
+            encoding: Encoding::default(),
+        }
     }
 }
 
@@ -303,6 +637,49 @@ impl FrameReply {
 /// - The stream times out
 pub type ReplyStream<T> = Pin<Box<dyn Stream<Item = Result<T, SystemActorError>> + Send>>;
 
+/// A stream of messages received as a single request, sent with `ActorContext::send_stream`.
+///
+/// The stream ends once the sender's stream ends, and each item is subject to a timeout.
+pub type RequestStream<T> = Pin<Box<dyn Stream<Item = Result<T, SystemActorError>> + Send>>;
+
+/// Replies to a previously sent message, as returned by `ActorContext::replies_for`.
+pub struct MessageReplies<T> {
+    /// Whether the final reply had already been stored when the replies were fetched
+    pub finished: bool,
+    /// Stored replies followed by the ones still to come
+    pub stream: ReplyStream<T>,
+}
+
+/// Record pointing an idempotency key to the first message sent with it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct IdempotencyRecord {
+    message: RecordId,
+}
+
+/// A failed attempt at sending a message
+enum SendFailure {
+    /// The message couldn't be sent
+    Send(SystemActorError),
+    /// The first reply to the message was an error
+    Reply(SystemActorError),
+}
+
+impl SendFailure {
+    fn error(&self) -> &SystemActorError {
+        match self {
+            SendFailure::Send(e) | SendFailure::Reply(e) => e,
+        }
+    }
+
+    /// Hands the failure over the same way a send without retries would
+    fn into_replies<RT: MessageType + 'static>(self) -> Result<ReplyStream<RT>, SystemActorError> {
+        match self {
+            SendFailure::Send(e) => Err(e),
+            SendFailure::Reply(e) => Ok(Box::pin(futures::stream::once(future::ready(Err(e))))),
+        }
+    }
+}
+
 /// Type representing a stream of messages to an actor.
 ///
 /// A MessageStream provides an ordered sequence of incoming messages that can be
@@ -311,6 +688,9 @@ pub type ReplyStream<T> = Pin<Box<dyn Stream<Item = Result<T, SystemActorError>>
 /// - New messages as they arrive
 pub type MessageStream = Pin<Box<dyn Stream<Item = Result<FrameMessage, SystemActorError>> + Send>>;
 
+/// A stream of the states persisted by an actor, see `ActorContext::watch_state`.
+pub type StateStream<T> = Pin<Box<dyn Stream<Item = Result<T, SystemActorError>> + Send>>;
+
 /// A trait for types that can be sent as messages between actors.
 ///
 /// This trait is automatically implemented for types that meet the requirements:
@@ -440,22 +820,12 @@ where
             // Process message and store result
             let result = self.handle(ctx, message).await;
 
-            // If error, send error to client
-            if let Err(e) = &result {
-                ctx.error(e).await?;
-            }
-
-            // Ensure cleanup happens regardless of handle result
-            let cleanup_result = {
-                // Clean up reply stream first
-                ctx.finish_message_processing().await;
-
-                // Then wait for final reply to be sent
-                handle.await
-            };
+            // Send the error if any, then the final reply
+            ctx.complete_message_processing(handle, &result).await?;
 
-            if let Err(e) = cleanup_result {
-                error!("Error during reply cleanup: {}", e);
+            // Virtual actors persist their state after every message, so they can be passivated at any time
+            if ctx.passivation.is_some() {
+                self.save(ctx).await?;
             }
 
             result
@@ -463,15 +833,17 @@ where
     }
 
     /// Send a message to an actor without waiting for a reply
+    ///
+    /// Returns the message id, which can be passed to `ActorContext::replies_for` later on.
     fn do_send(
         &self,
         ctx: &mut ActorContext<Self>,
         message: MT,
         to: &ActorId,
-    ) -> impl Future<Output = Result<(), SystemActorError>> {
+    ) -> impl Future<Output = Result<RecordId, SystemActorError>> {
         async move {
-            let _ = ctx.prepare_and_send_message::<MT>(&message, to, None).await?;
-            Ok(())
+            let (request_id, _, _) = ctx.prepare_and_send_message::<MT>(&message, to, None).await?;
+            Ok(request_id)
         }
     }
 
@@ -486,9 +858,167 @@ where
     where
         Self::Response: 'static,
     {
+        async move { ctx.send_with_policy::<MT, Self::Response>(&message, to, options).await }
+    }
+}
+
+/// A message handler taking the actor's state as shared, so several messages can be handled at once
+///
+/// Suits actors whose handlers don't change their state, such as actors forwarding the work to a shared
+/// model. Implement it next to `Message`, delegating `Message::handle` to `handle_shared`, then handle
+/// the frames with `ActorContext::handle_concurrently` and spawn the actor with `SpawnOptions::concurrency`.
+///
+/// # Example
+///
+/// ```rust
+/// impl Message<RankTexts> for Rerank {
+///     type Response = RankedTexts;
+///
+///     async fn handle(&mut self, ctx: &mut ActorContext<Self>, message: &RankTexts) -> Result<(), RerankError> {
+///         self.handle_shared(ctx, message).await
+///     }
+/// }
+///
+/// impl SharedMessage<RankTexts> for Rerank {
+///     async fn handle_shared(&self, ctx: &mut ActorContext<Self>, message: &RankTexts) -> Result<(), RerankError> {
+///         let ranked = self.rank(message).await?;
+///         ctx.reply(ranked).await?;
+///         Ok(())
+///     }
+/// }
+///
+/// // In the actor's start loop
+/// let stream = ctx.recv().await?;
+/// let rerank = &*self;
+/// ctx.handle_concurrently(stream, |mut ctx, frame| async move {
+///     if let Some(message) = frame.is::<RankTexts>() {
+///         rerank.reply_shared(&mut ctx, &message, &frame).await?;
+///     }
+///     Ok(())
+/// })
+/// .await
+/// ```
+pub trait SharedMessage<MT>: Message<MT>
+where
+    MT: MessageType,
+{
+    /// Handles a message of type `MT` with shared access to the actor.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of this message. You can use `ctx.reply()` to send responses.
+    /// * `message` - A reference to the message of type `MT` to be handled.
+    fn handle_shared(
+        &self,
+        ctx: &mut ActorContext<Self>,
+        message: &MT,
+    ) -> impl Future<Output = Result<(), Self::Error>>;
+
+    /// Processes a message and manages the reply stream lifecycle.
+    ///
+    /// This is the `Message::reply` counterpart for handlers taking shared state.
+    fn reply_shared(
+        &self,
+        ctx: &mut ActorContext<Self>,
+        message: &MT,
+        frame: &FrameMessage,
+    ) -> impl Future<Output = Result<(), Self::Error>> {
+        async move {
+            // Set up reply stream first
+            let handle = ctx.start_message_processing(frame.clone()).await;
+
+            // Process message and store result
+            let result = self.handle_shared(ctx, message).await;
+
+            // Send the error if any, then the final reply
+            ctx.complete_message_processing(handle, &result).await?;
+
+            // Virtual actors persist their state after every message, so they can be passivated at any time
+            if ctx.passivation.is_some() {
+                self.save(ctx).await?;
+            }
+
+            result
+        }
+    }
+}
+
+/// A trait for actors that can handle streams of messages of a specific type.
+///
+/// The messages are sent with `ActorContext::send_stream`, and received as a single
+/// request: the handler gets a `RequestStream` of all the messages, and replies with
+/// `ctx.reply()` just like `Message::handle` does.
+///
+/// # Example
+///
+/// ```rust
+/// impl StreamMessage<AudioChunk> for Transcriber {
+///     type Response = Transcript;
+///
+///     async fn handle_stream(
+///         &mut self,
+///         ctx: &mut ActorContext<Self>,
+///         mut chunks: RequestStream<AudioChunk>,
+///     ) -> Result<(), Self::Error> {
+///         while let Some(chunk) = chunks.next().await {
+///             let transcript = self.transcribe(chunk?)?;
+///             ctx.reply(transcript).await?;
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// // In the actor's start loop
+/// if frame.is_stream::<AudioChunk>() {
+///     StreamMessage::<AudioChunk>::reply_stream(self, ctx, &frame).await?;
+/// }
+/// ```
+pub trait StreamMessage<MT>: Actor
+where
+    MT: MessageType + 'static,
+{
+    /// The type of response this stream handler produces.
+    type Response: Clone + Serialize + for<'de> Deserialize<'de> + Send + Sync + 'static;
+
+    /// Handles a stream of messages of type `MT` for this actor.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - A mutable reference to the actor's context. You can use `ctx.reply()` to send responses.
+    /// * `stream` - The messages, in the order they were sent.
+    fn handle_stream(
+        &mut self,
+        ctx: &mut ActorContext<Self>,
+        stream: RequestStream<MT>,
+    ) -> impl Future<Output = Result<(), Self::Error>>;
+
+    /// Processes a streamed request and manages the reply stream lifecycle.
+    ///
+    /// This is the `Message::reply` counterpart for frames where `FrameMessage::is_stream` is true.
+    fn reply_stream(
+        &mut self,
+        ctx: &mut ActorContext<Self>,
+        frame: &FrameMessage,
+    ) -> impl Future<Output = Result<(), Self::Error>> {
         async move {
-            let (_, reply_id, _) = ctx.prepare_and_send_message::<MT>(&message, to, Some(options.clone())).await?;
-            ctx.wait_for_replies::<Self::Response>(&reply_id, options).await
+            let options = SendOptions::builder().maybe_timeout(frame.stream_timeout.map(Into::into)).build();
+            let stream = ctx.request_stream::<MT>(frame, options).await?;
+
+            // Set up reply stream first
+            let handle = ctx.start_message_processing(frame.clone()).await;
+
+            // Process the stream and store result
+            let result = self.handle_stream(ctx, stream).await;
+
+            // Send the error if any, then the final reply
+            ctx.complete_message_processing(handle, &result).await?;
+
+            // Virtual actors persist their state after every message, so they can be passivated at any time
+            if ctx.passivation.is_some() {
+                self.save(ctx).await?;
+            }
+
+            result
         }
     }
 }
@@ -497,6 +1027,9 @@ where
 ///
 /// Controls aspects of message delivery and reply handling such as:
 /// - Timeout duration
+/// - Deduplication of retried requests
+/// - Retries and circuit breaking
+/// - Encoding of the message content
 ///
 /// # Example
 ///
@@ -521,6 +1054,20 @@ pub struct SendOptions {
     /// Whether to check actor health before sending messages
     #[builder(default = default_check_health())]
     pub check_health: bool,
+    /// Key identifying the request across retries
+    ///
+    /// A send with the same key to the same receiver as an earlier one doesn't enqueue a new message,
+    /// it returns the replies of the original message instead. Ignored by `send_stream`.
+    pub idempotency_key: Option<String>,
+    /// Policy for sending the message again when an attempt fails
+    pub retry: Option<RetryPolicy>,
+    /// Circuit breaker failing fast while the target keeps timing out
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
+    /// Encoding of the message and its replies, overriding the one set for the message type
+    pub encoding: Option<Encoding>,
+    /// Collects the ids of the messages sent with these options, so `send_each` can cancel them
+    #[builder(skip)]
+    pub(crate) sent: Option<Arc<std::sync::Mutex<Vec<RecordId>>>>,
 }
 
 fn default_timeout() -> std::time::Duration {
@@ -537,6 +1084,87 @@ impl Default for SendOptions {
     }
 }
 
+/// How `ActorContext::send_all` and `ActorContext::send_each` decide they have their answer.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum GatherStrategy {
+    /// Wait for every target to reply or fail
+    #[default]
+    All,
+    /// Stop at the first successful reply
+    FirstSuccess,
+    /// Stop once the given number of targets replied successfully
+    Quorum(usize),
+}
+
+impl GatherStrategy {
+    /// Number of successful replies needed out of `total` targets
+    fn required(&self, total: usize) -> usize {
+        match self {
+            GatherStrategy::All => total,
+            GatherStrategy::FirstSuccess => 1,
+            GatherStrategy::Quorum(quorum) => *quorum,
+        }
+    }
+}
+
+/// Configuration options for scatter-gather requests.
+///
+/// # Example
+///
+/// ```rust
+/// let options = GatherOptions::builder()
+///     .strategy(GatherStrategy::Quorum(2))
+///     .send(SendOptions::builder().timeout(Duration::from_secs(5)).build())
+///     .build();
+/// ```
+#[derive(bon::Builder, Clone, Default)]
+pub struct GatherOptions {
+    /// When to stop waiting for replies
+    #[builder(default)]
+    pub strategy: GatherStrategy,
+    /// Options for the request to each target, which times out on its own
+    #[builder(default)]
+    pub send: SendOptions,
+}
+
+/// The reply of one target of a scatter-gather request.
+#[derive(Debug)]
+pub struct TargetReply<T> {
+    /// The target actor
+    pub id: ActorId,
+    /// The reply, or None if it was no longer waited for once the strategy had its answer
+    pub reply: Option<Result<T, SystemActorError>>,
+}
+
+impl<T> TargetReply<T> {
+    /// Converts the reply into a result, with cancelled replies as `SystemActorError::ReplyCancelled`
+    pub fn into_result(self) -> Result<T, SystemActorError> {
+        match self.reply {
+            Some(reply) => reply,
+            None => Err(SystemActorError::ReplyCancelled(self.id)),
+        }
+    }
+}
+
+/// The replies to a scatter-gather request.
+#[derive(Debug)]
+pub struct GatherReplies<T> {
+    /// One reply per request, in the order the requests were given
+    pub replies: Vec<TargetReply<T>>,
+    /// Whether the strategy got the successful replies it needed
+    pub satisfied: bool,
+}
+
+impl<T> GatherReplies<T> {
+    /// The successful replies, along with their target
+    pub fn successes(&self) -> impl Iterator<Item = (&ActorId, &T)> {
+        self.replies.iter().filter_map(|target| match &target.reply {
+            Some(Ok(reply)) => Some((&target.id, reply)),
+            _ => None,
+        })
+    }
+}
+
 /// A unique identifier for an actor in the system.
 ///
 /// Actor IDs combine:
@@ -617,23 +1245,67 @@ impl ActorId {
     pub fn health_id(&self) -> RecordId {
         RecordId::from_table_key(DB_TABLE_HEALTH, self.name.as_ref())
     }
-}
 
-/// Options for configuring actor spawn behavior.
-///
-/// These options control how the system handles cases where an actor
-/// with the same ID already exists when spawning.
-///
-/// # Examples
-///
-/// ```rust
-/// // Error if actor exists
-/// let options = SpawnOptions::default();
-///
-/// // Reset existing actor
-/// let options = SpawnOptions::builder()
-///     .exists(SpawnExistsOptions::Reset)
-///     .build();
+    /// Creates an activation record ID for this actor
+    ///
+    /// The activation record holds what is needed to reactivate a passivated virtual actor.
+    pub fn activation_id(&self) -> RecordId {
+        RecordId::from_table_key(DB_TABLE_ACTIVATION, self.name.as_ref())
+    }
+
+    /// Creates a lease record ID for this actor
+    ///
+    /// The lease record tells which process owns the actor ID.
+    pub fn lease_id(&self) -> RecordId {
+        RecordId::from_table_key(DB_TABLE_LEASE, self.name.as_ref())
+    }
+
+    /// Creates an idempotency record ID for a key of a message sent to this actor
+    ///
+    /// The idempotency record points to the first message sent with the key.
+    pub fn idempotency_id(&self, key: &str) -> RecordId {
+        let mut obj = surrealdb::Object::new();
+        obj.insert(
+            "rx".to_string(),
+            surrealdb::Value::from_inner(sql::Value::Strand(sql::Strand::from(self.name.to_string()))),
+        );
+        obj.insert("key".to_string(), surrealdb::Value::from_inner(sql::Value::Strand(sql::Strand::from(key))));
+        RecordId::from_table_key(DB_TABLE_IDEMPOTENCY, RecordIdKey::from(obj))
+    }
+
+    /// Creates a hierarchy record ID for this actor
+    ///
+    /// The hierarchy record points to the parent of a child actor.
+    pub fn hierarchy_id(&self) -> RecordId {
+        RecordId::from_table_key(DB_TABLE_HIERARCHY, self.name.as_ref())
+    }
+
+    /// The ID of a child of this actor, namespaced under this actor's name
+    pub fn child<T: Actor>(&self, name: impl AsRef<str>) -> ActorId {
+        self.child_tagged(name, std::any::type_name::<T>())
+    }
+
+    /// The ID of a child of this actor with the given tag, for children spawned by tag
+    pub fn child_tagged(&self, name: impl AsRef<str>, tag: impl Into<Cow<'static, str>>) -> ActorId {
+        Self::with_tag(format!("{}/{}", self.name, name.as_ref()), tag)
+    }
+}
+
+/// Options for configuring actor spawn behavior.
+///
+/// These options control how the system handles cases where an actor
+/// with the same ID already exists when spawning.
+///
+/// # Examples
+///
+/// ```rust
+/// // Error if actor exists
+/// let options = SpawnOptions::default();
+///
+/// // Reset existing actor
+/// let options = SpawnOptions::builder()
+///     .exists(SpawnExistsOptions::Reset)
+///     .build();
 ///
 /// // Restore existing actor state
 /// let options = SpawnOptions::builder()
@@ -651,6 +1323,67 @@ pub struct SpawnOptions {
     /// Health configuration for the actor
     /// Some = enabled with config, None = disabled
     health_config: Option<HealthConfig>,
+    /// Passivation configuration for the actor
+    /// Some = virtual actor, stopped when idle and reactivated on demand, None = runs until stopped
+    passivation: Option<PassivationConfig>,
+    /// Lease configuration for the actor
+    /// Some = single owner across processes, None = any process can run the actor
+    lease: Option<LeaseConfig>,
+    /// Number of messages the actor handles at once with `ActorContext::handle_concurrently`
+    /// Some = up to N messages in flight, None = one at a time
+    concurrency: Option<usize>,
+    /// Rate limit on the messages the actor handles
+    /// Some = excess messages are delayed or rejected, None = no limit
+    rate_limit: Option<RateLimit>,
+    /// Parent of the actor, set by `ActorContext::spawn_child`
+    #[builder(skip)]
+    parent: Option<ParentLink>,
+}
+
+/// Link from a child actor to its parent
+#[derive(Clone, Debug)]
+struct ParentLink {
+    /// ID of the parent actor
+    id: ActorId,
+    /// Ends the child's message stream once the parent stops it or stops itself
+    stopped: watch::Receiver<bool>,
+}
+
+impl SpawnOptions {
+    /// Specifies how to handle an existing actor with the same ID
+    pub fn exists(&self) -> &SpawnExistsOptions {
+        &self.exists
+    }
+
+    /// Health configuration for the actor, if health monitoring is enabled
+    pub fn health_config(&self) -> Option<&HealthConfig> {
+        self.health_config.as_ref()
+    }
+
+    /// Passivation configuration for the actor, if it is a virtual actor
+    pub fn passivation(&self) -> Option<&PassivationConfig> {
+        self.passivation.as_ref()
+    }
+
+    /// Lease configuration for the actor, if it must have a single owner
+    pub fn lease(&self) -> Option<&LeaseConfig> {
+        self.lease.as_ref()
+    }
+
+    /// Number of messages the actor handles at once, if it handles messages concurrently
+    pub fn concurrency(&self) -> Option<usize> {
+        self.concurrency
+    }
+
+    /// Rate limit on the messages the actor handles, if any
+    pub fn rate_limit(&self) -> Option<&RateLimit> {
+        self.rate_limit.as_ref()
+    }
+
+    /// ID of the parent actor, for actors spawned with `ActorContext::spawn_child`
+    pub fn parent(&self) -> Option<&ActorId> {
+        self.parent.as_ref().map(|parent| &parent.id)
+    }
 }
 
 fn default_spawn_exists() -> SpawnExistsOptions {
@@ -766,10 +1499,17 @@ pub trait Actor: Sized + Serialize + for<'de> Deserialize<'de> + Debug + Send +
         options: SpawnOptions,
     ) -> impl Future<Output = Result<(ActorContext<Self>, Self), Self::Error>> {
         async move {
+            // Take ownership of the actor ID before touching its state
+            let lease = match &options.lease {
+                Some(config) => Some(Lease::acquire(&engine, &id, config).await?),
+                None => None,
+            };
+
             // Check if the actor already exists
             let actor_record: Option<ActorRecord> =
                 engine.db().lock().await.select(&id.record_id()).await.map_err(SystemActorError::from)?;
 
+            let mut restored: Option<Self> = None;
             if let Some(actor_record) = actor_record {
                 // Actor exists, apply options
                 match options.exists {
@@ -777,6 +1517,7 @@ pub trait Actor: Sized + Serialize + for<'de> Deserialize<'de> + Debug + Send +
                         // Reset the actor by deleting its record
                         let _: Option<ActorRecord> =
                             engine.db().lock().await.delete(&id.record_id()).await.map_err(SystemActorError::from)?;
+                        engine.delete_actor_records(&id).await?;
                         // We'll create a new record below
                     }
                     SpawnExistsOptions::Error => {
@@ -784,34 +1525,54 @@ pub trait Actor: Sized + Serialize + for<'de> Deserialize<'de> + Debug + Send +
                     }
                     SpawnExistsOptions::Restore => {
                         // Restore the actor by loading its state from the database
-                        let actor_state = actor_record.state;
-                        let actor: Self = serde_json::from_value(actor_state).map_err(SystemActorError::from)?;
-                        // Create and return the actor context with restored state
-                        let mut ctx = ActorContext::new(engine.clone(), id.clone());
-                        ctx.init_health(options.health_config.clone()).await?;
-                        return Ok((ctx, actor));
+                        let actor_state = engine.decrypt(actor_record.state, &id.record_id().to_string(), "state")?;
+                        restored = Some(serde_json::from_value(actor_state).map_err(SystemActorError::from)?);
                     }
                 }
             }
 
-            // At this point, we either need to create a new actor or reset an existing one
-
-            // Serialize actor properties
-            let actor_state = serde_json::to_value(&actor).map_err(SystemActorError::from)?;
+            let actor = match restored {
+                Some(actor) => actor,
+                None => {
+                    // At this point, we either need to create a new actor or reset an existing one
+
+                    // Serialize actor properties
+                    let actor_state = serde_json::to_value(&actor).map_err(SystemActorError::from)?;
+                    let actor_state = engine.encrypt(actor_state, &id.record_id().to_string(), "state")?;
+
+                    // Create or update actor record in the database
+                    let content = ActorRecord { id: id.record_id(), tag: id.tag.clone(), state: actor_state };
+                    let _record: Option<Record> = engine
+                        .db()
+                        .lock()
+                        .await
+                        .create(DB_TABLE_ACTOR)
+                        .content(content)
+                        .await
+                        .map_err(SystemActorError::from)?;
+                    actor
+                }
+            };
 
-            // Create or update actor record in the database
-            let content = ActorRecord { id: id.record_id(), tag: id.tag.clone(), state: actor_state };
-            let _record: Option<Record> = engine
-                .db()
-                .lock()
-                .await
-                .create(DB_TABLE_ACTOR)
-                .content(content)
-                .await
-                .map_err(SystemActorError::from)?;
+            // Record the parent of a child actor, once spawned and before its messages can be handled
+            if let Some(parent) = &options.parent {
+                let record = HierarchyRecord {
+                    id: id.hierarchy_id(),
+                    parent: parent.id.record_id(),
+                    name: id.name.clone(),
+                    tag: id.tag.clone(),
+                };
+                let _: Option<HierarchyRecord> =
+                    engine.db().lock().await.upsert(&id.hierarchy_id()).content(record).await?;
+            }
 
             // Create the context
             let mut ctx = ActorContext::new(engine.clone(), id.clone());
+            ctx.passivation = options.passivation.clone();
+            ctx.lease = lease;
+            ctx.concurrency = options.concurrency;
+            ctx.rate_limiter = options.rate_limit.clone().map(|config| Arc::new(RateLimiter::new(config)));
+            ctx.parent = options.parent.clone();
 
             // Initialize health monitoring with the provided config
             ctx.init_health(options.health_config.clone()).await?;
@@ -860,8 +1621,8 @@ pub trait Actor: Sized + Serialize + for<'de> Deserialize<'de> + Debug + Send +
         async move {
             // Serialize actor properties
             let actor_state = serde_json::to_value(self).map_err(SystemActorError::from)?;
-
             let record_id = ctx.id().record_id();
+            let actor_state = ctx.engine().encrypt(actor_state, &record_id.to_string(), "state")?;
 
             // Update actor record in the database
             let content = ActorRecord { id: record_id.clone(), tag: ctx.id().tag.clone(), state: actor_state };
@@ -897,8 +1658,9 @@ pub struct ActorRecord {
 ///
 /// Health monitoring works by:
 /// 1. Creating a health record when the actor is spawned (if enabled)
-/// 2. Periodically updating a timestamp in the health record
-/// 3. Checking the timestamp before sending messages to ensure the actor is healthy
+/// 2. Periodically updating a timestamp in the health record, along with the
+///    readiness reported by the actor's health probe (see `ActorContext::set_health_probe`)
+/// 3. Checking the timestamp and readiness before sending messages to ensure the actor is healthy
 ///
 /// # Example
 ///
@@ -923,25 +1685,69 @@ pub struct HealthConfig {
 
 /// Record for storing health status
 #[derive(Clone, Debug, Serialize, Deserialize)]
-struct HealthRecord {
-    id: RecordId,
+pub(crate) struct HealthRecord {
+    pub(crate) id: RecordId,
+    #[serde(default)]
+    name: Cow<'static, str>,
+    #[serde(default)]
+    tag: Cow<'static, str>,
     last_seen: sql::Datetime,
     enabled: bool,
     update_interval: sql::Duration,
+    #[serde(default = "default_ready")]
+    ready: bool,
+    #[serde(default)]
+    reason: Option<String>,
+    #[serde(default)]
+    rate_limit: Option<RateLimitStatus>,
+}
+
+fn default_ready() -> bool {
+    true
 }
 
 impl HealthRecord {
-    fn new(id: RecordId, config: Option<&HealthConfig>) -> Self {
-        match config {
-            Some(config) => {
-                Self { id, last_seen: sql::Datetime::default(), enabled: true, update_interval: config.update_interval }
-            }
-            None => Self {
-                id,
-                last_seen: sql::Datetime::default(),
-                enabled: false,
-                update_interval: sql::Duration::from_secs(60), // default value not used
-            },
+    fn new(actor_id: &ActorId, config: Option<&HealthConfig>) -> Self {
+        let (enabled, update_interval) = match config {
+            Some(config) => (true, config.update_interval),
+            None => (false, sql::Duration::from_secs(60)), // default value not used
+        };
+        Self {
+            id: actor_id.health_id(),
+            name: actor_id.name.clone(),
+            tag: actor_id.tag.clone(),
+            last_seen: sql::Datetime::default(),
+            enabled,
+            update_interval,
+            ready: true,
+            reason: None,
+            rate_limit: None,
+        }
+    }
+
+    /// Evaluates the actor's health from this record
+    ///
+    /// An actor is live if health monitoring is disabled, or if it has updated its
+    /// health record within its configured update interval, plus a grace period.
+    pub(crate) fn health(&self) -> ActorHealth {
+        let live = if self.enabled {
+            let elapsed = SystemTime::now().duration_since(SystemTime::from(self.last_seen.0)).unwrap_or(Duration::MAX);
+            let update_interval: std::time::Duration = self.update_interval.into();
+            // Cap grace period at 1 second
+            let grace_period = std::cmp::min(update_interval / 10, Duration::from_secs(1));
+            elapsed <= update_interval + grace_period
+        } else {
+            true
+        };
+
+        ActorHealth {
+            id: ActorId::with_tag(self.name.clone(), self.tag.clone()),
+            enabled: self.enabled,
+            live,
+            ready: self.ready,
+            reason: self.reason.clone(),
+            last_seen: self.last_seen,
+            rate_limit: self.rate_limit.clone(),
         }
     }
 }
@@ -949,201 +1755,1096 @@ impl HealthRecord {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 struct UpdateHealth {
     last_seen: sql::Datetime,
+    ready: bool,
+    reason: Option<String>,
+    rate_limit: Option<RateLimitStatus>,
 }
 
-/// The context for an actor, providing access to the actor system.
-///
-/// The context allows an actor to:
-/// - Send and receive messages
-/// - Stream replies to messages
-/// - Access the underlying database
-/// - Manage actor lifecycle
-///
-/// Each actor instance has its own context that is created when the
-/// actor is spawned.
-#[derive(Debug)]
-pub struct ActorContext<T: Actor> {
-    /// The actor system engine
-    engine: Engine,
-    /// The actor's unique identifier
-    id: ActorId,
-    /// Channel for sending reply chunks during message processing
-    tx: Option<mpsc::UnboundedSender<Result<Value, Value>>>,
-    /// Handle to health update task
-    health_task: Option<tokio::task::JoinHandle<()>>,
-    /// Type marker for the actor
-    _marker: std::marker::PhantomData<T>,
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct UpdateReadiness {
+    ready: bool,
+    reason: Option<String>,
 }
 
-impl<T: Actor> Drop for ActorContext<T> {
-    fn drop(&mut self) {
-        if let Some(handle) = self.health_task.take() {
-            handle.abort();
+impl From<Readiness> for UpdateReadiness {
+    fn from(readiness: Readiness) -> Self {
+        match readiness {
+            Readiness::Ready => Self { ready: true, reason: None },
+            Readiness::NotReady(reason) => Self { ready: false, reason: Some(reason) },
         }
     }
 }
 
-impl<T: Actor> ActorContext<T> {
-    /// Create a new actor context
-    fn new(engine: Engine, id: ActorId) -> Self {
-        debug!("[{}] ctx-new", id.record_id());
-        Self { engine, id, tx: None, health_task: None, _marker: std::marker::PhantomData }
-    }
+/// Whether an actor is ready to handle messages, as reported by its health probe
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum Readiness {
+    /// The actor can handle messages
+    Ready,
+    /// The actor is running but can't handle messages, for the given reason
+    NotReady(String),
+}
 
-    async fn unreplied_messages(&self) -> Result<Vec<FrameMessage>, SystemActorError> {
-        let query = include_str!("../sql/unreplied_messages.surql");
-        let mut res = self.engine().db().lock().await.query(query).bind(("rx", self.id.record_id())).await?;
-        let existing_messages: Vec<FrameMessage> = res.take(0)?;
-        trace!("unreplied_messages: {:?}", existing_messages);
-        Ok(existing_messages)
-    }
+/// A health probe provided by an actor to report its readiness
+///
+/// The probe is evaluated when it is set and on every health update.
+#[derive(Clone)]
+pub struct HealthProbe(Arc<dyn Fn() -> Readiness + Send + Sync>);
 
-    /// Get the actor id
-    pub fn id(&self) -> &ActorId {
-        &self.id
+impl HealthProbe {
+    pub fn new(probe: impl Fn() -> Readiness + Send + Sync + 'static) -> Self {
+        Self(Arc::new(probe))
     }
 
-    /// Get the engine
-    pub fn engine(&self) -> &Engine {
-        &self.engine
+    pub fn readiness(&self) -> Readiness {
+        (self.0)()
     }
+}
 
-    /// Check the health of the actor
-    pub async fn health(&self) -> bool {
-        // Check if the actor is still in the database
-        let record: Result<Option<ActorRecord>, SystemActorError> =
-            self.engine().db().lock().await.select(&self.id.record_id()).await.map_err(SystemActorError::from);
-        if let Ok(Some(_)) = record {
-            true
-        } else {
-            false
-        }
+impl Debug for HealthProbe {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "HealthProbe")
     }
+}
 
-    /// Initialize health monitoring and start periodic health updates for this actor
-    ///
-    /// This function:
-    /// 1. Creates initial health record if health monitoring is enabled
-    /// 2. Establishes relation between actor and health record
-    /// 3. Starts periodic health update task if enabled
-    ///
-    /// # Arguments
-    ///
-    /// * `config` - Optional health monitoring configuration
-    ///
-    /// # Returns
-    ///
-    /// * `Ok(())` if initialization and startup succeed
-    /// * `Err(SystemActorError)` if any step fails
-    pub async fn init_health(&mut self, config: Option<HealthConfig>) -> Result<(), SystemActorError> {
-        debug!(
-            "[{}] health-init enabled={} interval={:?}",
-            self.id().name(),
-            config.is_some(),
-            config.as_ref().map(|c| c.update_interval)
-        );
+/// Health of an actor, evaluated from its health record
+///
+/// Liveness and readiness are tracked separately:
+/// - `live` tells whether the actor is running, based on its last health update
+/// - `ready` tells whether it can handle messages, as reported by its health probe
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct ActorHealth {
+    /// The actor this health refers to
+    pub id: ActorId,
+    /// Whether health monitoring is enabled for the actor
+    pub enabled: bool,
+    /// Whether the actor is running
+    pub live: bool,
+    /// Whether the actor is ready to handle messages
+    pub ready: bool,
+    /// Why the actor is not ready, if it isn't
+    pub reason: Option<String>,
+    /// Last time the actor updated its health record
+    pub last_seen: sql::Datetime,
+    /// State of the actor's rate limit, as of its last health update
+    #[serde(default)]
+    pub rate_limit: Option<RateLimitStatus>,
+}
 
-        let health_id = self.id().health_id();
-        let health_record = HealthRecord::new(health_id.clone(), config.as_ref());
+impl ActorHealth {
+    /// An actor is healthy when it's both live and ready
+    pub fn is_healthy(&self) -> bool {
+        self.live && self.ready
+    }
+}
 
-        // Explicitly specify Record type for upsert operation
-        let _: Option<HealthRecord> = self
-            .engine()
-            .db()
-            .lock()
-            .await
-            .upsert(&health_id)
-            .content(health_record.clone())
-            .await
-            .map_err(SystemActorError::from)?;
+/// Configuration for virtual actors
+///
+/// A virtual actor is stopped after going without messages for `idle_timeout`,
+/// and reactivated when a message is sent to its ID.
+///
+/// Reactivation goes through the `ActorTagRegistry` factory for the actor's tag, with
+/// `SpawnExistsOptions::Restore`, so virtual actors must be spawned through the registry
+/// and their factory must be registered in every engine that sends them messages.
+/// Their state is saved after every handled message.
+///
+/// # Example
+///
+/// ```rust
+/// // Passivate the actor after 5 minutes without messages
+/// let options = SpawnOptions::builder()
+///     .passivation(PassivationConfig::builder().idle_timeout(sql::Duration::from_secs(300)).build())
+///     .build();
+///
+/// let handle = engine.registry().spawn(tag, engine.clone(), config, id, options).await?;
+/// ```
+#[derive(bon::Builder, Clone, Debug, Serialize, Deserialize)]
+pub struct PassivationConfig {
+    /// How long the actor can go without messages before it is passivated
+    #[builder(default = sql::Duration::from_secs(300))]
+    pub idle_timeout: sql::Duration,
+}
 
-        // Start health update task if enabled
-        if let Some(config) = config {
-            let engine = self.engine().clone();
-            let update_interval = config.update_interval;
-            let health_id = health_id.clone();
-            let actor_name = self.id().name().to_string();
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct UpdateActivation {
+    active: bool,
+}
 
-            let handle = tokio::spawn(async move {
-                loop {
-                    tokio::time::sleep(update_interval.into()).await;
+/// Marks a virtual actor as passivated
+pub(crate) async fn deactivate(engine: &Engine, id: &ActorId) -> Result<(), SystemActorError> {
+    let _: Option<ActivationRecord> =
+        engine.db().lock().await.update(&id.activation_id()).merge(UpdateActivation { active: false }).await?;
+    Ok(())
+}
 
-                    let update = UpdateHealth { last_seen: sql::Datetime::default() };
+/// Record for reactivating a passivated virtual actor
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct ActivationRecord {
+    pub(crate) id: RecordId,
+    /// Tag of the factory that spawns the actor
+    pub(crate) tag: Cow<'static, str>,
+    /// Config the factory was given
+    #[serde(default)]
+    pub(crate) config: Value,
+    pub(crate) health_config: Option<HealthConfig>,
+    pub(crate) passivation: PassivationConfig,
+    #[serde(default)]
+    pub(crate) lease: Option<LeaseConfig>,
+    #[serde(default)]
+    pub(crate) concurrency: Option<usize>,
+    #[serde(default)]
+    pub(crate) rate_limit: Option<RateLimit>,
+    /// Whether the actor is currently running
+    pub(crate) active: bool,
+}
 
-                    if let Err(e) =
-                        engine.db().lock().await.update::<Option<HealthRecord>>(&health_id).merge(update).await
-                    {
-                        error!("[{}] health-update-error: {}", actor_name, e);
-                        break;
-                    }
-                }
-            });
+/// How long a target found not to be a virtual actor is trusted to stay one
+///
+/// Bounds how long a send can miss an actor spawned as virtual by another process under the same ID.
+const NOT_VIRTUAL_TTL: Duration = Duration::from_secs(10);
 
-            self.health_task = Some(handle);
-        }
+/// Which targets of the sends in a process are virtual actors
+///
+/// Lets sends skip the activation update for actors that can't be passivated.
+/// Targets found not to be virtual actors are dropped once expired, virtual actors when they're killed.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct VirtualActors {
+    targets: Arc<std::sync::Mutex<HashMap<ActorId, Option<Instant>>>>,
+}
 
-        Ok(())
+impl VirtualActors {
+    /// Whether a target is known not to be a virtual actor
+    fn is_not_virtual(&self, id: &ActorId) -> bool {
+        let targets = self.targets.lock().unwrap_or_else(|e| e.into_inner());
+        matches!(targets.get(id), Some(Some(checked)) if checked.elapsed() < NOT_VIRTUAL_TTL)
     }
 
-    /// Check if an actor is healthy based on its health record
-    ///
-    /// An actor is considered healthy if either:
-    /// - Health monitoring is disabled for the actor
-    /// - The actor has updated its health status within its configured update interval
-    ///
-    /// # Arguments
-    ///
-    /// * `actor_id` - ID of the actor to check
-    ///
-    /// # Returns
-    ///
-    /// * `Ok(true)` if the actor is healthy
-    /// * `Ok(false)` if the actor is unhealthy or not found
-    /// * `Err(SystemActorError)` if checking health status fails
-    pub async fn check_actor_health(&self, actor_id: &ActorId) -> Result<bool, SystemActorError> {
-        let health_id = actor_id.health_id();
-
-        let health: Option<HealthRecord> =
-            self.engine().db().lock().await.select(&health_id).await.map_err(SystemActorError::from)?;
-
-        if let Some(health) = health {
-            if !health.enabled {
-                debug!("[{}] health-check {} disabled=true", self.id().name(), actor_id.name());
-                return Ok(true);
-            }
+    /// Records whether a target is a virtual actor
+    pub(crate) fn set(&self, id: &ActorId, is_virtual: bool) {
+        let mut targets = self.targets.lock().unwrap_or_else(|e| e.into_inner());
+        targets.retain(|_, checked| checked.is_none_or(|checked| checked.elapsed() < NOT_VIRTUAL_TTL));
+        targets.insert(id.clone(), if is_virtual { None } else { Some(Instant::now()) });
+    }
 
-            let elapsed =
-                SystemTime::now().duration_since(SystemTime::from(health.last_seen.0)).unwrap_or(Duration::MAX);
+    /// Forgets a target, so the next send checks it again
+    pub(crate) fn forget(&self, id: &ActorId) {
+        self.targets.lock().unwrap_or_else(|e| e.into_inner()).remove(id);
+    }
+}
 
-            let update_interval: std::time::Duration = health.update_interval.into();
-            // Cap grace period at 1 second
-            let grace_period = std::cmp::min(update_interval / 10, Duration::from_secs(1));
-            let is_healthy = elapsed <= update_interval + grace_period;
+/// Record linking a child actor to its parent
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct HierarchyRecord {
+    pub(crate) id: RecordId,
+    pub(crate) parent: RecordId,
+    /// Name and tag of the child, to list children that haven't stored their state yet
+    pub(crate) name: Cow<'static, str>,
+    pub(crate) tag: Cow<'static, str>,
+}
 
-            debug!(
-                "[{}] health-check {} elapsed={:?} interval={:?} grace={:?} healthy={}",
-                self.id().name(),
+/// Configuration for single ownership of an actor ID across processes
+///
+/// The process that spawns the actor takes a lease on its ID, and renews it every third
+/// of `ttl` while the actor's context is alive. Another process spawning the same ID
+/// fails or waits, depending on `conflict`, until the lease is released or expires.
+/// An owner that fails to renew its lease in time loses it, and its `recv` stream ends.
+///
+/// # Example
+///
+/// ```rust
+/// // Run as a standby replica, taking over once the current owner is gone
+/// let lease = LeaseConfig::builder().conflict(LeaseConflict::Wait).build();
+/// let options = SpawnOptions::builder().exists(SpawnExistsOptions::Restore).lease(lease).build();
+///
+/// let (ctx, actor) = MyActor::spawn(engine, id, actor, options).await?;
+/// ```
+#[derive(bon::Builder, Clone, Debug, Serialize, Deserialize)]
+pub struct LeaseConfig {
+    /// How long the lease lasts without being renewed
+    #[builder(default = sql::Duration::from_secs(15))]
+    pub ttl: sql::Duration,
+    /// What to do when another owner holds the lease
+    #[builder(default)]
+    #[serde(default)]
+    pub conflict: LeaseConflict,
+}
+
+/// What to do when spawning an actor whose lease is held by another owner
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub enum LeaseConflict {
+    /// Fail with `SystemActorError::LeaseHeld`
+    #[default]
+    Error,
+    /// Wait until the lease is released or expires
+    Wait,
+}
+
+/// Replies to a message with an error, followed by the final reply
+pub(crate) fn reply_error(
+    engine: Engine,
+    frame: FrameMessage,
+    error: &SystemActorError,
+) -> tokio::task::JoinHandle<()> {
+    let (tx, writer) = spawn_reply_writer(engine, frame);
+    let _ = tx.send(Err(ErrorReply::new(error).to_value()));
+    writer
+}
+
+/// Reads the state of an actor record
+fn read_state<T>(engine: &Engine, record: ActorRecord) -> Result<T, SystemActorError>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    Ok(serde_json::from_value(engine.decrypt(record.state, &record.id.to_string(), "state")?)?)
+}
+
+/// Deletes the records of an actor
+pub(crate) async fn kill_actor(engine: &Engine, id: &ActorId) -> Result<(), SystemActorError> {
+    // Children don't outlive their parent
+    for child in engine.children(id).await? {
+        Box::pin(kill_actor(engine, &child)).await?;
+    }
+    // Payloads of the messages it received and of its replies are no longer needed
+    let mut res = engine
+        .db()
+        .lock()
+        .await
+        .query("SELECT VALUE payload FROM message, message_chunk WHERE rx = $id AND payload != NONE")
+        .query("SELECT VALUE payload FROM reply WHERE tx = $id AND payload != NONE")
+        .query("UPDATE message, message_chunk SET payload = NONE WHERE rx = $id AND payload != NONE")
+        .query("UPDATE reply SET payload = NONE WHERE tx = $id AND payload != NONE")
+        .bind(("id", id.record_id()))
+        .await?;
+    let mut paths: Vec<Cow<'static, str>> = res.take(0)?;
+    paths.extend(res.take::<Vec<Cow<'static, str>>>(1)?);
+    res.check()?;
+    engine.delete_payloads(paths).await?;
+    engine.delete_actor_records(id).await?;
+    let _: Option<ActorRecord> =
+        engine.db().lock().await.delete(&id.record_id()).await.map_err(SystemActorError::from)?;
+    engine.virtual_actors().forget(id);
+    engine.circuit_breakers().forget(id);
+    // A killed virtual actor must not be reactivated
+    let _: Option<ActivationRecord> =
+        engine.db().lock().await.delete(&id.activation_id()).await.map_err(SystemActorError::from)?;
+    let _: Option<HealthRecord> =
+        engine.db().lock().await.delete(&id.health_id()).await.map_err(SystemActorError::from)?;
+    let _: Option<LeaseRecord> =
+        engine.db().lock().await.delete(&id.lease_id()).await.map_err(SystemActorError::from)?;
+    let _: Option<HierarchyRecord> =
+        engine.db().lock().await.delete(&id.hierarchy_id()).await.map_err(SystemActorError::from)?;
+    Ok(())
+}
+
+/// Spawns the task storing the replies to a message
+///
+/// Replies sent on the returned channel are stored as chunks, and the final reply is stored once the channel is closed.
+fn spawn_reply_writer(
+    engine: Engine,
+    frame: FrameMessage,
+) -> (mpsc::UnboundedSender<Result<Value, Value>>, tokio::task::JoinHandle<()>) {
+    // Create an unbounded channel for streaming replies
+    let (tx, mut rx) = mpsc::unbounded_channel::<Result<Value, Value>>();
+
+    let db = engine.db().clone();
+
+    // Load SQL query template for reply insertion
+    let reply_query = include_str!("../sql/reply.surql");
+
+    // Spawn async task to handle reply processing
+    let handle = tokio::spawn(async move {
+        // Counter for tracking reply chunks in stream
+        let chunk_counter = AtomicU64::new(1);
+
+        while let Some(result) = rx.recv().await {
+            let chunk = chunk_counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            debug!("[{}] msg-chunk {} {}-{}", frame.rx, frame.name, frame.id.key(), chunk);
+
+            // Create reply frame based on Ok/Err
+            let reply = match result {
+                Ok(msg) => {
+                    // Move large replies to the object store
+                    let path = payload_path(DB_TABLE_REPLY, format!("{}-{}", frame.id.key(), chunk));
+                    match engine.offload_payload(path, msg).await {
+                        Ok((msg, payload)) => {
+                            let mut reply = FrameReply::new_chunk(
+                                frame.id.key().to_string(),
+                                chunk,
+                                frame.name.clone(),
+                                frame.rx.clone(),
+                                frame.tx.clone(),
+                                msg,
+                            );
+                            reply.payload = payload;
+                            reply.encoding = frame.encoding;
+                            reply
+                        }
+                        Err(e) => {
+                            error!("[{}] msg-chunk-offload-error {} {}", frame.rx, frame.name, e);
+                            FrameReply::new_chunk_error(
+                                frame.id.key().to_string(),
+                                chunk,
+                                frame.name.clone(),
+                                frame.rx.clone(),
+                                frame.tx.clone(),
+                                ErrorReply::new(&e).to_value(),
+                            )
+                        }
+                    }
+                }
+                Err(err) => FrameReply::new_chunk_error(
+                    frame.id.key().to_string(),
+                    chunk,
+                    frame.name.clone(),
+                    frame.rx.clone(),
+                    frame.tx.clone(),
+                    err,
+                ),
+            };
+
+            // Encrypt the reply at rest
+            let reply = reply.encrypt_or_error(&engine, DB_TABLE_REPLY);
+
+            // Get database ID for reply
+            let reply_id = reply.id.to_record_id();
+
+            // Insert reply chunk into database
+            // This query likely creates a new reply record and links it to original message
+            let result = db
+                .lock()
+                .await
+                .query(reply_query)
+                .bind(("reply_id", reply_id))
+                .bind(("reply", reply.stored()))
+                .bind(("msg_id", frame.id.clone()))
+                .await;
+
+            // Log any errors during reply insertion
+            if let Err(e) = result {
+                error!("[{}] msg-chunk-error {} {}-{} {}", frame.rx, frame.name, frame.id.key(), chunk, e);
+            }
+        }
+
+        // After channel closes (all replies sent), send final reply
+        // Store values needed for logging
+        let rx = frame.rx.clone();
+        let name = frame.name.clone();
+        let id_key = frame.id.key().to_string();
+
+        debug!("[{}] msg-final {} {}", rx, name, id_key);
+
+        // Create final reply frame (chunk = None indicates end of stream)
+        let reply = FrameReply::new_final(id_key.clone(), frame.name, frame.rx, frame.tx);
+        let reply_id = reply.id.to_record_id();
+
+        // Insert final reply into database
+        // Uses same query template but marks this as final reply
+        if let Err(e) = db
+            .lock()
+            .await
+            .query(reply_query)
+            .bind(("reply_id", reply_id))
+            .bind(("reply", reply.stored()))
+            .bind(("msg_id", frame.id.clone()))
+            .await
+        {
+            error!("[{}] msg-final-error {} {} {}", rx, name, id_key, e);
+        }
+
+        // The content of a handled message isn't needed anymore, `forward` hands it over to the forwarded message
+        if let Some(path) = frame.payload {
+            if let Err(e) = clear_payload(&engine, frame.id, path).await {
+                error!("[{}] msg-final-error {} {} {}", rx, name, id_key, e);
+            }
+        }
+
+        // Nor are the messages of a streamed request
+        if frame.stream {
+            if let Err(e) = delete_request_stream(&engine, &id_key).await {
+                error!("[{}] msg-final-error {} {} {}", rx, name, id_key, e);
+            }
+        }
+    });
+
+    (tx, handle)
+}
+
+/// Records the id of a message sent with options collecting them, see `SendOptions::sent`
+fn record_sent(options: Option<&SendOptions>, id: &RecordId) {
+    if let Some(sent) = options.and_then(|options| options.sent.as_ref()) {
+        sent.lock().unwrap_or_else(|e| e.into_inner()).push(id.clone());
+    }
+}
+
+/// Deletes the payload of a record from the object store, and the record's reference to it
+async fn clear_payload(engine: &Engine, id: RecordId, path: Cow<'static, str>) -> Result<(), SystemActorError> {
+    engine.db().lock().await.query("UPDATE $id SET payload = NONE").bind(("id", id)).await?.check()?;
+    engine.delete_payloads([path]).await
+}
+
+/// Deletes the messages of a streamed request, and their payloads
+async fn delete_request_stream(engine: &Engine, id_key: &str) -> Result<(), SystemActorError> {
+    let query = format!(
+        "SELECT VALUE payload FROM {table} WHERE id.id = $id AND payload != NONE; DELETE {table} WHERE id.id = $id;",
+        table = DB_TABLE_MESSAGE_CHUNK
+    );
+    let mut res = engine.db().lock().await.query(query).bind(("id", id_key.to_string())).await?;
+    let paths: Vec<Cow<'static, str>> = res.take(0)?;
+    res.check()?;
+    engine.delete_payloads(paths).await
+}
+
+/// Record for the owner of an actor ID
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct LeaseRecord {
+    id: RecordId,
+    owner: String,
+    expires_at: sql::Datetime,
+}
+
+/// A lease on an actor ID held by this process
+///
+/// The lease is renewed in the background and released when dropped.
+#[derive(Debug)]
+struct Lease {
+    engine: Engine,
+    id: RecordId,
+    owner: String,
+    /// Set to true if the lease is lost to another owner
+    lost: watch::Receiver<bool>,
+    renew_task: tokio::task::JoinHandle<()>,
+}
+
+impl Lease {
+    /// Acquires the lease of an actor ID, renewing it until dropped
+    async fn acquire(engine: &Engine, actor_id: &ActorId, config: &LeaseConfig) -> Result<Self, SystemActorError> {
+        let id = actor_id.lease_id();
+        let owner = Id::ulid().to_string();
+        let renew_interval = Duration::from(config.ttl) / 3;
+
+        while !Self::try_acquire(engine, &id, &owner, config.ttl).await? {
+            match config.conflict {
+                LeaseConflict::Error => return Err(SystemActorError::LeaseHeld(actor_id.clone())),
+                LeaseConflict::Wait => {
+                    debug!("[{}] lease-wait", actor_id.record_id());
+                    tokio::time::sleep(renew_interval).await;
+                }
+            }
+        }
+        debug!("[{}] lease-acquire {}", actor_id.record_id(), owner);
+
+        let (lost_tx, lost) = watch::channel(false);
+        let renew_task = {
+            let engine = engine.clone();
+            let id = id.clone();
+            let owner = owner.clone();
+            let ttl = config.ttl;
+            tokio::spawn(async move {
+                loop {
+                    tokio::time::sleep(renew_interval).await;
+                    match Self::try_acquire(&engine, &id, &owner, ttl).await {
+                        Ok(true) => {}
+                        Ok(false) => {
+                            error!("[{}] lease-lost {}", id, owner);
+                            let _ = lost_tx.send(true);
+                            break;
+                        }
+                        // Keep trying until the lease expires
+                        Err(e) => error!("[{}] lease-renew-error {}", id, e),
+                    }
+                }
+            })
+        };
+
+        Ok(Self { engine: engine.clone(), id, owner, lost, renew_task })
+    }
+
+    /// Acquires or renews a lease, returning false if another owner holds it
+    async fn try_acquire(
+        engine: &Engine,
+        id: &RecordId,
+        owner: &str,
+        ttl: sql::Duration,
+    ) -> Result<bool, SystemActorError> {
+        let query = include_str!("../sql/lease.surql");
+        let mut res = engine
+            .db()
+            .lock()
+            .await
+            .query(query)
+            .bind(("lease_id", id.clone()))
+            .bind(("owner", owner.to_string()))
+            .bind(("ttl", ttl))
+            .await?;
+        let leases: Vec<LeaseRecord> = res.take(0)?;
+        Ok(!leases.is_empty())
+    }
+}
+
+impl Drop for Lease {
+    fn drop(&mut self) {
+        self.renew_task.abort();
+
+        // Release the lease right away, so another process can take over without waiting for it to expire
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let engine = self.engine.clone();
+        let id = self.id.clone();
+        let owner = self.owner.clone();
+        runtime.spawn(async move {
+            debug!("[{}] lease-release {}", id, owner);
+            let query = "DELETE $lease_id WHERE owner = $owner";
+            if let Err(e) =
+                engine.db().lock().await.query(query).bind(("lease_id", id.clone())).bind(("owner", owner)).await
+            {
+                error!("[{}] lease-release-error {}", id, e);
+            }
+        });
+    }
+}
+
+/// The context for an actor, providing access to the actor system.
+///
+/// The context allows an actor to:
+/// - Send and receive messages
+/// - Stream replies to messages
+/// - Access the underlying database
+/// - Manage actor lifecycle
+///
+/// Each actor instance has its own context that is created when the
+/// actor is spawned.
+#[derive(Debug)]
+pub struct ActorContext<T: Actor> {
+    /// The actor system engine
+    engine: Engine,
+    /// The actor's unique identifier
+    id: ActorId,
+    /// Channel for sending reply chunks during message processing
+    tx: Option<mpsc::UnboundedSender<Result<Value, Value>>>,
+    /// Encoding of the replies to the message being processed
+    encoding: Encoding,
+    /// Handle to health update task
+    health_task: Option<tokio::task::JoinHandle<()>>,
+    /// Health probe reporting the actor's readiness, shared with the health update task
+    health_probe: Arc<std::sync::RwLock<Option<HealthProbe>>>,
+    /// Passivation configuration, for virtual actors
+    passivation: Option<PassivationConfig>,
+    /// Lease on the actor ID, for actors with a single owner across processes
+    lease: Option<Lease>,
+    /// Number of messages handled at once by `handle_concurrently`
+    concurrency: Option<usize>,
+    /// Rate limit on incoming messages, shared with the health update task
+    rate_limiter: Option<Arc<RateLimiter>>,
+    /// Parent of the actor, for child actors
+    parent: Option<ParentLink>,
+    /// Children spawned by the actor, with the signal that stops each of them
+    children: Vec<(ActorId, watch::Sender<bool>)>,
+    /// Whether the context handles a single message alongside others, see `handle_concurrently`
+    shared: bool,
+    /// Type marker for the actor
+    _marker: std::marker::PhantomData<T>,
+}
+
+impl<T: Actor> Drop for ActorContext<T> {
+    fn drop(&mut self) {
+        let Some(handle) = self.health_task.take() else {
+            return;
+        };
+        handle.abort();
+
+        // Stop reporting the actor as down, leaving alone a record already written by a respawned actor
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let engine = self.engine.clone();
+        let health_id = self.id.health_id();
+        let stopped = sql::Datetime::default();
+        runtime.spawn(async move {
+            debug!("[{}] health-delete", health_id);
+            let query = "DELETE $id WHERE last_seen <= $stopped";
+            if let Err(e) =
+                engine.db().lock().await.query(query).bind(("id", health_id.clone())).bind(("stopped", stopped)).await
+            {
+                error!("[{}] health-delete-error {}", health_id, e);
+            }
+        });
+    }
+}
+
+impl<T: Actor> ActorContext<T> {
+    /// Create a new actor context
+    fn new(engine: Engine, id: ActorId) -> Self {
+        debug!("[{}] ctx-new", id.record_id());
+        Self {
+            engine,
+            id,
+            tx: None,
+            encoding: Encoding::default(),
+            health_task: None,
+            health_probe: Arc::new(std::sync::RwLock::new(None)),
+            passivation: None,
+            lease: None,
+            concurrency: None,
+            rate_limiter: None,
+            parent: None,
+            children: Vec::new(),
+            shared: false,
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    async fn unreplied_messages(&self) -> Result<Vec<FrameMessage>, SystemActorError> {
+        let existing_messages = self.engine().pending_messages(&self.id).await?;
+        trace!("unreplied_messages: {:?}", existing_messages);
+        Ok(existing_messages)
+    }
+
+    /// Get the actor id
+    pub fn id(&self) -> &ActorId {
+        &self.id
+    }
+
+    /// Get the engine
+    pub fn engine(&self) -> &Engine {
+        &self.engine
+    }
+
+    /// Check the health of the actor
+    pub async fn health(&self) -> bool {
+        // Check if the actor is still in the database
+        let record: Result<Option<ActorRecord>, SystemActorError> =
+            self.engine().db().lock().await.select(&self.id.record_id()).await.map_err(SystemActorError::from);
+        if let Ok(Some(_)) = record {
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Initialize health monitoring and start periodic health updates for this actor
+    ///
+    /// This function:
+    /// 1. Creates initial health record if health monitoring is enabled
+    /// 2. Establishes relation between actor and health record
+    /// 3. Starts periodic health update task if enabled
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Optional health monitoring configuration
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if initialization and startup succeed
+    /// * `Err(SystemActorError)` if any step fails
+    pub async fn init_health(&mut self, config: Option<HealthConfig>) -> Result<(), SystemActorError> {
+        debug!(
+            "[{}] health-init enabled={} interval={:?}",
+            self.id().name(),
+            config.is_some(),
+            config.as_ref().map(|c| c.update_interval)
+        );
+
+        let health_id = self.id().health_id();
+        let mut health_record = HealthRecord::new(self.id(), config.as_ref());
+
+        // Keep reporting the readiness of an already set probe
+        if let Some(probe) = self.health_probe.read().unwrap().as_ref() {
+            let readiness = UpdateReadiness::from(probe.readiness());
+            health_record.ready = readiness.ready;
+            health_record.reason = readiness.reason;
+        }
+        health_record.rate_limit = self.rate_limiter.as_ref().map(|limiter| limiter.status());
+
+        // Explicitly specify Record type for upsert operation
+        let _: Option<HealthRecord> = self
+            .engine()
+            .db()
+            .lock()
+            .await
+            .upsert(&health_id)
+            .content(health_record.clone())
+            .await
+            .map_err(SystemActorError::from)?;
+
+        // Start health update task if enabled
+        if let Some(config) = config {
+            let engine = self.engine().clone();
+            let update_interval = config.update_interval;
+            let health_id = health_id.clone();
+            let health_probe = self.health_probe.clone();
+            let rate_limiter = self.rate_limiter.clone();
+            let actor_name = self.id().name().to_string();
+
+            let handle = tokio::spawn(async move {
+                loop {
+                    tokio::time::sleep(update_interval.into()).await;
+
+                    let readiness = match health_probe.read().unwrap().as_ref() {
+                        Some(probe) => UpdateReadiness::from(probe.readiness()),
+                        None => UpdateReadiness::from(Readiness::Ready),
+                    };
+                    let update = UpdateHealth {
+                        last_seen: sql::Datetime::default(),
+                        ready: readiness.ready,
+                        reason: readiness.reason,
+                        rate_limit: rate_limiter.as_ref().map(|limiter| limiter.status()),
+                    };
+
+                    if let Err(e) =
+                        engine.db().lock().await.update::<Option<HealthRecord>>(&health_id).merge(update).await
+                    {
+                        error!("[{}] health-update-error: {}", actor_name, e);
+                        break;
+                    }
+                }
+            });
+
+            self.health_task = Some(handle);
+        }
+
+        Ok(())
+    }
+
+    /// Set the health probe that reports this actor's readiness
+    ///
+    /// The probe is evaluated right away, and then on every health update if the actor was
+    /// spawned with a `HealthConfig`. Without one, the reported readiness only changes when
+    /// `refresh_readiness` is called. Use it to report conditions that make the actor unable to
+    /// handle messages while it's still running, such as a model that failed to load.
+    ///
+    /// # Arguments
+    ///
+    /// * `probe` - Function returning the current readiness of the actor
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let model_tx = self.model_tx.clone();
+    /// ctx.set_health_probe(move || {
+    ///     if model_tx.is_closed() {
+    ///         Readiness::NotReady("Model failed to load".into())
+    ///     } else {
+    ///         Readiness::Ready
+    ///     }
+    /// })
+    /// .await?;
+    /// ```
+    pub async fn set_health_probe(
+        &self,
+        probe: impl Fn() -> Readiness + Send + Sync + 'static,
+    ) -> Result<(), SystemActorError> {
+        *self.health_probe.write().unwrap() = Some(HealthProbe::new(probe));
+        self.refresh_readiness().await
+    }
+
+    /// Evaluate the health probe now and report the readiness in the health record
+    ///
+    /// Reports `Readiness::Ready` if no probe is set. Call it after a change the probe
+    /// depends on, to report it before the next health update.
+    pub async fn refresh_readiness(&self) -> Result<(), SystemActorError> {
+        let readiness = match self.health_probe.read().unwrap().as_ref() {
+            Some(probe) => probe.readiness(),
+            None => Readiness::Ready,
+        };
+        debug!("[{}] health-probe {:?}", self.id().name(), readiness);
+
+        let _: Option<HealthRecord> = self
+            .engine()
+            .db()
+            .lock()
+            .await
+            .update(&self.id().health_id())
+            .merge(UpdateReadiness::from(readiness))
+            .await
+            .map_err(SystemActorError::from)?;
+
+        Ok(())
+    }
+
+    /// Get the health of an actor from its health record
+    ///
+    /// # Arguments
+    ///
+    /// * `actor_id` - ID of the actor to check
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(ActorHealth))` with the actor's liveness and readiness
+    /// * `Ok(None)` if the actor has no health record
+    /// * `Err(SystemActorError)` if reading the health record fails
+    pub async fn actor_health(&self, actor_id: &ActorId) -> Result<Option<ActorHealth>, SystemActorError> {
+        let health: Option<HealthRecord> =
+            self.engine().db().lock().await.select(&actor_id.health_id()).await.map_err(SystemActorError::from)?;
+        Ok(health.map(|health| health.health()))
+    }
+
+    /// Check if an actor is healthy based on its health record
+    ///
+    /// An actor is considered healthy if it is ready to handle messages and either:
+    /// - Health monitoring is disabled for the actor
+    /// - The actor has updated its health status within its configured update interval
+    ///
+    /// # Arguments
+    ///
+    /// * `actor_id` - ID of the actor to check
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(true)` if the actor is healthy
+    /// * `Ok(false)` if the actor is unhealthy or not found
+    /// * `Err(SystemActorError)` if checking health status fails
+    pub async fn check_actor_health(&self, actor_id: &ActorId) -> Result<bool, SystemActorError> {
+        if let Some(health) = self.actor_health(actor_id).await? {
+            debug!(
+                "[{}] health-check {} enabled={} live={} ready={} last_seen={}",
+                self.id().name(),
                 actor_id.name(),
-                elapsed,
-                update_interval,
-                grace_period,
-                is_healthy
+                health.enabled,
+                health.live,
+                health.ready,
+                health.last_seen
             );
+            Ok(health.is_healthy())
+        } else {
+            debug!("[{}] health-check {} record-not-found", self.id().name(), actor_id.name());
+            Ok(false)
+        }
+    }
+
+    /// Spawns a child actor, namespaced under this actor's name
+    ///
+    /// The child's ID is `{parent}/{name}`, and the relation is recorded in the database, see
+    /// `Engine::children` and `Engine::actor_tree`. Start the returned actor as usual; its message
+    /// stream ends when this actor stops it with `stop_child`, or stops itself, so the child's `start`
+    /// returns and its own children stop in turn. Killing this actor kills its descendants.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let (mut embeddings_ctx, mut embeddings) = ctx.spawn_child("embeddings", self.embeddings.clone(), options).await?;
+    /// tokio::spawn(async move {
+    ///     if let Err(e) = embeddings.start(&mut embeddings_ctx).await {
+    ///         error!("Embeddings actor error: {}", e);
+    ///     }
+    /// });
+    /// ```
+    pub async fn spawn_child<A: Actor>(
+        &mut self,
+        name: impl AsRef<str>,
+        actor: A,
+        options: SpawnOptions,
+    ) -> Result<(ActorContext<A>, A), A::Error> {
+        self.check_owns_children()?;
+        let id = self.id.child::<A>(name);
+        debug!("[{}] child-spawn {}", self.id.record_id(), id.record_id());
+        let options = self.child_options(&id, options);
+        let spawned = A::spawn(self.engine.clone(), id.clone(), actor, options).await;
+        if spawned.is_err() {
+            self.children.retain(|(child, _)| child != &id);
+        }
+        spawned
+    }
 
-            Ok(is_healthy)
-        } else {
-            debug!("[{}] health-check {} record-not-found", self.id().name(), actor_id.name());
-            Ok(false)
+    /// Spawns a child actor with the factory registered for a tag, see `spawn_child`
+    ///
+    /// Dropping the returned handle doesn't stop the child, `stop_child` or stopping this actor does.
+    pub async fn spawn_child_tagged(
+        &mut self,
+        tag: impl Into<Cow<'static, str>>,
+        name: impl AsRef<str>,
+        config: Value,
+        options: SpawnOptions,
+    ) -> Result<(ActorId, ActorHandle), SystemActorError> {
+        self.check_owns_children()?;
+        let tag = tag.into();
+        let id = self.id.child_tagged(name, tag.clone());
+        debug!("[{}] child-spawn {}", self.id.record_id(), id.record_id());
+        let options = self.child_options(&id, options);
+        match self.engine.registry().spawn(tag, self.engine.clone(), config, id.clone(), options).await {
+            Ok(handle) => Ok((id, handle)),
+            Err(e) => {
+                self.children.retain(|(child, _)| child != &id);
+                Err(e)
+            }
         }
     }
 
-    /// Kill the actor
-    pub async fn kill(&self) -> Result<(), SystemActorError> {
-        let _: Option<ActorRecord> =
-            self.engine().db().lock().await.delete(&self.id.record_id()).await.map_err(SystemActorError::from)?;
+    /// Fails in the context of a single message, children are spawned from the actor's own context
+    fn check_owns_children(&self) -> Result<(), SystemActorError> {
+        if self.shared {
+            return Err(SystemActorError::SharedContext("spawning children".into()));
+        }
         Ok(())
     }
 
+    /// Links a child to this actor, replacing any earlier child with the same ID
+    fn child_options(&mut self, id: &ActorId, mut options: SpawnOptions) -> SpawnOptions {
+        let (stop, stopped) = watch::channel(false);
+        self.children.retain(|(child, _)| child != id);
+        self.children.push((id.clone(), stop));
+        options.parent = Some(ParentLink { id: self.id.clone(), stopped });
+        options
+    }
+
+    /// Stops a child of this actor, ending its message stream
+    ///
+    /// The child's records are kept, use `Engine::kill_actor` to delete them.
+    /// Returns false if the actor isn't a running child of this actor, which is always the case
+    /// in the context of a message handled by `handle_concurrently`.
+    pub fn stop_child(&mut self, id: &ActorId) -> bool {
+        let Some(index) = self.children.iter().position(|(child, _)| child == id) else {
+            return false;
+        };
+        debug!("[{}] child-stop {}", self.id.record_id(), id.record_id());
+        let (_, stop) = self.children.remove(index);
+        stop.send_replace(true);
+        true
+    }
+
+    /// Unlinks a child from this actor, returning the signal that stops it
+    pub(crate) fn detach_child(&mut self, id: &ActorId) -> Option<watch::Sender<bool>> {
+        let index = self.children.iter().position(|(child, _)| child == id)?;
+        Some(self.children.remove(index).1)
+    }
+
+    /// Stops all the children of this actor
+    pub fn stop_children(&mut self) {
+        for (id, stop) in self.children.drain(..) {
+            debug!("[{}] child-stop {}", self.id.record_id(), id.record_id());
+            stop.send_replace(true);
+        }
+    }
+
+    /// The running children of this actor
+    pub fn children(&self) -> impl Iterator<Item = &ActorId> {
+        self.children.iter().map(|(child, _)| child)
+    }
+
+    /// ID of the parent of this actor, for child actors
+    pub fn parent(&self) -> Option<&ActorId> {
+        self.parent.as_ref().map(|parent| &parent.id)
+    }
+
+    /// Reads the persisted state of another actor
+    ///
+    /// The state is the one the actor was spawned with or last saved with `Actor::save`,
+    /// read as `T`, which can be the actor type or any type matching part of its fields.
+    ///
+    /// # Arguments
+    ///
+    /// * `actor_id` - ID of the actor to read
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(T))` with the actor's state
+    /// * `Ok(None)` if the actor doesn't exist
+    /// * `Err(SystemActorError)` if reading the actor record or deserializing its state fails
+    pub async fn peek<T>(&self, actor_id: &ActorId) -> Result<Option<T>, SystemActorError>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let record: Option<ActorRecord> =
+            self.engine().db().lock().await.select(&actor_id.record_id()).await.map_err(SystemActorError::from)?;
+        debug!("[{}] state-peek {} found={}", self.id().record_id(), actor_id.record_id(), record.is_some());
+        record.map(|record| read_state(self.engine(), record)).transpose()
+    }
+
+    /// Streams the states persisted by another actor
+    ///
+    /// The stream starts with the actor's current state if it exists, then yields every state it saves,
+    /// and ends when the actor is killed. Backed by a live query on the actor's record, so observers
+    /// don't need to poll or to message the actor.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut states = ctx.watch_state::<IndexerProgress>(&indexer_id).await?;
+    /// while let Some(progress) = states.next().await {
+    ///     println!("{} files indexed", progress?.indexed);
+    /// }
+    /// ```
+    pub async fn watch_state<T>(&self, actor_id: &ActorId) -> Result<StateStream<T>, SystemActorError>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'static,
+    {
+        // Listen before reading the current state, so no change falls in between
+        let query = format!("LIVE SELECT * FROM {} WHERE id = {}", DB_TABLE_ACTOR, actor_id.record_id());
+        debug!("[{}] state-watch {}", self.id().record_id(), query);
+        let mut res = self.engine().db().lock().await.query(&query).await?;
+        let notifications = res.stream::<Notification<ActorRecord>>(0)?;
+
+        let engine = self.engine().clone();
+        let changes = notifications
+            // The actor was killed
+            .take_while(|notification| future::ready(!matches!(notification, Ok(n) if n.action == Action::Delete)))
+            .map(move |notification| read_state(&engine, notification?.data));
+
+        let current: Option<T> = self.peek(actor_id).await?;
+        Ok(Box::pin(futures::stream::iter(current.map(Ok)).chain(changes)))
+    }
+
+    /// Kill the actor and its descendants
+    pub async fn kill(&self) -> Result<(), SystemActorError> {
+        kill_actor(self.engine(), &self.id).await
+    }
+
+    /// Reactivates an actor if it is a passivated virtual actor
+    ///
+    /// The actor is spawned again through the `ActorTagRegistry` factory of its tag,
+    /// restoring its saved state.
+    /// Targets found not to be virtual actors are skipped for a while, without querying the database.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(true)` if the actor was passivated and has been reactivated
+    /// * `Ok(false)` if the actor is not a virtual actor or is already active
+    /// * `Err(SystemActorError)` if the factory is not registered or fails to spawn the actor
+    async fn activate(&self, actor_id: &ActorId) -> Result<bool, SystemActorError> {
+        let virtual_actors = self.engine().virtual_actors();
+        if virtual_actors.is_not_virtual(actor_id) {
+            return Ok(false);
+        }
+
+        let activation_id = actor_id.activation_id();
+
+        // Only one sender gets to flip the record back to active
+        let query = "UPDATE $id SET active = true WHERE active = false; RETURN record::exists($id);";
+        let mut res = self.engine().db().lock().await.query(query).bind(("id", activation_id)).await?;
+        let activated: Vec<ActivationRecord> = res.take(0)?;
+        let is_virtual: Option<bool> = res.take(1)?;
+        virtual_actors.set(actor_id, is_virtual.unwrap_or(false));
+
+        let Some(record) = activated.into_iter().next() else {
+            return Ok(false);
+        };
+
+        debug!("[{}] actor-activate {} {}", self.id().record_id(), actor_id.record_id(), record.tag);
+
+        let options = SpawnOptions::builder()
+            .exists(SpawnExistsOptions::Restore)
+            .maybe_health_config(record.health_config)
+            .passivation(record.passivation)
+            .maybe_lease(record.lease)
+            .maybe_concurrency(record.concurrency)
+            .maybe_rate_limit(record.rate_limit)
+            .build();
+
+        let spawned = self
+            .engine()
+            .registry()
+            .spawn(record.tag, self.engine().clone(), record.config, actor_id.clone(), options)
+            .await;
+
+        if let Err(e) = spawned {
+            error!("[{}] actor-activate-error {} {}", self.id().record_id(), actor_id.record_id(), e);
+            // Leave the actor passivated, so a later message can try again
+            deactivate(self.engine(), actor_id).await?;
+            return Err(e);
+        }
+
+        Ok(true)
+    }
+
     /// Receive messages for this actor
     ///
     /// This method sets up a stream of messages for the actor, combining any unreplied messages
@@ -1167,6 +2868,7 @@ impl<T: Actor> ActorContext<T> {
         let mut res = self.engine().db().lock().await.query(&query).await?;
         let live_query = res.stream::<Notification<FrameMessage>>(0)?;
         let self_id = self.id().clone();
+        let engine = self.engine().clone();
         let live_query = live_query
             .filter(|item| {
                 // Filter out non-create actions
@@ -1179,6 +2881,29 @@ impl<T: Actor> ActorContext<T> {
                 let item = item?;
                 Ok(item.data)
             })
+            // Load offloaded message content from the object store, skipping the messages cancelled by their sender
+            .filter_map(move |item| {
+                let engine = engine.clone();
+                async move {
+                    let frame = match item {
+                        Ok(frame) => frame,
+                        Err(e) => return Some(Err(e)),
+                    };
+                    let cancellable = frame.cancellable.then(|| frame.id.clone());
+                    let loaded = load_message_payload(&engine, frame).await;
+                    let Some(id) = cancellable else {
+                        return Some(loaded);
+                    };
+                    match engine.message_exists(&id).await {
+                        Ok(true) => Some(loaded),
+                        Ok(false) => {
+                            debug!("msg-recv-cancelled {}", id);
+                            None
+                        }
+                        Err(e) => Some(Err(e)),
+                    }
+                }
+            })
             .inspect(move |item| match item {
                 Ok(frame) => {
                     debug!(
@@ -1195,10 +2920,171 @@ impl<T: Actor> ActorContext<T> {
             });
 
         let unreplied_messages = self.unreplied_messages().await?;
-        let unreplied_stream = futures::stream::iter(unreplied_messages).map(Ok);
-        let chained_stream = unreplied_stream.chain(live_query);
+        let engine = self.engine().clone();
+        let unreplied_stream = futures::stream::iter(unreplied_messages).then(move |frame| {
+            let engine = engine.clone();
+            async move { load_message_payload(&engine, frame).await }
+        });
+        let engine = self.engine().clone();
+        let chained_stream = unreplied_stream
+            .chain(live_query)
+            // Let the engine's interceptors inspect, modify or reject the messages
+            .filter_map(move |item| {
+                future::ready(match item {
+                    Ok(mut frame) => match engine.interceptors().on_recv(&mut frame) {
+                        Ok(()) => Some(Ok(frame)),
+                        Err(e) => {
+                            // The sender gets the error, and the message counts as replied
+                            reply_error(engine.clone(), frame, &e);
+                            None
+                        }
+                    },
+                    Err(e) => Some(Err(e)),
+                })
+            });
+
+        // Delay or reject the messages exceeding the actor's rate limit
+        let chained_stream: MessageStream = match &self.rate_limiter {
+            Some(limiter) => limiter.clone().limit(self.engine().clone(), self.id().clone(), Box::pin(chained_stream)),
+            None => Box::pin(chained_stream),
+        };
+
+        // Stop receiving messages once the lease on the actor ID is lost to another owner
+        let chained_stream: MessageStream = match &self.lease {
+            Some(lease) => {
+                let mut lost = lease.lost.clone();
+                Box::pin(chained_stream.take_until(async move {
+                    let _ = lost.wait_for(|lost| *lost).await;
+                }))
+            }
+            None => chained_stream,
+        };
+
+        // Stop receiving messages once the parent stops the actor, or stops itself
+        let chained_stream: MessageStream = match &self.parent {
+            Some(parent) => {
+                let mut stopped = parent.stopped.clone();
+                Box::pin(chained_stream.take_until(async move {
+                    let _ = stopped.wait_for(|stopped| *stopped).await;
+                }))
+            }
+            None => chained_stream,
+        };
+
+        match &self.passivation {
+            Some(passivation) => Ok(self.passivating(chained_stream, passivation.idle_timeout.into())),
+            None => Ok(chained_stream),
+        }
+    }
+
+    /// Ends a message stream once it has been idle for `idle_timeout`, passivating the actor
+    ///
+    /// The actor is marked as passivated before the stream ends, so that the next message sent
+    /// to it reactivates it. Messages that arrive while passivating keep the actor running.
+    fn passivating(&self, stream: MessageStream, idle_timeout: Duration) -> MessageStream {
+        let engine = self.engine().clone();
+        let self_id = self.id().clone();
+
+        let stream = futures::stream::unfold(stream, move |mut stream| {
+            let engine = engine.clone();
+            let self_id = self_id.clone();
+            async move {
+                if let Ok(item) = tokio::time::timeout(idle_timeout, stream.next()).await {
+                    return item.map(|item| (item, stream));
+                }
+
+                debug!("[{}] actor-passivate idle={:?}", self_id.record_id(), idle_timeout);
+                let activation_id = self_id.activation_id();
+                let result: Result<Option<ActivationRecord>, surrealdb::Error> =
+                    engine.db().lock().await.update(&activation_id).merge(UpdateActivation { active: false }).await;
+                if let Err(e) = result {
+                    error!("[{}] actor-passivate-error {}", self_id.record_id(), e);
+                }
+
+                // Catch messages sent right before the actor was marked as passivated
+                let item = match tokio::time::timeout(PASSIVATION_GRACE, stream.next()).await {
+                    Ok(Some(item)) => item,
+                    _ => return None,
+                };
+
+                // Resume, unless a sender has already reactivated the actor elsewhere
+                let query = "UPDATE $id SET active = true WHERE active = false";
+                let resumed = match engine.db().lock().await.query(query).bind(("id", activation_id)).await {
+                    Ok(mut res) => res.take::<Vec<ActivationRecord>>(0).map(|records| !records.is_empty()),
+                    Err(e) => Err(e),
+                };
+                match resumed {
+                    Ok(true) => {
+                        debug!("[{}] actor-passivate-cancel", self_id.record_id());
+                        Some((item, stream))
+                    }
+                    Ok(false) => None,
+                    Err(e) => {
+                        error!("[{}] actor-passivate-error {}", self_id.record_id(), e);
+                        None
+                    }
+                }
+            }
+        });
+
+        Box::pin(stream)
+    }
+
+    /// Handles the frames of a message stream concurrently
+    ///
+    /// Up to `SpawnOptions::concurrency` frames are handled at once, one at a time if it isn't set.
+    /// Each frame gets a context of its own, so the replies of concurrent handlers reach the right
+    /// sender. Handlers share the actor's state, see `SharedMessage` for an example, and the health
+    /// probe of the actor's context, but can't spawn children, which the actor's context owns.
+    ///
+    /// Errors of the stream are logged and skipped. After the first error of a handler, no new frames
+    /// are taken and the handlers in flight are left to finish, so their senders get a final reply.
+    ///
+    /// Returns once the stream ends, or with the first error of a handler.
+    pub async fn handle_concurrently<F, Fut>(&self, mut stream: MessageStream, mut handler: F) -> Result<(), T::Error>
+    where
+        F: FnMut(ActorContext<T>, FrameMessage) -> Fut,
+        Fut: Future<Output = Result<(), T::Error>>,
+    {
+        let limit = self.concurrency.unwrap_or(1).max(1);
+        debug!("[{}] msg-concurrent limit={}", self.id().record_id(), limit);
+
+        let mut in_flight = futures::stream::FuturesUnordered::new();
+        let mut result = Ok(());
+        let mut open = true;
+        loop {
+            tokio::select! {
+                frame = stream.next(), if open && result.is_ok() && in_flight.len() < limit => match frame {
+                    Some(Ok(frame)) => in_flight.push(handler(self.message_context(), frame)),
+                    Some(Err(e)) => error!("[{}] msg-concurrent-error {}", self.id().record_id(), e),
+                    None => open = false,
+                },
+                Some(handled) = in_flight.next(), if !in_flight.is_empty() => {
+                    if let Err(e) = handled {
+                        error!("[{}] msg-concurrent-error {}", self.id().record_id(), e);
+                        if result.is_ok() {
+                            result = Err(e);
+                        }
+                    }
+                }
+                else => break,
+            }
+        }
+        result
+    }
 
-        Ok(Box::pin(chained_stream))
+    /// Context for handling a single message alongside others
+    ///
+    /// Shares the health probe and rate limiter of the actor's context, children stay with the actor's context.
+    fn message_context(&self) -> ActorContext<T> {
+        let mut ctx = ActorContext::new(self.engine.clone(), self.id.clone());
+        ctx.health_probe = Arc::clone(&self.health_probe);
+        ctx.passivation = self.passivation.clone();
+        ctx.concurrency = self.concurrency;
+        ctx.rate_limiter = self.rate_limiter.clone();
+        ctx.parent = self.parent.clone();
+        ctx.shared = true;
+        ctx
     }
 
     /// Begins processing an incoming message and sets up reply streaming.
@@ -1219,164 +3105,331 @@ impl<T: Actor> ActorContext<T> {
         // Set up message processing and logging
         debug!("[{}] msg-process-start {} {}", self.id().record_id(), frame.name, frame.id);
 
-        // Create an unbounded channel for streaming replies
-        let (tx, mut rx) = mpsc::unbounded_channel::<Result<Value, Value>>();
+        // Replies are encoded like the message
+        self.encoding = frame.encoding;
+        let (tx, handle) = spawn_reply_writer(self.engine.clone(), frame);
+
+        // Store sender in context for later reply sending
+        self.tx = Some(tx);
+        handle
+    }
+
+    // Called when handle() returns to clean up
+    async fn finish_message_processing(&mut self) {
+        // Drop channel to trigger final reply
+        self.tx = None;
+        self.encoding = Encoding::default();
+    }
 
-        // Clone database and frame for use in spawned task
-        let db = self.engine.db().clone();
-        let frame_clone = frame.clone();
+    /// Sends the handler's error if any, then the final reply once all replies are stored
+    async fn complete_message_processing<E: ActorError>(
+        &mut self,
+        handle: tokio::task::JoinHandle<()>,
+        result: &Result<(), E>,
+    ) -> Result<(), SystemActorError> {
+        // If error, send error to client
+        if let Err(e) = result {
+            self.error(e).await?;
+        }
 
-        // Load SQL query template for reply insertion
-        let reply_query = include_str!("../sql/reply.surql");
+        // Ensure cleanup happens regardless of handle result
+        let cleanup_result = {
+            // Clean up reply stream first
+            self.finish_message_processing().await;
 
-        // Spawn async task to handle reply processing
-        let handle = tokio::spawn(async move {
-            // Counter for tracking reply chunks in stream
-            let chunk_counter = AtomicU64::new(1);
+            // Then wait for final reply to be sent
+            handle.await
+        };
 
-            while let Some(result) = rx.recv().await {
-                let chunk = chunk_counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
-                debug!("[{}] msg-chunk {} {}-{}", frame_clone.rx, frame_clone.name, frame_clone.id.key(), chunk);
+        if let Err(e) = cleanup_result {
+            error!("Error during reply cleanup: {}", e);
+        }
 
-                // Create reply frame based on Ok/Err
-                let reply = match result {
-                    Ok(msg) => FrameReply::new_chunk(
-                        frame_clone.id.key().to_string(),
-                        chunk,
-                        frame_clone.name.clone(),
-                        frame_clone.rx.clone(),
-                        frame_clone.tx.clone(),
-                        msg,
-                    ),
-                    Err(err) => FrameReply::new_chunk_error(
-                        frame_clone.id.key().to_string(),
-                        chunk,
-                        frame_clone.name.clone(),
-                        frame_clone.rx.clone(),
-                        frame_clone.tx.clone(),
-                        err,
-                    ),
-                };
+        Ok(())
+    }
 
-                // Get database ID for reply
-                let reply_id = reply.id.to_record_id();
+    /// Internal method to prepare and send a message
+    async fn prepare_and_send_message<MT>(
+        &self,
+        message: &MT,
+        to: &ActorId,
+        options: Option<SendOptions>,
+    ) -> Result<(RecordId, RecordId, FrameMessage), SystemActorError>
+    where
+        MT: MessageType,
+    {
+        let name = std::any::type_name::<MT>();
+        let encoding = self.encoding_for(name, options.as_ref());
+        let msg_value = encoding.encode(message)?;
+        self.prepare_and_send_frame(name.into(), msg_value.into(), encoding, false, to, options).await
+    }
 
-                // Insert reply chunk into database
-                // This query likely creates a new reply record and links it to original message
-                let result = db
-                    .lock()
-                    .await
-                    .query(reply_query)
-                    .bind(("reply_id", reply_id))
-                    .bind(("reply", reply))
-                    .bind(("msg_id", frame_clone.id.clone()))
-                    .await;
+    /// Encoding of a message, from the send options or else the message type
+    fn encoding_for(&self, name: &str, options: Option<&SendOptions>) -> Encoding {
+        options.and_then(|options| options.encoding).unwrap_or_else(|| self.engine().encoding_for(name))
+    }
 
-                // Log any errors during reply insertion
-                if let Err(e) = result {
-                    error!(
-                        "[{}] msg-chunk-error {} {}-{} {}",
-                        frame_clone.rx,
-                        frame_clone.name,
-                        frame_clone.id.key(),
-                        chunk,
-                        e
-                    );
-                }
+    /// Internal method to prepare and send a message frame
+    async fn prepare_and_send_frame(
+        &self,
+        name: Cow<'static, str>,
+        content: FrameContent,
+        encoding: Encoding,
+        stream: bool,
+        to: &ActorId,
+        options: Option<SendOptions>,
+    ) -> Result<(RecordId, RecordId, FrameMessage), SystemActorError> {
+        let msg_id = Id::ulid();
+        let request_id = RecordId::from_table_key(DB_TABLE_MESSAGE, msg_id.to_string());
+        let stream_timeout = options.as_ref().filter(|_| stream).map(|options| options.timeout.into());
+        let reply_id = RecordId::from_table_key(DB_TABLE_REPLY, msg_id.to_string());
+
+        let mut request = FrameMessage {
+            id: request_id.clone(),
+            name,
+            tx: self.id().record_id(),
+            rx: to.record_id(),
+            msg: content.msg,
+            payload: None,
+            stream,
+            stream_timeout,
+            encoding,
+            cancellable: options.as_ref().is_some_and(|options| options.sent.is_some()),
+            engine: None,
+        };
+
+        // Interceptors may change the content of a forwarded message, which is then stored on its own
+        let mut forwarded = content.payload;
+        if !self.engine().interceptors().is_empty() {
+            if let Some(path) = forwarded.take().filter(|_| request.msg.is_null()) {
+                request.msg = self.engine().load_payload(&path).await?;
             }
+        }
 
-            // After channel closes (all replies sent), send final reply
-            // Store values needed for logging
-            let rx = frame_clone.rx.clone();
-            let name = frame_clone.name.clone();
-            let id_key = frame_clone.id.key().to_string();
+        // Let the engine's interceptors inspect, modify or reject the message, before anything is stored
+        self.engine().interceptors().on_send(&mut request)?;
 
-            debug!("[{}] msg-final {} {}", rx, name, id_key);
+        // Reactivate the receiver if it's a passivated virtual actor
+        let activated = self.activate(to).await?;
 
-            // Create final reply frame (chunk = None indicates end of stream)
-            let reply = FrameReply::new_final(id_key.clone(), frame_clone.name, frame_clone.rx, frame_clone.tx);
-            let reply_id = reply.id.to_record_id();
+        // Check health if enabled, a just activated actor is still starting up
+        if !activated && options.as_ref().is_some_and(|options| options.check_health) {
+            let is_healthy = self.check_actor_health(to).await?;
+            if !is_healthy {
+                return Err(SystemActorError::UnhealthyActor(to.clone()));
+            }
+        }
 
-            // Insert final reply into database
-            // Uses same query template but marks this as final reply
-            if let Err(e) = db
-                .lock()
-                .await
-                .query(reply_query)
-                .bind(("reply_id", reply_id))
-                .bind(("reply", reply))
-                .bind(("msg_id", frame_clone.id))
-                .await
-            {
-                error!("[{}] msg-final-error {} {} {}", rx, name, id_key, e);
+        // Move large messages to the object store, forwarded ones keep referring to the original content
+        let (msg_value, payload) = match forwarded {
+            Some(payload) => (Value::Null, Some(payload)),
+            None => self.engine().offload_payload(payload_path(DB_TABLE_MESSAGE, &msg_id), request.msg).await?,
+        };
+        request.msg = msg_value.clone();
+        request.payload = payload;
+
+        debug!(
+            "[{}] msg-send {} {} {} {}",
+            &self.id().record_id(),
+            &request.name,
+            &request.id,
+            &to.record_id(),
+            &msg_value
+        );
+
+        let db = self.engine().db().clone();
+
+        let task_request_id = request_id.clone();
+        let mut task_request = request.clone();
+        task_request.msg = self.engine().encrypt(task_request.msg, &request_id.to_string(), "msg")?;
+
+        // Don't enqueue the message again if it was already sent with the same key
+        let idempotency_key = options.as_ref().and_then(|options| options.idempotency_key.as_deref());
+        if let Some(key) = idempotency_key {
+            let original_id = self.enqueue_idempotent(to, key, task_request).await?;
+            if original_id != request_id {
+                debug!(
+                    "[{}] msg-send-duplicate {} {} {} {}",
+                    &self.id().record_id(),
+                    &request.name,
+                    key,
+                    &original_id,
+                    &to.record_id()
+                );
+                // The duplicate's content was never stored with a message
+                if let Some(path) =
+                    request.payload.take().filter(|path| *path == payload_path(DB_TABLE_MESSAGE, &msg_id))
+                {
+                    self.engine().delete_payloads([path]).await?;
+                }
+                let reply_id = RecordId::from_table_key(DB_TABLE_REPLY, original_id.key().to_string());
+                request.id = original_id.clone();
+                record_sent(options.as_ref(), &original_id);
+                return Ok((original_id, reply_id, request));
+            }
+            record_sent(options.as_ref(), &request_id);
+            return Ok((request_id, reply_id, request));
+        }
+
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_secs(0)).await;
+            let msg_id: Result<Option<Record>, surrealdb::Error> =
+                db.lock().await.create(DB_TABLE_MESSAGE).content(task_request.stored()).await;
+            if let Ok(Some(msg_id)) = msg_id {
+                let id = msg_id.id.clone();
+                if task_request_id != id {
+                    error!("msg-send {}", &task_request_id);
+                }
+            } else {
+                error!("msg-send {:?}", msg_id);
             }
         });
 
-        // Store sender in context for later reply sending
-        self.tx = Some(tx);
-        handle
+        record_sent(options.as_ref(), &request_id);
+        Ok((request_id, reply_id, request))
     }
 
-    // Called when handle() returns to clean up
-    async fn finish_message_processing(&mut self) {
-        // Drop channel to trigger final reply
-        self.tx = None;
+    /// Enqueues a message sent with an idempotency key, unless the key was already used for the receiver
+    ///
+    /// The key is claimed in the same transaction as the message is stored, so a failed send leaves no claim,
+    /// and claims expire after `EngineOptions::idempotency_ttl`. Returns the id of the message the key points to.
+    async fn enqueue_idempotent(
+        &self,
+        to: &ActorId,
+        key: &str,
+        request: FrameMessage,
+    ) -> Result<RecordId, SystemActorError> {
+        let idempotency_id = to.idempotency_id(key);
+        let query = include_str!("../sql/idempotent_send.surql");
+        let db = self.engine().db().lock().await;
+        let enqueued = db
+            .query(query)
+            .bind(("idempotency_id", idempotency_id.clone()))
+            .bind(("message_id", request.id.clone()))
+            .bind(("message", request.stored()))
+            .bind(("ttl", self.engine().options().idempotency_ttl))
+            .await
+            .and_then(|mut res| res.take::<Option<RecordId>>(2));
+        match enqueued {
+            Ok(Some(message_id)) => Ok(message_id),
+            Ok(None) => Err(SystemActorError::MessageReply("Idempotency key wasn't claimed".into())),
+            // The transaction fails when another engine claimed the key at the same time
+            Err(e) => {
+                let existing: Option<IdempotencyRecord> = db.select(&idempotency_id).await?;
+                existing.map(|existing| existing.message).ok_or_else(|| e.into())
+            }
+        }
     }
 
-    /// Internal method to prepare and send a message
-    async fn prepare_and_send_message<MT>(
+    /// Sends a message following the retry policy and circuit breaker in `options`, and streams its replies
+    async fn send_with_policy<MT, RT>(
         &self,
         message: &MT,
         to: &ActorId,
-        options: Option<SendOptions>,
-    ) -> Result<(RecordId, RecordId, FrameMessage), SystemActorError>
+        options: SendOptions,
+    ) -> Result<ReplyStream<RT>, SystemActorError>
     where
         MT: MessageType,
+        RT: MessageType + 'static,
     {
-        // Check health if enabled
-        if options.is_some() && options.unwrap().check_health {
-            let is_healthy = self.check_actor_health(to).await?;
-            if !is_healthy {
-                return Err(SystemActorError::UnhealthyActor(to.clone()));
+        if options.retry.is_none() && options.circuit_breaker.is_none() {
+            let (_, reply_id, _) = self.prepare_and_send_message::<MT>(message, to, Some(options.clone())).await?;
+            return self.replies_after_send::<RT>(&reply_id, options).await;
+        }
+
+        // Attempts share a key, so the target doesn't handle a message whose reply was late twice
+        let generated_key = options.retry.is_some() && options.idempotency_key.is_none();
+        let mut options = options;
+        if generated_key {
+            options.idempotency_key = Some(Id::ulid().to_string());
+        }
+
+        let mut attempt = 1;
+        loop {
+            let failure = match self.send_attempt::<MT, RT>(message, to, &options).await {
+                Ok(replies) => return Ok(replies),
+                Err(failure) => failure,
+            };
+            match &options.retry {
+                Some(retry) if retry.should_retry(attempt, failure.error()) => {
+                    // The target handled an attempt that replied with an error, the next one is a new request
+                    if generated_key && matches!(failure, SendFailure::Reply(_)) {
+                        options.idempotency_key = Some(Id::ulid().to_string());
+                    }
+                    let backoff = retry.backoff(attempt);
+                    debug!(
+                        "[{}] msg-retry {} {} attempt={} backoff={:?} {}",
+                        self.id().record_id(),
+                        std::any::type_name::<MT>(),
+                        to.record_id(),
+                        attempt,
+                        backoff,
+                        failure.error()
+                    );
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                }
+                _ => return failure.into_replies(),
             }
         }
+    }
+
+    /// Makes one attempt at sending a message, waiting for its first reply
+    ///
+    /// The outcome is recorded in the target's circuit breaker, if `options` has one.
+    async fn send_attempt<MT, RT>(
+        &self,
+        message: &MT,
+        to: &ActorId,
+        options: &SendOptions,
+    ) -> Result<ReplyStream<RT>, SendFailure>
+    where
+        MT: MessageType,
+        RT: MessageType + 'static,
+    {
+        let breakers = self.engine().circuit_breakers();
+        if let Some(config) = &options.circuit_breaker {
+            breakers.check(to, config).map_err(SendFailure::Send)?;
+        }
 
-        let msg_value = serde_json::to_value(&message)?;
-        let name = std::any::type_name::<MT>();
-        let msg_id = Id::ulid();
-        let request_id = RecordId::from_table_key(DB_TABLE_MESSAGE, msg_id.to_string());
-        let reply_id = RecordId::from_table_key(DB_TABLE_REPLY, msg_id.to_string());
-
-        let request = FrameMessage {
-            id: request_id.clone(),
-            name: name.into(),
-            tx: self.id().record_id(),
-            rx: to.record_id(),
-            msg: msg_value.clone(),
+        let sent = async {
+            let (_, reply_id, _) = self.prepare_and_send_message::<MT>(message, to, Some(options.clone())).await?;
+            self.replies_after_send::<RT>(&reply_id, options.clone()).await
+        }
+        .await;
+
+        let result = match sent {
+            Ok(mut replies) => match replies.next().await {
+                Some(Ok(first)) => {
+                    let replies: ReplyStream<RT> =
+                        Box::pin(futures::stream::once(future::ready(Ok(first))).chain(replies));
+                    Ok(replies)
+                }
+                Some(Err(e)) => Err(SendFailure::Reply(e)),
+                None => Ok(replies),
+            },
+            Err(e) => Err(SendFailure::Send(e)),
         };
 
-        debug!("[{}] msg-send {} {} {} {}", &self.id().record_id(), name, &request.id, &to.record_id(), &msg_value);
-
-        let db = self.engine().db().clone();
-
-        let task_request_id = request_id.clone();
-        let task_request = request.clone();
-
-        tokio::spawn(async move {
-            tokio::time::sleep(std::time::Duration::from_secs(0)).await;
-            let msg_id: Result<Option<Record>, surrealdb::Error> =
-                db.lock().await.create(DB_TABLE_MESSAGE).content(task_request).await;
-            if let Ok(Some(msg_id)) = msg_id {
-                let id = msg_id.id.clone();
-                if task_request_id != id {
-                    error!("msg-send {}", &task_request_id);
-                }
-            } else {
-                error!("msg-send {:?}", msg_id);
-            }
-        });
+        if let Some(config) = &options.circuit_breaker {
+            breakers.record(to, config, result.as_ref().err().map(SendFailure::error));
+        }
+        result
+    }
 
-        Ok((request_id, reply_id, request))
+    /// Streams the replies of a message that was just sent
+    ///
+    /// Messages sent with an idempotency key may be duplicates of earlier ones, whose replies are already stored.
+    async fn replies_after_send<RT: MessageType + 'static>(
+        &self,
+        reply_id: &RecordId,
+        options: SendOptions,
+    ) -> Result<ReplyStream<RT>, SystemActorError> {
+        if options.idempotency_key.is_some() {
+            Ok(self.replies_for::<RT>(reply_id, options).await?.stream)
+        } else {
+            self.wait_for_replies::<RT>(reply_id, options).await
+        }
     }
 
     /// Send a message to an actor without waiting for a reply.
@@ -1397,19 +3450,19 @@ impl<T: Actor> ActorContext<T> {
     /// # Returns
     ///
     /// A `Result` which is:
-    /// - `Ok(())` if the message was successfully sent.
+    /// - `Ok(RecordId)` with the id of the sent message, usable with `replies_for`.
     /// - `Err(SystemActorError)` if there was an error in sending the message.
     ///
     /// # Errors
     ///
     /// This method will return an error if the message preparation or sending process fails.
-    pub async fn do_send<M, MT>(&self, message: MT, to: &ActorId) -> Result<(), SystemActorError>
+    pub async fn do_send<M, MT>(&self, message: MT, to: &ActorId) -> Result<RecordId, SystemActorError>
     where
         M: Message<MT>,
         MT: MessageType,
     {
-        let _ = self.prepare_and_send_message::<MT>(&message, to, None).await?;
-        Ok(())
+        let (request_id, _, _) = self.prepare_and_send_message::<MT>(&message, to, None).await?;
+        Ok(request_id)
     }
 
     /// Send a message to an actor without waiting for a reply.
@@ -1430,18 +3483,18 @@ impl<T: Actor> ActorContext<T> {
     /// # Returns
     ///
     /// A `Result` which is:
-    /// - `Ok(())` if the message was successfully sent.
+    /// - `Ok(RecordId)` with the id of the sent message, usable with `replies_for`.
     /// - `Err(SystemActorError)` if there was an error in sending the message.
     ///
     /// # Errors
     ///
     /// This method will return an error if the message preparation or sending process fails.
-    pub async fn do_send_as<MT>(&self, message: MT, to: &ActorId) -> Result<(), SystemActorError>
+    pub async fn do_send_as<MT>(&self, message: MT, to: &ActorId) -> Result<RecordId, SystemActorError>
     where
         MT: MessageType,
     {
-        let (_, _, _) = self.prepare_and_send_message(&message, to, None).await?;
-        Ok(())
+        let (request_id, _, _) = self.prepare_and_send_message(&message, to, None).await?;
+        Ok(request_id)
     }
 
     /// Send a message and receive a stream of replies.
@@ -1492,8 +3545,7 @@ impl<T: Actor> ActorContext<T> {
         M: Message<MT>,
         MT: MessageType,
     {
-        let (_, reply_id, _) = self.prepare_and_send_message::<MT>(&message, to, Some(options.clone())).await?;
-        self.wait_for_replies::<M::Response>(&reply_id, options).await
+        self.send_with_policy::<MT, M::Response>(&message, to, options).await
     }
 
     /// Send a message to an actor and wait for a reply.
@@ -1527,8 +3579,129 @@ impl<T: Actor> ActorContext<T> {
         MT: MessageType,
         RT: MessageType + 'static,
     {
-        let (_, reply_id, _) = self.prepare_and_send_message::<MT>(&message, &to, Some(options.clone())).await?;
-        self.wait_for_replies::<RT>(&reply_id, options).await
+        self.send_with_policy::<MT, RT>(&message, &to, options).await
+    }
+
+    /// Send a message by name and receive a stream of replies, without knowing its Rust types.
+    ///
+    /// This is meant for tools and bridges handling messages as JSON, the `name` must be
+    /// the one the receiver expects, usually the type name of the message.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: The message name.
+    /// * `message`: The message content.
+    /// * `to`: The `ActorId` of the recipient actor.
+    /// * `options`: The `SendOptions` for this message.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the stream of replies as JSON, or an error if the message can't be sent.
+    pub async fn send_named(
+        &self,
+        name: impl Into<Cow<'static, str>>,
+        message: Value,
+        to: &ActorId,
+        options: SendOptions,
+    ) -> Result<ReplyStream<Value>, SystemActorError> {
+        let (_, reply_id, _) = self
+            .prepare_and_send_frame(name.into(), message.into(), Encoding::default(), false, to, Some(options.clone()))
+            .await?;
+        self.replies_after_send::<Value>(&reply_id, options).await
+    }
+
+    /// Forwards a received message to another actor, relaying its replies to the original sender.
+    ///
+    /// The message is sent under a new id, and a task copies the replies of the other actor
+    /// to the replies of the received message, ending with its final reply. Errors in sending
+    /// the message, or waiting for the replies, are sent back as error replies.
+    ///
+    /// Messages are forwarded independently of `Message::reply`, so several can be in flight at once.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame`: The received message.
+    /// * `to`: The `ActorId` of the actor handling the message.
+    /// * `options`: The `SendOptions` for the forwarded message.
+    ///
+    /// # Returns
+    ///
+    /// The handle of the task relaying the replies, which finishes once the final reply is stored.
+    pub async fn forward(
+        &self,
+        mut frame: FrameMessage,
+        to: &ActorId,
+        options: SendOptions,
+    ) -> tokio::task::JoinHandle<()> {
+        if frame.stream {
+            let error = SystemActorError::MessageReply("Streamed messages can't be forwarded".into());
+            return self.reject(frame, &error);
+        }
+
+        debug!("[{}] msg-forward {} {} {}", self.id().record_id(), frame.name, frame.id, to.record_id());
+
+        let sent = self
+            .prepare_and_send_frame(
+                frame.name.clone(),
+                FrameContent { msg: frame.msg.clone(), payload: frame.payload.clone() },
+                frame.encoding,
+                false,
+                to,
+                Some(options.clone()),
+            )
+            .await;
+
+        // The forwarded message may refer to the offloaded content, it's then deleted once that message is handled
+        if let Ok((_, _, request)) = &sent {
+            if request.payload.is_some() && request.payload == frame.payload {
+                frame.payload = None;
+                let query = "UPDATE $id SET payload = NONE";
+                if let Err(e) = self.engine().db().lock().await.query(query).bind(("id", frame.id.clone())).await {
+                    error!("[{}] msg-forward-error {} {} {}", self.id().record_id(), frame.name, frame.id, e);
+                }
+            }
+        }
+
+        let replies = match sent {
+            Ok((_, reply_id, _)) => self.live_replies(&reply_id.key().to_string()).await,
+            Err(e) => Err(e),
+        };
+        let mut replies = match replies {
+            Ok(replies) => replies,
+            Err(e) => return self.reject(frame, &e),
+        };
+
+        let engine = self.engine().clone();
+        let timeout = options.timeout;
+        tokio::spawn(async move {
+            let name = frame.name.clone();
+            let (tx, writer) = spawn_reply_writer(engine.clone(), frame);
+            loop {
+                let reply = match tokio::time::timeout(timeout, replies.next()).await {
+                    Ok(Some(Ok(reply))) => match load_reply_payload(&engine, reply).await {
+                        Ok(reply) if reply.id.chunk.is_none() => break,
+                        Ok(reply) if !reply.err.is_null() => Err(reply.err),
+                        Ok(reply) => Ok(reply.msg),
+                        Err(e) => Err(ErrorReply::new(&e).to_value()),
+                    },
+                    Ok(Some(Err(e))) => Err(ErrorReply::new(&e).to_value()),
+                    Ok(None) => break,
+                    Err(_) => Err(ErrorReply::new(&SystemActorError::MessageTimeout(name.clone(), timeout)).to_value()),
+                };
+                let failed = reply.is_err();
+                if tx.send(reply).is_err() || failed {
+                    break;
+                }
+            }
+            drop(tx);
+            let _ = writer.await;
+        })
+    }
+
+    /// Replies to a received message with an error, outside of `Message::reply`
+    pub(crate) fn reject(&self, frame: FrameMessage, error: &SystemActorError) -> tokio::task::JoinHandle<()> {
+        debug!("[{}] msg-reject {} {} {}", self.id().record_id(), frame.name, frame.id, error);
+        reply_error(self.engine().clone(), frame, error)
     }
 
     /// Sends a message and collects all replies into a Vec.
@@ -1619,6 +3792,141 @@ impl<T: Actor> ActorContext<T> {
         }
     }
 
+    /// Sends the same message to several actors and gathers their replies.
+    ///
+    /// Each target is sent its own copy of the message and times out on its own, following
+    /// `options.send`. Once `options.strategy` has its answer, the remaining replies are
+    /// cancelled: they're no longer waited for, and show up as `None` in the result. Their messages
+    /// are deleted, so targets skip them unless they're already handling them.
+    ///
+    /// # Type Parameters
+    ///
+    /// * `M`: The message handler type of the targets
+    /// * `MT`: The message type being sent
+    ///
+    /// # Arguments
+    ///
+    /// * `ids`: The target actor IDs
+    /// * `message`: The message to send
+    /// * `options`: Strategy and per-target send options
+    ///
+    /// # Returns
+    ///
+    /// The reply of each target, in the order of `ids`, and whether the strategy was satisfied.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let options = GatherOptions::builder().strategy(GatherStrategy::FirstSuccess).build();
+    /// let gathered = ctx.send_all::<Embeddings, TopK>(embeddings_ids, top_k, options).await;
+    /// for (id, similarities) in gathered.successes() {
+    ///     info!("{}: {:?}", id, similarities);
+    /// }
+    /// ```
+    pub async fn send_all<M, MT>(
+        &self,
+        ids: impl IntoIterator<Item = ActorId>,
+        message: MT,
+        options: GatherOptions,
+    ) -> GatherReplies<M::Response>
+    where
+        M: Message<MT>,
+        MT: MessageType,
+    {
+        let requests = ids.into_iter().map(|id| (id, message.clone()));
+        self.send_each::<M, MT>(requests, options).await
+    }
+
+    /// Sends a message to each of several actors and gathers their replies.
+    ///
+    /// Like `send_all`, but with a distinct message per request, e.g. to spread work over a pool of actors.
+    /// The same actor can be the target of several requests.
+    ///
+    /// # Arguments
+    ///
+    /// * `requests`: The target actor ID and message of each request
+    /// * `options`: Strategy and per-target send options
+    ///
+    /// # Returns
+    ///
+    /// The reply to each request, in the order of `requests`, and whether the strategy was satisfied.
+    pub async fn send_each<M, MT>(
+        &self,
+        requests: impl IntoIterator<Item = (ActorId, MT)>,
+        options: GatherOptions,
+    ) -> GatherReplies<M::Response>
+    where
+        M: Message<MT>,
+        MT: MessageType,
+    {
+        let requests: Vec<(ActorId, MT)> = requests.into_iter().collect();
+        let total = requests.len();
+        let required = options.strategy.required(total);
+        debug!(
+            "[{}] send-gather {} targets={} strategy={:?}",
+            self.id().record_id(),
+            std::any::type_name::<MT>(),
+            total,
+            options.strategy
+        );
+
+        let mut replies: Vec<TargetReply<M::Response>> =
+            requests.iter().map(|(id, _)| TargetReply { id: id.clone(), reply: None }).collect();
+
+        // Messages sent for each request, retries included, to cancel the ones left once the strategy has its answer
+        let sent: Vec<Arc<std::sync::Mutex<Vec<RecordId>>>> = (0..total).map(|_| Arc::default()).collect();
+
+        let mut pending = requests
+            .into_iter()
+            .enumerate()
+            .map(|(index, (id, message))| {
+                let send_options = SendOptions { sent: Some(sent[index].clone()), ..options.send.clone() };
+                async move { (index, self.send_and_wait_reply::<M, MT>(message, &id, send_options).await) }
+            })
+            .collect::<futures::stream::FuturesUnordered<_>>();
+
+        let mut successes = 0;
+        let mut remaining = total;
+        while let Some((index, reply)) = pending.next().await {
+            remaining -= 1;
+            if reply.is_ok() {
+                successes += 1;
+            }
+            replies[index].reply = Some(reply);
+
+            // Stop once the strategy has its answer, or once it can no longer get it
+            let done = match options.strategy {
+                GatherStrategy::All => false,
+                _ => successes >= required || successes + remaining < required,
+            };
+            if done {
+                break;
+            }
+        }
+
+        // Dropping the pending requests stops waiting for their replies, and their messages are cancelled
+        if !pending.is_empty() {
+            debug!(
+                "[{}] send-gather-cancel {} pending={}",
+                self.id().record_id(),
+                std::any::type_name::<MT>(),
+                pending.len()
+            );
+        }
+        drop(pending);
+        let cancelled: Vec<RecordId> = replies
+            .iter()
+            .zip(&sent)
+            .filter(|(reply, _)| reply.reply.is_none())
+            .flat_map(|(_, sent)| std::mem::take(&mut *sent.lock().unwrap_or_else(|e| e.into_inner())))
+            .collect();
+        if let Err(e) = self.engine().cancel_messages(cancelled).await {
+            error!("[{}] send-gather-cancel-error {}", self.id().record_id(), e);
+        }
+
+        GatherReplies { replies, satisfied: successes >= required }
+    }
+
     /// Sends a message to an actor and waits for exactly one reply without knowing the handler type.
     ///
     /// This is a convenience method that combines `send_as` and waiting for a single reply.
@@ -1694,7 +4002,7 @@ impl<T: Actor> ActorContext<T> {
     /// - Serialization of the reply content fails
     pub async fn reply<R: MessageType>(&self, response: R) -> Result<(), SystemActorError> {
         if let Some(tx) = &self.tx {
-            let value = serde_json::to_value(&response)?;
+            let value = self.encoding.encode(&response)?;
             tx.send(Ok(value)).map_err(|_| SystemActorError::MessageReply("Reply channel closed".into()))?;
             Ok(())
         } else {
@@ -1704,7 +4012,8 @@ impl<T: Actor> ActorContext<T> {
 
     /// Sends an error response during message processing.
     ///
-    /// The error will be propagated through the reply stream to the sender.
+    /// The error will be propagated through the reply stream to the sender, as an `ErrorReply`
+    /// with the code, kind and data given by its `ActorError` implementation.
     ///
     /// # Arguments
     ///
@@ -1716,8 +4025,8 @@ impl<T: Actor> ActorContext<T> {
     /// * `Err(SystemActorError)` if sending the error failed
     pub async fn error(&self, error: &impl ActorError) -> Result<(), SystemActorError> {
         if let Some(tx) = &self.tx {
-            let value = serde_json::to_value(&error.to_string())?;
-            tx.send(Err(value)).map_err(|_| SystemActorError::MessageReply("Reply channel closed".into()))?;
+            tx.send(Err(ErrorReply::new(error).to_value()))
+                .map_err(|_| SystemActorError::MessageReply("Reply channel closed".into()))?;
             Ok(())
         } else {
             Err(SystemActorError::MessageReply("No active message processing".into()))
@@ -1789,22 +4098,301 @@ impl<T: Actor> ActorContext<T> {
         // Debug print for starting to wait for replies
         debug!("[{}] reply-wait {} {}", self.id().record_id(), std::any::type_name::<RT>(), reply_id.key());
 
-        // Set up the live query for replies
-        let query =
-            format!("LIVE SELECT id.{{id, chunk}}, * FROM {} WHERE id.id = '{}'", DB_TABLE_REPLY, reply_id.key());
+        let frames = self.live_replies(&reply_id.key().to_string()).await?;
+        Ok(self.reply_stream::<RT>(frames, options))
+    }
+
+    /// Fetches the replies to a previously sent message.
+    ///
+    /// This is the counterpart of `do_send` and `do_send_as` for long running requests:
+    /// the sender keeps the returned message id and asks for the replies whenever it needs them,
+    /// instead of holding a reply stream open for the whole job.
+    ///
+    /// The returned stream yields the replies already stored, in chunk order, followed by
+    /// the ones still to come, until the final reply is received.
+    ///
+    /// # Type Parameters
+    ///
+    /// * `RT` - The expected type of the reply messages.
+    ///
+    /// # Arguments
+    ///
+    /// * `message_id` - The id of the sent message, as returned by `do_send`
+    /// * `options` - Options controlling timeout while waiting for pending replies
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing either:
+    /// - `Ok(MessageReplies<RT>)` - The reply stream and whether the final reply has already arrived
+    /// - `Err(SystemActorError)` - If querying the stored replies or setting up the live query fails
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let message_id = ctx.do_send::<LongJobActor, StartJob>(StartJob, &job_actor_id).await?;
+    ///
+    /// // ... later on
+    /// let mut replies = ctx.replies_for::<JobProgress>(&message_id, SendOptions::default()).await?;
+    /// if !replies.finished {
+    ///     println!("Job still running");
+    /// }
+    /// while let Some(progress) = replies.stream.next().await {
+    ///     println!("Progress: {:?}", progress?);
+    /// }
+    /// ```
+    pub async fn replies_for<RT: MessageType + 'static>(
+        &self,
+        message_id: &RecordId,
+        options: SendOptions,
+    ) -> Result<MessageReplies<RT>, SystemActorError> {
+        let id_key = message_id.key().to_string();
+        debug!("[{}] reply-fetch {} {}", self.id().record_id(), std::any::type_name::<RT>(), id_key);
+
+        let (finished, frames) = self.stored_and_live_frames(DB_TABLE_REPLY, &id_key).await?;
+        Ok(MessageReplies { finished, stream: self.reply_stream::<RT>(frames, options) })
+    }
+
+    /// Streams the chunk frames of a message in the given table, both the stored ones and the ones to come
+    ///
+    /// Returns whether the final chunk was already stored, along with the frames in chunk order.
+    async fn stored_and_live_frames(
+        &self,
+        table: &str,
+        id_key: &str,
+    ) -> Result<(bool, ReplyStream<FrameReply>), SystemActorError> {
+        // Listen before reading the stored chunks, so no chunk falls in between
+        let live = self.live_frames(table, id_key).await?;
+
+        let query = format!("SELECT id.{{id, chunk}}, * FROM {} WHERE id.id = $id", table);
+        let mut res = self.engine().db().lock().await.query(&query).bind(("id", id_key.to_string())).await?;
+        let mut stored: Vec<FrameReply> = res.take(0)?;
+        stored.sort_by_key(|frame| frame.id.chunk.unwrap_or(u64::MAX));
+
+        let finished = stored.iter().any(|frame| frame.id.chunk.is_none());
+        let last_chunk = stored.iter().filter_map(|frame| frame.id.chunk).max();
+
+        debug!(
+            "[{}] chunk-stored {} {} count={} finished={}",
+            self.id().record_id(),
+            table,
+            id_key,
+            stored.len(),
+            finished
+        );
+
+        let engine = self.engine().clone();
+        let table_name = table.to_string();
+        let stored = futures::stream::iter(stored).map(move |frame| frame.decrypt(&engine, &table_name));
+        let frames: ReplyStream<FrameReply> = if finished {
+            Box::pin(stored)
+        } else {
+            // Skip live chunks that were already stored
+            let live = live.filter(move |frame| {
+                future::ready(match (frame, last_chunk) {
+                    (Ok(frame), Some(last_chunk)) => !matches!(frame.id.chunk, Some(chunk) if chunk <= last_chunk),
+                    _ => true,
+                })
+            });
+            Box::pin(stored.chain(live))
+        };
+
+        Ok((finished, frames))
+    }
+
+    /// Receives the messages of a streamed request, sent with `send_stream`
+    ///
+    /// This is usually called through `StreamMessage::reply_stream`.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - The request frame, for which `FrameMessage::is_stream` is true
+    /// * `options` - Options controlling the timeout for each message, `reply_stream` takes it from the sender
+    pub async fn request_stream<MT: MessageType + 'static>(
+        &self,
+        frame: &FrameMessage,
+        options: SendOptions,
+    ) -> Result<RequestStream<MT>, SystemActorError> {
+        if !frame.is_stream::<MT>() {
+            return Err(SystemActorError::MessageTypeMismatch(std::any::type_name::<MT>()));
+        }
+        let id_key = frame.id.key().to_string();
+        debug!("[{}] msg-stream-recv {} {}", self.id().record_id(), frame.name, id_key);
+
+        let (_, frames) = self.stored_and_live_frames(DB_TABLE_MESSAGE_CHUNK, &id_key).await?;
+        Ok(self.reply_stream::<MT>(frames, options))
+    }
+
+    /// Sends a stream of messages to an actor as a single request, and waits for replies.
+    ///
+    /// The receiver handles all the messages at once with `StreamMessage::handle_stream`,
+    /// and its replies stream back just like with `send`. Each message is stored as a chunk
+    /// of the request as soon as the input stream yields it.
+    ///
+    /// # Type Parameters
+    ///
+    /// * `M`: The receiving actor type, which must implement `StreamMessage<MT>`.
+    /// * `MT`: The type of the streamed messages.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream`: The messages to send.
+    /// * `to`: The ID of the receiving actor.
+    /// * `options`: Options controlling the timeout for each reply, and for each message on the receiving side.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let chunks = futures::stream::iter(audio_chunks);
+    /// let mut transcripts = ctx.send_stream::<Transcriber, AudioChunk>(chunks, &transcriber_id, SendOptions::default()).await?;
+    /// while let Some(transcript) = transcripts.next().await {
+    ///     println!("{:?}", transcript?);
+    /// }
+    /// ```
+    pub async fn send_stream<M, MT>(
+        &self,
+        stream: impl Stream<Item = MT> + Send + 'static,
+        to: &ActorId,
+        options: SendOptions,
+    ) -> Result<ReplyStream<M::Response>, SystemActorError>
+    where
+        M: StreamMessage<MT>,
+        MT: MessageType + 'static,
+    {
+        let name = std::any::type_name::<MT>();
+        // Streams can't be replayed, so every stream is a new request
+        let options = SendOptions { idempotency_key: None, ..options };
+        let encoding = self.encoding_for(name, Some(&options));
+        let (request_id, reply_id, request) = self
+            .prepare_and_send_frame(name.into(), Value::Null.into(), encoding, true, to, Some(options.clone()))
+            .await?;
+
+        // Store each message as a chunk of the request
+        let engine = self.engine().clone();
+        let self_id = self.id().clone();
+        tokio::spawn(async move {
+            let id_key = request_id.key().to_string();
+            let mut stream = std::pin::pin!(stream);
+            let mut chunk = 0;
+            while let Some(message) = stream.next().await {
+                chunk += 1;
+                debug!("[{}] msg-stream-chunk {} {}-{}", self_id.record_id(), name, id_key, chunk);
+                let frame = match encoding.encode(&message) {
+                    Ok(msg) => {
+                        // Move large messages to the object store
+                        let path = payload_path(DB_TABLE_MESSAGE_CHUNK, format!("{}-{}", id_key, chunk));
+                        match engine.offload_payload(path, msg).await {
+                            Ok((msg, payload)) => {
+                                let mut frame = FrameReply::new_chunk(
+                                    id_key.clone(),
+                                    chunk,
+                                    name.into(),
+                                    request.tx.clone(),
+                                    request.rx.clone(),
+                                    msg,
+                                );
+                                frame.payload = payload;
+                                frame.encoding = encoding;
+                                frame
+                            }
+                            Err(e) => FrameReply::new_chunk_error(
+                                id_key.clone(),
+                                chunk,
+                                name.into(),
+                                request.tx.clone(),
+                                request.rx.clone(),
+                                ErrorReply::new(&e).to_value(),
+                            ),
+                        }
+                    }
+                    Err(e) => FrameReply::new_chunk_error(
+                        id_key.clone(),
+                        chunk,
+                        name.into(),
+                        request.tx.clone(),
+                        request.rx.clone(),
+                        ErrorReply::new(&e).to_value(),
+                    ),
+                }
+                .encrypt_or_error(&engine, DB_TABLE_MESSAGE_CHUNK);
+                let chunk_id = frame.id.to_record_id_in(DB_TABLE_MESSAGE_CHUNK);
+                let result = engine
+                    .db()
+                    .lock()
+                    .await
+                    .query("CREATE $chunk_id CONTENT $chunk")
+                    .bind(("chunk_id", chunk_id))
+                    .bind(("chunk", frame.stored()))
+                    .await;
+                if let Err(e) = result {
+                    error!("[{}] msg-stream-chunk-error {} {}-{} {}", self_id.record_id(), name, id_key, chunk, e);
+                }
+            }
+
+            // The final chunk ends the request stream
+            debug!("[{}] msg-stream-final {} {}", self_id.record_id(), name, id_key);
+            let frame = FrameReply::new_final(id_key.clone(), name.into(), request.tx, request.rx);
+            let chunk_id = frame.id.to_record_id_in(DB_TABLE_MESSAGE_CHUNK);
+            let result = engine
+                .db()
+                .lock()
+                .await
+                .query("CREATE $chunk_id CONTENT $chunk")
+                .bind(("chunk_id", chunk_id))
+                .bind(("chunk", frame.stored()))
+                .await;
+            if let Err(e) = result {
+                error!("[{}] msg-stream-final-error {} {} {}", self_id.record_id(), name, id_key, e);
+            }
+        });
+
+        self.wait_for_replies::<M::Response>(&reply_id, options).await
+    }
+
+    /// Sets up a live query on the reply frames of a message
+    async fn live_replies(&self, id_key: &str) -> Result<ReplyStream<FrameReply>, SystemActorError> {
+        self.live_frames(DB_TABLE_REPLY, id_key).await
+    }
+
+    /// Sets up a live query on the chunk frames of a message in the given table
+    async fn live_frames(&self, table: &str, id_key: &str) -> Result<ReplyStream<FrameReply>, SystemActorError> {
+        // Set up the live query for chunks
+        let query = format!("LIVE SELECT id.{{id, chunk}}, * FROM {} WHERE id.id = '{}'", table, id_key);
         debug!("[{}] reply-live {}", self.id().record_id(), query);
 
         let mut res = self.engine().db().lock().await.query(&query).await?;
         let notification_stream = res.stream::<Notification<FrameReply>>(0)?;
-        let self_id = self.id().clone();
 
-        // Transform the notification stream into a reply stream
+        let engine = self.engine().clone();
+        let table = table.to_string();
         let stream = notification_stream
             // Only process Create actions
             .filter(|n| future::ready(matches!(n, Ok(n) if n.action == Action::Create)))
-            .map(|n| -> Result<FrameReply, SystemActorError> {
+            .map(move |n| -> Result<FrameReply, SystemActorError> {
                 let n = n?;
-                Ok(n.data)
+                n.data.decrypt(&engine, &table)
+            });
+
+        Ok(Box::pin(stream))
+    }
+
+    /// Turns a stream of reply frames into a stream of typed replies
+    ///
+    /// The stream ends with the final reply frame, and each item is subject to the timeout in `options`.
+    fn reply_stream<RT: MessageType + 'static>(
+        &self,
+        frames: ReplyStream<FrameReply>,
+        options: SendOptions,
+    ) -> ReplyStream<RT> {
+        let self_id = self.id().clone();
+        let engine = self.engine().clone();
+
+        let final_engine = engine.clone();
+        let stream = frames
+            // The final reply has no content, interceptors see it as is
+            .inspect(move |reply| {
+                if let Some(reply) = reply.as_ref().ok().filter(|reply| reply.id.chunk.is_none()) {
+                    final_engine.interceptors().on_reply(reply);
+                }
             })
             // Take messages until we get final message (chunk = None)
             .take_while(|reply| {
@@ -1813,6 +4401,11 @@ impl<T: Actor> ActorContext<T> {
                     Err(_) => false,                       // Stop on error
                 })
             })
+            // Load offloaded reply content from the object store
+            .then(move |reply| {
+                let engine = engine.clone();
+                async move { load_reply_payload(&engine, reply?).await }
+            })
             // Process each reply
             .map(move |reply| -> Result<RT, SystemActorError> {
                 let reply = reply?;
@@ -1825,10 +4418,10 @@ impl<T: Actor> ActorContext<T> {
                 );
 
                 if !reply.err.is_null() {
-                    return Err(SystemActorError::MessageReply(reply.err.to_string().into()));
+                    return Err(SystemActorError::ErrorReply(ErrorReply::from_value(reply.err)));
                 }
 
-                let response: RT = serde_json::from_value(reply.msg)?;
+                let response: RT = reply.encoding.decode(reply.msg)?;
                 Ok(response)
             });
 
@@ -1853,7 +4446,7 @@ impl<T: Actor> ActorContext<T> {
                 }
             });
 
-        Ok(Box::pin(stream))
+        Box::pin(stream)
     }
 }
 
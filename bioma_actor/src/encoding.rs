@@ -0,0 +1,194 @@
+use crate::actor::SystemActorError;
+use base64::Engine as _;
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// Serialization format of the content of a frame
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EncodingFormat {
+    /// Plain JSON, stored as is and readable from queries
+    #[default]
+    Json,
+    /// CBOR, compact and self-describing
+    Cbor,
+    /// Bincode, the most compact, but only readable with the Rust type of the content
+    Bincode,
+}
+
+/// How the content of a message or reply frame is encoded
+///
+/// Plain JSON content is stored as is. Any other encoding stores the content as bytes, which frames
+/// hold as a base64 string, and `FrameMessage::is` and the reply streams decode transparently.
+/// Replies use the encoding of the message they reply to.
+///
+/// The encoding of a message is, in order of precedence:
+/// - `SendOptions::encoding`
+/// - The encoding set for the message type with `Engine::set_encoding`
+/// - Plain JSON
+///
+/// # Example
+///
+/// ```rust
+/// // Embeddings are large arrays of floats, send them as compressed CBOR
+/// let encoding = Encoding::builder().format(EncodingFormat::Cbor).zstd(3).build();
+/// engine.set_encoding::<GenerateEmbeddings>(encoding);
+/// ```
+#[derive(bon::Builder, Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Encoding {
+    /// Serialization format
+    #[builder(default)]
+    #[serde(default)]
+    pub format: EncodingFormat,
+    /// zstd compression level, uncompressed when None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zstd: Option<i32>,
+}
+
+impl Encoding {
+    /// Whether the content is stored as plain JSON
+    pub fn is_json(&self) -> bool {
+        self.format == EncodingFormat::Json && self.zstd.is_none()
+    }
+
+    /// Encodes content into the value stored in a frame
+    pub fn encode<T: Serialize>(&self, content: &T) -> Result<Value, SystemActorError> {
+        if self.is_json() {
+            return Ok(serde_json::to_value(content)?);
+        }
+        let bytes = match self.format {
+            EncodingFormat::Json => serde_json::to_vec(content)?,
+            EncodingFormat::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(content, &mut bytes).map_err(|e| encoding_error("cbor", e))?;
+                bytes
+            }
+            EncodingFormat::Bincode => bincode::serialize(content).map_err(|e| encoding_error("bincode", e))?,
+        };
+        let bytes = match self.zstd {
+            Some(level) => zstd::encode_all(bytes.as_slice(), level).map_err(|e| encoding_error("zstd", e))?,
+            None => bytes,
+        };
+        Ok(Value::String(base64::engine::general_purpose::STANDARD.encode(bytes)))
+    }
+
+    /// Decodes the value stored in a frame into its content
+    pub fn decode<T: DeserializeOwned>(&self, value: Value) -> Result<T, SystemActorError> {
+        if self.is_json() {
+            return Ok(serde_json::from_value(value)?);
+        }
+        let Value::String(encoded) = value else {
+            return Err(SystemActorError::Encoding("Expected base64 encoded content".into()));
+        };
+        let bytes =
+            base64::engine::general_purpose::STANDARD.decode(encoded).map_err(|e| encoding_error("base64", e))?;
+        let bytes = match self.zstd {
+            Some(_) => zstd::decode_all(bytes.as_slice()).map_err(|e| encoding_error("zstd", e))?,
+            None => bytes,
+        };
+        match self.format {
+            EncodingFormat::Json => Ok(serde_json::from_slice(&bytes)?),
+            EncodingFormat::Cbor => ciborium::from_reader(bytes.as_slice()).map_err(|e| encoding_error("cbor", e)),
+            EncodingFormat::Bincode => bincode::deserialize(&bytes).map_err(|e| encoding_error("bincode", e)),
+        }
+    }
+}
+
+fn encoding_error(format: &str, error: impl std::fmt::Display) -> SystemActorError {
+    SystemActorError::Encoding(format!("{}: {}", format, error).into())
+}
+
+/// A frame as stored in the database, with binary content as bytes rather than the base64 string frames hold
+#[derive(Serialize)]
+pub(crate) struct StoredFrame<F> {
+    #[serde(flatten)]
+    frame: F,
+    msg: StoredContent,
+}
+
+impl<F> StoredFrame<F> {
+    /// Stores a frame whose content was taken out of it, the frame must skip serializing its null content
+    pub(crate) fn new(frame: F, msg: Value, encoding: Encoding) -> Self {
+        let msg = match msg {
+            Value::String(encoded) if !encoding.is_json() => {
+                match base64::engine::general_purpose::STANDARD.decode(&encoded) {
+                    Ok(bytes) => StoredContent::Bytes(ContentBytes(bytes)),
+                    Err(_) => StoredContent::Json(Value::String(encoded)),
+                }
+            }
+            msg => StoredContent::Json(msg),
+        };
+        Self { frame, msg }
+    }
+}
+
+/// Content of a frame as stored
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum StoredContent {
+    Json(Value),
+    Bytes(ContentBytes),
+}
+
+/// Binary content, stored as SurrealDB bytes
+struct ContentBytes(Vec<u8>);
+
+impl Serialize for ContentBytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for ContentBytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct BytesVisitor;
+
+        impl serde::de::Visitor<'_> for BytesVisitor {
+            type Value = ContentBytes;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("bytes")
+            }
+
+            fn visit_bytes<E: serde::de::Error>(self, bytes: &[u8]) -> Result<ContentBytes, E> {
+                Ok(ContentBytes(bytes.to_vec()))
+            }
+
+            fn visit_byte_buf<E: serde::de::Error>(self, bytes: Vec<u8>) -> Result<ContentBytes, E> {
+                Ok(ContentBytes(bytes))
+            }
+        }
+
+        deserializer.deserialize_byte_buf(BytesVisitor)
+    }
+}
+
+/// Reads the stored content of a frame, with binary content as the base64 string `Encoding::decode` expects
+pub(crate) fn deserialize_content<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Value, D::Error> {
+    Ok(match StoredContent::deserialize(deserializer)? {
+        StoredContent::Json(value) => value,
+        StoredContent::Bytes(ContentBytes(bytes)) => {
+            Value::String(base64::engine::general_purpose::STANDARD.encode(bytes))
+        }
+    })
+}
+
+/// The encodings set per message type on an engine
+#[derive(Clone, Debug, Default)]
+pub(crate) struct MessageEncodings {
+    encodings: Arc<RwLock<HashMap<Cow<'static, str>, Encoding>>>,
+}
+
+impl MessageEncodings {
+    pub(crate) fn set(&self, name: Cow<'static, str>, encoding: Encoding) {
+        self.encodings.write().unwrap_or_else(|e| e.into_inner()).insert(name, encoding);
+    }
+
+    /// The encoding of a message type, plain JSON if none was set
+    pub(crate) fn get(&self, name: &str) -> Encoding {
+        self.encodings.read().unwrap_or_else(|e| e.into_inner()).get(name).copied().unwrap_or_default()
+    }
+}
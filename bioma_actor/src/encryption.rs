@@ -0,0 +1,185 @@
+use crate::actor::SystemActorError;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::Engine as _;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Field holding the envelope of an encrypted value
+const ENCRYPTED_FIELD: &str = "__encrypted";
+
+/// Provides the keys encrypting the payloads an engine stores
+///
+/// New payloads are encrypted with the current key, and each payload records the id of its key,
+/// so payloads encrypted with earlier keys stay readable as long as the provider still knows them.
+/// To rotate keys, make a new key current, then call `Engine::rotate_encryption` to re-encrypt
+/// the stored payloads before retiring the earlier keys.
+pub trait KeyProvider: Send + Sync {
+    /// Id of the key new payloads are encrypted with
+    fn current_key_id(&self) -> Cow<'_, str>;
+
+    /// The 256-bit key with the given id, None if unknown
+    fn key(&self, id: &str) -> Option<[u8; 32]>;
+}
+
+/// Keys held in memory, such as keys read from the environment or a secrets file
+///
+/// # Example
+///
+/// ```rust
+/// let keys = StaticKeys::new("2024-06", new_key).with_key("2024-01", old_key);
+/// let options = EngineOptions::builder().encryption(Encryption::new(keys)).build();
+/// ```
+#[derive(Clone)]
+pub struct StaticKeys {
+    current: String,
+    keys: HashMap<String, [u8; 32]>,
+}
+
+impl StaticKeys {
+    /// Keys with a single key, the current one
+    pub fn new(id: impl Into<String>, key: [u8; 32]) -> Self {
+        let current = id.into();
+        Self { keys: HashMap::from([(current.clone(), key)]), current }
+    }
+
+    /// Adds an earlier key, to read the payloads encrypted with it
+    pub fn with_key(mut self, id: impl Into<String>, key: [u8; 32]) -> Self {
+        self.keys.insert(id.into(), key);
+        self
+    }
+}
+
+impl std::fmt::Debug for StaticKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print the keys
+        write!(f, "StaticKeys({}, {} keys)", self.current, self.keys.len())
+    }
+}
+
+impl KeyProvider for StaticKeys {
+    fn current_key_id(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.current)
+    }
+
+    fn key(&self, id: &str) -> Option<[u8; 32]> {
+        self.keys.get(id).copied()
+    }
+}
+
+/// Encryption at rest of the payloads stored by an engine
+///
+/// Encrypts the content and errors of messages and replies, the state of actors, and the payloads
+/// moved to the object store, with AES-256-GCM. Routing fields such as `name`, `tx` and `rx` stay
+/// in the clear, so they can still be queried.
+///
+/// Each value is bound to the record and field it's stored in, so it can't be decrypted after being
+/// copied to another one. Values stored in the clear, for instance before encryption was enabled,
+/// are read as is.
+#[derive(Clone)]
+pub struct Encryption {
+    keys: Arc<dyn KeyProvider>,
+}
+
+impl std::fmt::Debug for Encryption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Encryption({})", self.keys.current_key_id())
+    }
+}
+
+/// An encrypted value, as stored under `ENCRYPTED_FIELD`
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    /// Id of the key the value is encrypted with
+    kid: String,
+    nonce: String,
+    data: String,
+}
+
+impl Encryption {
+    pub fn new(keys: impl KeyProvider + 'static) -> Self {
+        Self { keys: Arc::new(keys) }
+    }
+
+    /// Id of the key new values are encrypted with
+    pub fn current_key_id(&self) -> String {
+        self.keys.current_key_id().into_owned()
+    }
+
+    fn cipher(&self, kid: &str) -> Result<Aes256Gcm, SystemActorError> {
+        let key = self.keys.key(kid).ok_or_else(|| encryption_error(format!("Unknown key {}", kid)))?;
+        Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)))
+    }
+
+    /// Encrypts a value stored in the given record and field with the current key, null values are kept as is
+    pub fn encrypt(&self, value: Value, record: &str, field: &str) -> Result<Value, SystemActorError> {
+        if value.is_null() {
+            return Ok(value);
+        }
+        let kid = self.current_key_id();
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let plaintext = serde_json::to_vec(&value)?;
+        let aad = aad(record, field);
+        let payload = Payload { msg: plaintext.as_slice(), aad: aad.as_bytes() };
+        let data = self.cipher(&kid)?.encrypt(&nonce, payload).map_err(encryption_error)?;
+        let engine = base64::engine::general_purpose::STANDARD;
+        let envelope = Envelope { kid, nonce: engine.encode(nonce), data: engine.encode(data) };
+        let mut encrypted = serde_json::Map::new();
+        encrypted.insert(ENCRYPTED_FIELD.to_string(), serde_json::to_value(envelope)?);
+        Ok(Value::Object(encrypted))
+    }
+
+    /// Decrypts a value stored in the given record and field, values stored in the clear are returned as is
+    pub fn decrypt(&self, value: Value, record: &str, field: &str) -> Result<Value, SystemActorError> {
+        match Self::envelope(&value) {
+            Some(envelope) => {
+                let engine = base64::engine::general_purpose::STANDARD;
+                let nonce = engine.decode(&envelope.nonce).map_err(encryption_error)?;
+                let data = engine.decode(&envelope.data).map_err(encryption_error)?;
+                if nonce.len() != 12 {
+                    return Err(encryption_error("Invalid nonce"));
+                }
+                let aad = aad(record, field);
+                let payload = Payload { msg: data.as_slice(), aad: aad.as_bytes() };
+                let plaintext = self
+                    .cipher(&envelope.kid)?
+                    .decrypt(Nonce::from_slice(&nonce), payload)
+                    .map_err(encryption_error)?;
+                Ok(serde_json::from_slice(&plaintext)?)
+            }
+            None => Ok(value),
+        }
+    }
+
+    /// Whether a value still needs to be encrypted with the current key
+    pub(crate) fn needs_rotation(&self, value: &Value) -> bool {
+        match Self::envelope(value) {
+            Some(envelope) => envelope.kid != self.keys.current_key_id(),
+            None => !value.is_null(),
+        }
+    }
+
+    fn envelope(value: &Value) -> Option<Envelope> {
+        value.get(ENCRYPTED_FIELD).and_then(|envelope| serde_json::from_value(envelope.clone()).ok())
+    }
+}
+
+/// Fails to read an encrypted value without a key provider
+pub(crate) fn check_plaintext(value: Value) -> Result<Value, SystemActorError> {
+    if value.get(ENCRYPTED_FIELD).is_some() {
+        return Err(encryption_error("Encrypted value but no encryption configured"));
+    }
+    Ok(value)
+}
+
+/// Additional authenticated data binding a ciphertext to where it's stored
+fn aad(record: &str, field: &str) -> String {
+    format!("{}/{}", record, field)
+}
+
+fn encryption_error(error: impl std::fmt::Display) -> SystemActorError {
+    SystemActorError::Encryption(error.to_string().into())
+}
@@ -1,18 +1,35 @@
-use crate::actor::SystemActorError;
+use crate::actor::{
+    kill_actor, ActorHealth, ActorId, FrameMessage, FrameReply, HealthRecord, MessageType, ReplyId, SystemActorError,
+    VirtualActors, DB_TABLE_REPLY,
+};
+use crate::encoding::{deserialize_content, Encoding, MessageEncodings};
+use crate::encryption::{check_plaintext, Encryption};
 use crate::factory::ActorTagRegistry;
+use crate::flow::{FlowAggregator, FlowFrame, MessageFlow};
+use crate::interceptor::{Interceptor, Interceptors};
+use crate::migration::{schema_version, Migrations};
+use crate::retry::CircuitBreakers;
+use crate::schema::MessageRegistry;
 use crate::util::find_project_root;
 use derive_more::Display;
+use futures::{future, Stream, StreamExt};
+use object_store::aws::AmazonS3Builder;
 use object_store::local::LocalFileSystem;
+use object_store::{path::Path, ObjectStore};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::pin::Pin;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 use surrealdb::{
     engine::any::{Any, IntoEndpoint},
     opt::auth::Root,
+    sql,
     value::RecordId,
-    Surreal,
+    Action, Notification, Surreal,
 };
 use tokio::sync::Mutex;
 use tokio::time::sleep;
@@ -35,6 +52,118 @@ pub struct Record {
     pub id: RecordId,
 }
 
+/// Number of records re-encrypted at once by `Engine::rotate_encryption`
+const ROTATION_BATCH_SIZE: usize = 100;
+
+/// Component of the engine's tables in the `schema_version` table
+const ENGINE_SCHEMA: &str = "bioma_actor";
+
+/// Field that payloads moved to the object store are bound to when encrypted
+const PAYLOAD_FIELD: &str = "payload";
+
+/// Payload fields of a stored record, as read by `Engine::rotate_encryption`
+#[derive(Deserialize)]
+struct StoredPayloads {
+    id: RecordId,
+    /// Id of the reply or chunk, for the records of the `reply` and `message_chunk` tables
+    #[serde(default)]
+    reply: Option<ReplyId>,
+    #[serde(default, deserialize_with = "deserialize_content")]
+    msg: Value,
+    #[serde(default)]
+    err: Value,
+    #[serde(default)]
+    state: Value,
+    #[serde(default)]
+    payload: Option<Cow<'static, str>>,
+}
+
+/// Interval at which watched health records are re-evaluated, since an actor that stops doesn't write anything
+const HEALTH_WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long an actor that stopped updating its health record is reported as down, before it's assumed gone
+///
+/// Actors that stop delete their health record, only the actors of a crashed process go silent.
+const HEALTH_REPORT_EXPIRY: Duration = Duration::from_secs(600);
+
+/// A stream of actor health changes, see `Engine::health_changes`
+pub type HealthStream = Pin<Box<dyn Stream<Item = Result<ActorHealth, SystemActorError>> + Send>>;
+
+/// Stream of the message flow between actors, see `Engine::message_flow`
+pub type MessageFlowStream = Pin<Box<dyn Stream<Item = Result<MessageFlow, SystemActorError>> + Send>>;
+
+/// Engine-wide health report
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct HealthReport {
+    /// Whether the database is reachable
+    pub database: bool,
+    /// Health of the monitored actors, and of any actor reporting it's not ready
+    pub actors: Vec<ActorHealth>,
+}
+
+impl HealthReport {
+    /// The engine is healthy when the database is reachable and every reported actor is healthy
+    pub fn is_healthy(&self) -> bool {
+        self.database && self.actors.iter().all(|actor| actor.is_healthy())
+    }
+}
+
+/// An actor and its descendants, see `Engine::actor_tree`
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct ActorTree {
+    pub id: ActorId,
+    pub children: Vec<ActorTree>,
+}
+
+/// Name and tag of an actor record
+#[derive(Deserialize)]
+struct ActorName {
+    name: String,
+    tag: String,
+}
+
+enum HealthEvent {
+    Record(Action, HealthRecord),
+    Tick,
+}
+
+enum FlowEvent {
+    Sent(FlowFrame),
+    Replied(FlowFrame),
+    Tick,
+}
+
+/// Object store backend used by the engine.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ObjectStoreOptions {
+    /// Local file system, rooted at `EngineOptions::local_store_dir`.
+    #[default]
+    Local,
+    /// Any S3-compatible endpoint, such as AWS S3 or MinIO.
+    ///
+    /// Settings left unset are read from the standard `AWS_*` environment variables.
+    S3 {
+        /// Endpoint URL, required for S3-compatible services like MinIO (e.g. `http://127.0.0.1:9000`)
+        #[serde(default)]
+        endpoint: Option<String>,
+        /// Bucket name
+        bucket: String,
+        /// Region
+        #[serde(default)]
+        region: Option<String>,
+        /// Access key ID
+        #[serde(default)]
+        access_key_id: Option<String>,
+        /// Secret access key
+        #[serde(default)]
+        secret_access_key: Option<String>,
+        /// Allow plain HTTP endpoints
+        #[serde(default)]
+        allow_http: bool,
+    },
+}
+
 /// Configuration options for the Engine.
 #[derive(Clone, Debug, Serialize, Deserialize, bon::Builder)]
 pub struct EngineOptions {
@@ -70,6 +199,25 @@ pub struct EngineOptions {
     #[builder(default = default_hf_cache_dir())]
     #[serde(default = "default_hf_cache_dir")]
     pub hf_cache_dir: PathBuf,
+    /// The object store backend.
+    #[builder(default)]
+    #[serde(default)]
+    pub object_store: ObjectStoreOptions,
+    /// Message and reply payloads larger than this many bytes are kept in the object store
+    /// and passed by reference. Disabled when None.
+    ///
+    /// All the engines exchanging messages must share the same object store.
+    #[serde(default)]
+    pub payload_offload_threshold: Option<usize>,
+    /// How long an idempotency key is remembered, see `SendOptions::idempotency_key`
+    #[builder(default = default_idempotency_ttl())]
+    #[serde(default = "default_idempotency_ttl")]
+    pub idempotency_ttl: sql::Duration,
+    /// Encryption of message, reply and actor state payloads at rest. Disabled when None.
+    ///
+    /// All the engines sharing a database must be able to read each other's keys.
+    #[serde(skip)]
+    pub encryption: Option<Encryption>,
 }
 
 fn default_endpoint() -> Cow<'static, str> {
@@ -92,6 +240,10 @@ fn default_password() -> Cow<'static, str> {
     "root".into()
 }
 
+fn default_idempotency_ttl() -> sql::Duration {
+    sql::Duration::from_secs(24 * 60 * 60)
+}
+
 fn default_output_dir() -> PathBuf {
     let project_root = find_project_root();
     project_root.join(".output")
@@ -117,6 +269,34 @@ impl EngineOptions {
     pub fn info(&self) {
         info!("Engine: {}, ns: {}, db: {}, user: {}", self.endpoint, self.namespace, self.database, self.username);
     }
+
+    /// Builds the object store selected in the options
+    fn build_store(&self) -> Result<Arc<dyn ObjectStore>, SystemActorError> {
+        match &self.object_store {
+            ObjectStoreOptions::Local => {
+                if !self.local_store_dir.is_dir() {
+                    std::fs::create_dir_all(&self.local_store_dir)?;
+                }
+                Ok(Arc::new(LocalFileSystem::new_with_prefix(self.local_store_dir.clone())?))
+            }
+            ObjectStoreOptions::S3 { endpoint, bucket, region, access_key_id, secret_access_key, allow_http } => {
+                let mut builder = AmazonS3Builder::from_env().with_bucket_name(bucket).with_allow_http(*allow_http);
+                if let Some(endpoint) = endpoint {
+                    builder = builder.with_endpoint(endpoint);
+                }
+                if let Some(region) = region {
+                    builder = builder.with_region(region);
+                }
+                if let Some(access_key_id) = access_key_id {
+                    builder = builder.with_access_key_id(access_key_id);
+                }
+                if let Some(secret_access_key) = secret_access_key {
+                    builder = builder.with_secret_access_key(secret_access_key);
+                }
+                Ok(Arc::new(builder.build()?))
+            }
+        }
+    }
 }
 
 /// The engine is the main entry point for the Actor framework.
@@ -126,6 +306,35 @@ pub struct Engine {
     db: Arc<Mutex<Surreal<Any>>>,
     options: EngineOptions,
     registry: ActorTagRegistry,
+    messages: MessageRegistry,
+    store: Arc<dyn ObjectStore>,
+    circuit_breakers: CircuitBreakers,
+    virtual_actors: VirtualActors,
+    interceptors: Interceptors,
+    encodings: MessageEncodings,
+    actor_tables: Arc<std::sync::RwLock<Vec<ActorTable>>>,
+}
+
+/// A table of records belonging to actors, kept by a crate built on the engine
+///
+/// The records of an actor are deleted when it's killed or spawned with `SpawnExistsOptions::Reset`,
+/// and their encrypted fields are re-encrypted by `Engine::rotate_encryption`, see `Engine::add_actor_table`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ActorTable {
+    /// Name of the table
+    pub table: Cow<'static, str>,
+    /// Field holding the name of the actor a record belongs to
+    pub owner: Cow<'static, str>,
+    /// Fields encrypted with `Engine::encrypt`, bound to the record id and the field name
+    pub encrypted: Vec<Cow<'static, str>>,
+}
+
+/// A field of a record in an `ActorTable`, as read by `Engine::rotate_encryption`
+#[derive(Deserialize)]
+struct StoredField {
+    id: RecordId,
+    #[serde(default)]
+    value: Value,
 }
 
 impl Engine {
@@ -142,6 +351,8 @@ impl Engine {
         loop {
             match Self::attempt_connect(options.endpoint.to_string(), &options).await {
                 Ok(engine) => return Ok(engine),
+                // Retrying won't fix the schema
+                Err(e @ SystemActorError::MigrationFailed(..)) => return Err(e),
                 Err(e) => {
                     warn!("Failed to connect: {}. Retrying in {:?}...", e, retry_delay);
                     sleep(retry_delay).await;
@@ -156,44 +367,277 @@ impl Engine {
         db.connect(address).await?;
         db.signin(Root { username: &options.username, password: &options.password }).await?;
         db.use_ns(options.namespace.clone()).use_db(options.database.clone()).await?;
-        Engine::define(&db).await?;
-        Ok(Engine { db: Arc::new(Mutex::new(db)), options: options.clone(), registry: ActorTagRegistry::default() })
+        Engine::migrations().apply(&db).await?;
+        let store = options.build_store()?;
+        Ok(Engine {
+            db: Arc::new(Mutex::new(db)),
+            options: options.clone(),
+            registry: ActorTagRegistry::default(),
+            messages: MessageRegistry::default(),
+            store,
+            circuit_breakers: CircuitBreakers::default(),
+            virtual_actors: VirtualActors::default(),
+            interceptors: Interceptors::default(),
+            encodings: MessageEncodings::default(),
+            actor_tables: Arc::default(),
+        })
     }
 
     pub async fn test() -> Result<Engine, SystemActorError> {
-        let options = EngineOptions::default();
+        Self::test_with_options(EngineOptions::default()).await
+    }
+
+    /// Creates an in-memory engine for tests, ignoring the endpoint and credentials in `options`
+    pub async fn test_with_options(options: EngineOptions) -> Result<Engine, SystemActorError> {
         options.info();
         let db: Surreal<Any> = Surreal::init();
         db.connect("memory").await?;
         db.use_ns(options.namespace.clone()).use_db(options.database.clone()).await?;
-        Engine::define(&db).await?;
-        Ok(Engine { db: Arc::new(Mutex::new(db)), options, registry: ActorTagRegistry::default() })
+        Engine::migrations().apply(&db).await?;
+        let store = options.build_store()?;
+        Ok(Engine {
+            db: Arc::new(Mutex::new(db)),
+            options,
+            registry: ActorTagRegistry::default(),
+            messages: MessageRegistry::default(),
+            store,
+            circuit_breakers: CircuitBreakers::default(),
+            virtual_actors: VirtualActors::default(),
+            interceptors: Interceptors::default(),
+            encodings: MessageEncodings::default(),
+            actor_tables: Arc::default(),
+        })
     }
 
     pub async fn reset(&self) -> Result<(), SystemActorError> {
         let db = self.db.lock().await;
         let db_name = self.options.database.clone();
         let ns_name = self.options.namespace.clone();
+        let mut res = db.query("SELECT VALUE payload FROM message, reply, message_chunk WHERE payload != NONE").await?;
+        let paths: Vec<Cow<'static, str>> = res.take(0)?;
         db.query(format!("REMOVE DATABASE `{}`;", db_name)).await?;
         db.use_ns(ns_name).use_db(db_name).await?;
-        Engine::define(&db).await?;
-        Ok(())
+        Engine::migrations().apply(&db).await?;
+        drop(db);
+        self.delete_payloads(paths).await
     }
 
     pub async fn health(&self) -> bool {
         self.db.lock().await.health().await.is_ok()
     }
 
-    async fn define(db: &Surreal<Any>) -> Result<(), SystemActorError> {
-        // load the surreal definition file
-        // let def = std::fs::read_to_string("assets/surreal/def.surql").unwrap();
-        let def = include_str!("../sql/def.surql").parse::<String>().unwrap();
-        let mut res = db.query(&def).await?;
-        let err = res.take_errors();
-        for (k, v) in &err {
-            debug!("{}: {}", k, v);
+    /// Builds a health report of the database and the actors
+    ///
+    /// Actors without health monitoring are only reported when their health probe says they're not ready.
+    /// Actors that have been down for longer than `HEALTH_REPORT_EXPIRY` are left out.
+    pub async fn health_report(&self) -> Result<HealthReport, SystemActorError> {
+        let database = self.health().await;
+        let records: Vec<HealthRecord> = self.db.lock().await.select("health").await?;
+        let mut actors: Vec<ActorHealth> = records
+            .iter()
+            .map(|record| record.health())
+            .filter(|health| match health.enabled {
+                true => health.live || silent_for(health) <= HEALTH_REPORT_EXPIRY,
+                false => !health.ready,
+            })
+            .collect();
+        actors.sort_by(|a, b| a.id.name().cmp(b.id.name()));
+        Ok(HealthReport { database, actors })
+    }
+
+    /// Streams changes in actor health
+    ///
+    /// An item is yielded whenever an actor becomes live or stops being live, or its readiness changes,
+    /// including actors spawned after the stream was created. Liveness is re-evaluated periodically,
+    /// so an actor that stops updating its health record is reported once its update interval expires.
+    pub async fn health_changes(&self) -> Result<HealthStream, SystemActorError> {
+        let mut res = self.db.lock().await.query("LIVE SELECT * FROM health").await?;
+        let updates =
+            res.stream::<Notification<HealthRecord>>(0)?.map(|notification| -> Result<HealthEvent, SystemActorError> {
+                let notification = notification?;
+                Ok(HealthEvent::Record(notification.action, notification.data))
+            });
+
+        let ticks = futures::stream::unfold((), |_| async {
+            tokio::time::sleep(HEALTH_WATCH_INTERVAL).await;
+            Some((Ok(HealthEvent::Tick), ()))
+        });
+
+        // Start from the current state, so that only changes are reported
+        let records: Vec<HealthRecord> = self.db.lock().await.select("health").await?;
+        let known: HashMap<String, (HealthRecord, ActorHealth)> =
+            records.into_iter().map(|record| (record.id.to_string(), (record.clone(), record.health()))).collect();
+
+        let changes = futures::stream::select(updates, ticks)
+            .scan(known, |known, event: Result<HealthEvent, SystemActorError>| {
+                let changes = match event {
+                    Ok(HealthEvent::Record(Action::Delete, record)) => {
+                        known.remove(&record.id.to_string());
+                        vec![]
+                    }
+                    Ok(HealthEvent::Record(_, record)) => {
+                        let health = record.health();
+                        let key = record.id.to_string();
+                        let changed = match known.get(&key) {
+                            Some((_, last)) => has_changed(last, &health),
+                            None => true,
+                        };
+                        known.insert(key, (record, health.clone()));
+                        if changed {
+                            vec![Ok(health)]
+                        } else {
+                            vec![]
+                        }
+                    }
+                    Ok(HealthEvent::Tick) => known
+                        .values_mut()
+                        .filter_map(|(record, last)| {
+                            let health = record.health();
+                            if has_changed(last, &health) {
+                                *last = health.clone();
+                                Some(Ok(health))
+                            } else {
+                                None
+                            }
+                        })
+                        .collect(),
+                    Err(e) => vec![Err(e)],
+                };
+                future::ready(Some(futures::stream::iter(changes)))
+            })
+            .flatten();
+
+        Ok(Box::pin(changes))
+    }
+
+    /// Streams the message flow between actors, aggregated over windows of time
+    ///
+    /// An item is yielded at the end of each window, with an edge for each sender, receiver and message name
+    /// that had messages sent or replies received during the window. Messages sent by the actors of all
+    /// the processes sharing the database are included.
+    pub async fn message_flow(&self, window: Duration) -> Result<MessageFlowStream, SystemActorError> {
+        let query = "LIVE SELECT record::id(id) AS id, name, record::id(tx) AS tx, record::id(rx) AS rx FROM message";
+        let messages = self.live_flow(query).await?.map(|frame| frame.map(FlowEvent::Sent));
+        let query = "LIVE SELECT id.id AS id, id.chunk AS chunk, name, record::id(tx) AS tx, record::id(rx) AS rx, err FROM reply";
+        let replies = self.live_flow(query).await?.map(|frame| frame.map(FlowEvent::Replied));
+
+        let interval = tokio::time::interval_at(tokio::time::Instant::now() + window, window);
+        let ticks = futures::stream::unfold(interval, |mut interval| async move {
+            interval.tick().await;
+            Some((Ok(FlowEvent::Tick), interval))
+        });
+
+        let flow = futures::stream::select(futures::stream::select(messages, replies), ticks)
+            .scan(FlowAggregator::default(), move |aggregator, event: Result<FlowEvent, SystemActorError>| {
+                let flow = match event {
+                    Ok(FlowEvent::Sent(frame)) => {
+                        aggregator.sent(frame);
+                        None
+                    }
+                    Ok(FlowEvent::Replied(frame)) => {
+                        aggregator.replied(frame);
+                        None
+                    }
+                    Ok(FlowEvent::Tick) => Some(Ok(aggregator.flush(window))),
+                    Err(e) => Some(Err(e)),
+                };
+                future::ready(Some(flow))
+            })
+            .filter_map(future::ready);
+
+        Ok(Box::pin(flow))
+    }
+
+    /// Sets up a live query on the frames created in a table
+    async fn live_flow(
+        &self,
+        query: &str,
+    ) -> Result<impl Stream<Item = Result<FlowFrame, SystemActorError>>, SystemActorError> {
+        let mut res = self.db.lock().await.query(query).await?;
+        let frames = res
+            .stream::<Notification<FlowFrame>>(0)?
+            // Only process Create actions
+            .filter(|n| future::ready(matches!(n, Ok(n) if n.action == Action::Create)))
+            .map(|n| n.map(|n| n.data).map_err(SystemActorError::from));
+        Ok(frames)
+    }
+
+    /// Lists the actors with a record in the database, sorted by name
+    pub async fn actors(&self) -> Result<Vec<ActorId>, SystemActorError> {
+        let query = "SELECT record::id(id) AS name, tag FROM actor ORDER BY name";
+        let mut res = self.db.lock().await.query(query).await?;
+        let actors: Vec<ActorName> = res.take(0)?;
+        Ok(actors.into_iter().map(|actor| ActorId::with_tag(actor.name, actor.tag)).collect())
+    }
+
+    /// Messages sent to an actor that have no reply yet, oldest first
+    pub async fn pending_messages(&self, id: &ActorId) -> Result<Vec<FrameMessage>, SystemActorError> {
+        let query = include_str!("../sql/unreplied_messages.surql");
+        let mut res = self.db.lock().await.query(query).bind(("rx", id.record_id())).await?;
+        let messages: Vec<FrameMessage> = res.take(0)?;
+        Ok(messages)
+    }
+
+    /// Whether a message is still stored, i.e. it wasn't cancelled or deleted with its receiver
+    pub(crate) async fn message_exists(&self, id: &RecordId) -> Result<bool, SystemActorError> {
+        let mut res = self.db.lock().await.query("RETURN record::exists($id)").bind(("id", id.clone())).await?;
+        let exists: Option<bool> = res.take(0)?;
+        Ok(exists.unwrap_or(false))
+    }
+
+    /// Deletes the messages that have no reply yet, and their offloaded content
+    pub(crate) async fn cancel_messages(&self, ids: Vec<RecordId>) -> Result<(), SystemActorError> {
+        if ids.is_empty() {
+            return Ok(());
         }
-        Ok(())
+        debug!("msg-cancel {:?}", ids);
+        let query = "SELECT VALUE payload FROM $ids WHERE payload != NONE AND ->message_replies[0].out = NONE; \
+                     DELETE $ids WHERE ->message_replies[0].out = NONE;";
+        let mut res = self.db.lock().await.query(query).bind(("ids", ids)).await?;
+        let paths: Vec<Cow<'static, str>> = res.take(0)?;
+        res.check()?;
+        self.delete_payloads(paths).await
+    }
+
+    /// Children spawned by an actor with `ActorContext::spawn_child`, sorted by name
+    pub async fn children(&self, id: &ActorId) -> Result<Vec<ActorId>, SystemActorError> {
+        let query = "SELECT name, tag FROM hierarchy WHERE parent = $parent ORDER BY name";
+        let mut res = self.db.lock().await.query(query).bind(("parent", id.record_id())).await?;
+        let children: Vec<ActorName> = res.take(0)?;
+        Ok(children.into_iter().map(|child| ActorId::with_tag(child.name, child.tag)).collect())
+    }
+
+    /// An actor and all its descendants
+    pub async fn actor_tree(&self, id: &ActorId) -> Result<ActorTree, SystemActorError> {
+        let mut children = Vec::new();
+        for child in self.children(id).await? {
+            children.push(Box::pin(self.actor_tree(&child)).await?);
+        }
+        Ok(ActorTree { id: id.clone(), children })
+    }
+
+    /// Deletes the records of an actor and of its descendants, as `ActorContext::kill` does
+    ///
+    /// A process still running the actor isn't stopped, but its state and health are gone.
+    pub async fn kill_actor(&self, id: &ActorId) -> Result<(), SystemActorError> {
+        kill_actor(self, id).await
+    }
+
+    /// Migrations of the engine's own tables, applied on connect
+    pub fn migrations() -> Migrations {
+        Migrations::new(ENGINE_SCHEMA)
+            .migration(1, "init", include_str!("../sql/migrations/0001_init.surql"))
+            .migration(2, "idempotency_expiry", include_str!("../sql/migrations/0002_idempotency_expiry.surql"))
+    }
+
+    /// Applies the pending migrations of a component, returning the versions applied
+    pub async fn migrate(&self, migrations: &Migrations) -> Result<Vec<u32>, SystemActorError> {
+        migrations.apply(&*self.db.lock().await).await
+    }
+
+    /// Version of a component's schema, None if no migration was applied
+    pub async fn schema_version(&self, component: &str) -> Result<Option<u32>, SystemActorError> {
+        schema_version(&*self.db.lock().await, component).await
     }
 
     pub fn local_store(&self) -> Result<LocalFileSystem, SystemActorError> {
@@ -201,6 +645,246 @@ impl Engine {
         Ok(store)
     }
 
+    /// The object store selected in `EngineOptions::object_store`
+    pub fn store(&self) -> Arc<dyn ObjectStore> {
+        Arc::clone(&self.store)
+    }
+
+    /// Moves a payload to the object store if it's larger than the offload threshold
+    ///
+    /// Returns the payload to store inline, Null if offloaded, and the object store path it was moved to.
+    pub(crate) async fn offload_payload(
+        &self,
+        path: String,
+        payload: Value,
+    ) -> Result<(Value, Option<Cow<'static, str>>), SystemActorError> {
+        let Some(threshold) = self.options.payload_offload_threshold else {
+            return Ok((payload, None));
+        };
+        let bytes = serde_json::to_vec(&payload)?;
+        if bytes.len() <= threshold {
+            return Ok((payload, None));
+        }
+        debug!("payload-offload {} {} bytes", path, bytes.len());
+        let bytes = match &self.options.encryption {
+            Some(encryption) => serde_json::to_vec(&encryption.encrypt(payload, &path, PAYLOAD_FIELD)?)?,
+            None => bytes,
+        };
+        self.store.put(&Path::parse(&path)?, bytes.into()).await?;
+        Ok((Value::Null, Some(path.into())))
+    }
+
+    /// Loads a payload moved to the object store by `offload_payload`
+    pub(crate) async fn load_payload(&self, path: &str) -> Result<Value, SystemActorError> {
+        debug!("payload-load {}", path);
+        let bytes = self.store.get(&Path::parse(path)?).await?.bytes().await?;
+        self.decrypt(serde_json::from_slice(&bytes)?, path, PAYLOAD_FIELD)
+    }
+
+    /// Deletes payloads moved to the object store, skipping the ones already deleted
+    pub(crate) async fn delete_payloads(
+        &self,
+        paths: impl IntoIterator<Item = Cow<'static, str>>,
+    ) -> Result<(), SystemActorError> {
+        for path in paths {
+            debug!("payload-delete {}", path);
+            match self.store.delete(&Path::parse(path.as_ref())?).await {
+                Ok(()) | Err(object_store::Error::NotFound { .. }) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Encrypts a payload before it's stored in the given record and field, when encryption is enabled
+    ///
+    /// The record is the string form of its id. Payloads of other crates' tables are encrypted with it too.
+    pub fn encrypt(&self, value: Value, record: &str, field: &str) -> Result<Value, SystemActorError> {
+        match &self.options.encryption {
+            Some(encryption) => encryption.encrypt(value, record, field),
+            None => Ok(value),
+        }
+    }
+
+    /// Decrypts a payload stored in the given record and field, payloads stored in the clear are returned as is
+    pub fn decrypt(&self, value: Value, record: &str, field: &str) -> Result<Value, SystemActorError> {
+        match &self.options.encryption {
+            Some(encryption) => encryption.decrypt(value, record, field),
+            None => check_plaintext(value),
+        }
+    }
+
+    /// Decrypts the content of a message read from the `message` table
+    pub fn decrypt_message(&self, mut frame: FrameMessage) -> Result<FrameMessage, SystemActorError> {
+        frame.msg = self.decrypt(std::mem::take(&mut frame.msg), &frame.id().to_string(), "msg")?;
+        Ok(frame)
+    }
+
+    /// Decrypts the content and error of a reply read from the `reply` table
+    pub fn decrypt_reply(&self, frame: FrameReply) -> Result<FrameReply, SystemActorError> {
+        frame.decrypt(self, DB_TABLE_REPLY)
+    }
+
+    /// Re-encrypts the stored payloads with the current key of the key provider
+    ///
+    /// Covers the content and errors of messages, replies and streamed messages, the state of actors,
+    /// the payloads moved to the object store, and the encrypted fields of the tables added with
+    /// `add_actor_table`, including the ones stored in the clear before encryption was enabled.
+    /// The earlier keys can be retired once it returns.
+    ///
+    /// Returns the number of records updated.
+    pub async fn rotate_encryption(&self) -> Result<usize, SystemActorError> {
+        let Some(encryption) = &self.options.encryption else {
+            return Err(SystemActorError::Encryption("No encryption configured".into()));
+        };
+        let mut updated = 0;
+        for table in ["actor", "message", "reply", "message_chunk"] {
+            let mut start = 0;
+            loop {
+                // Replies and chunks are keyed by the message they belong to, which their payloads are bound to
+                let reply = if matches!(table, "reply" | "message_chunk") { "id.{id, chunk} AS reply, " } else { "" };
+                let query = format!(
+                    "SELECT {}id, msg, err, state, payload FROM type::table($table) ORDER BY id LIMIT $limit START $start",
+                    reply
+                );
+                let mut res = self
+                    .db
+                    .lock()
+                    .await
+                    .query(query)
+                    .bind(("table", table))
+                    .bind(("limit", ROTATION_BATCH_SIZE))
+                    .bind(("start", start))
+                    .await?;
+                let records: Vec<StoredPayloads> = res.take(0)?;
+                if records.is_empty() {
+                    break;
+                }
+                start += records.len();
+
+                for record in records {
+                    let location = match &record.reply {
+                        Some(reply) => reply.location(table),
+                        None => record.id.to_string(),
+                    };
+                    let mut fields = serde_json::Map::new();
+                    for (field, value) in [("msg", record.msg), ("err", record.err), ("state", record.state)] {
+                        if encryption.needs_rotation(&value) {
+                            let value = encryption.decrypt(value, &location, field)?;
+                            fields.insert(field.to_string(), encryption.encrypt(value, &location, field)?);
+                        }
+                    }
+                    if let Some(path) = &record.payload {
+                        let store_path = Path::parse(path.as_ref())?;
+                        // Payloads deleted since the record was read are skipped
+                        let bytes = match self.store.get(&store_path).await {
+                            Ok(result) => result.bytes().await?,
+                            Err(object_store::Error::NotFound { .. }) => continue,
+                            Err(e) => return Err(e.into()),
+                        };
+                        let value: Value = serde_json::from_slice(&bytes)?;
+                        if encryption.needs_rotation(&value) {
+                            let value = encryption.decrypt(value, path, PAYLOAD_FIELD)?;
+                            let value = encryption.encrypt(value, path, PAYLOAD_FIELD)?;
+                            self.store.put(&store_path, serde_json::to_vec(&value)?.into()).await?;
+                        }
+                    }
+                    if !fields.is_empty() {
+                        self.db
+                            .lock()
+                            .await
+                            .query("UPDATE $id MERGE $fields")
+                            .bind(("id", record.id))
+                            .bind(("fields", Value::Object(fields)))
+                            .await?
+                            .check()?;
+                        updated += 1;
+                    }
+                }
+            }
+        }
+        for table in self.actor_tables() {
+            for field in &table.encrypted {
+                updated += self.rotate_field(encryption, &table.table, field).await?;
+            }
+        }
+        debug!("encryption-rotate {} {}", encryption.current_key_id(), updated);
+        Ok(updated)
+    }
+
+    /// Re-encrypts a field of the records of a table, returning the number of records updated
+    async fn rotate_field(&self, encryption: &Encryption, table: &str, field: &str) -> Result<usize, SystemActorError> {
+        let mut updated = 0;
+        let mut start = 0;
+        loop {
+            let mut res = self
+                .db
+                .lock()
+                .await
+                .query(
+                    "SELECT id, type::field($field) AS value FROM type::table($table) \
+                     ORDER BY id LIMIT $limit START $start",
+                )
+                .bind(("table", table.to_string()))
+                .bind(("field", field.to_string()))
+                .bind(("limit", ROTATION_BATCH_SIZE))
+                .bind(("start", start))
+                .await?;
+            let records: Vec<StoredField> = res.take(0)?;
+            if records.is_empty() {
+                break;
+            }
+            start += records.len();
+
+            for record in records.into_iter().filter(|record| encryption.needs_rotation(&record.value)) {
+                let location = record.id.to_string();
+                let value = encryption.decrypt(record.value, &location, field)?;
+                let mut fields = serde_json::Map::new();
+                fields.insert(field.to_string(), encryption.encrypt(value, &location, field)?);
+                self.db
+                    .lock()
+                    .await
+                    .query("UPDATE $id MERGE $fields")
+                    .bind(("id", record.id))
+                    .bind(("fields", Value::Object(fields)))
+                    .await?
+                    .check()?;
+                updated += 1;
+            }
+        }
+        Ok(updated)
+    }
+
+    /// Adds a table whose records belong to actors, see `ActorTable`
+    ///
+    /// Adding the same table again has no effect.
+    pub fn add_actor_table(&self, table: ActorTable) {
+        let mut tables = self.actor_tables.write().unwrap_or_else(|e| e.into_inner());
+        if !tables.contains(&table) {
+            tables.push(table);
+        }
+    }
+
+    fn actor_tables(&self) -> Vec<ActorTable> {
+        self.actor_tables.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Deletes the records of an actor in the tables added with `add_actor_table`
+    pub(crate) async fn delete_actor_records(&self, id: &ActorId) -> Result<(), SystemActorError> {
+        for table in self.actor_tables() {
+            self.db
+                .lock()
+                .await
+                .query("DELETE type::table($table) WHERE type::field($owner) = $name")
+                .bind(("table", table.table.to_string()))
+                .bind(("owner", table.owner.to_string()))
+                .bind(("name", id.name().to_string()))
+                .await?
+                .check()?;
+        }
+        Ok(())
+    }
+
     pub fn local_store_dir(&self) -> &PathBuf {
         &self.options.local_store_dir
     }
@@ -224,6 +908,53 @@ impl Engine {
     pub fn registry(&self) -> &ActorTagRegistry {
         &self.registry
     }
+
+    /// Message types handled by the actors of this process, with their JSON Schemas
+    pub fn messages(&self) -> &MessageRegistry {
+        &self.messages
+    }
+
+    /// Adds an interceptor to the messages sent and received through this engine
+    ///
+    /// Interceptors run in the order they were added, see `Interceptor`.
+    pub fn add_interceptor(&self, interceptor: impl Interceptor + 'static) {
+        self.interceptors.add(Arc::new(interceptor));
+    }
+
+    pub(crate) fn interceptors(&self) -> &Interceptors {
+        &self.interceptors
+    }
+
+    /// Sets the encoding of a message type, for the messages sent through this engine
+    ///
+    /// `SendOptions::encoding` takes precedence, see `Encoding`.
+    pub fn set_encoding<MT: MessageType>(&self, encoding: Encoding) {
+        self.encodings.set(std::any::type_name::<MT>().into(), encoding);
+    }
+
+    /// The encoding of a message type, plain JSON if none was set
+    pub(crate) fn encoding_for(&self, name: &str) -> Encoding {
+        self.encodings.get(name)
+    }
+
+    /// Circuit breakers of the targets of the sends in this process
+    pub(crate) fn circuit_breakers(&self) -> &CircuitBreakers {
+        &self.circuit_breakers
+    }
+
+    /// Which targets of the sends in this process are virtual actors
+    pub(crate) fn virtual_actors(&self) -> &VirtualActors {
+        &self.virtual_actors
+    }
+}
+
+fn has_changed(last: &ActorHealth, health: &ActorHealth) -> bool {
+    last.live != health.live || last.ready != health.ready || last.reason != health.reason
+}
+
+/// How long since an actor last updated its health record
+fn silent_for(health: &ActorHealth) -> Duration {
+    SystemTime::now().duration_since(SystemTime::from(health.last_seen.0)).unwrap_or(Duration::ZERO)
 }
 
 #[cfg(test)]
@@ -0,0 +1,137 @@
+use crate::actor::{ActorError, SystemActorError};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+
+/// How a sender should treat an error reply
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// The failure may go away, sending the message again can succeed
+    Transient,
+    /// The message was invalid, sending it again will fail the same way
+    Invalid,
+    /// The receiver can't handle the message
+    #[default]
+    Permanent,
+}
+
+/// An error replied by an actor, as the sender receives it
+///
+/// `ActorContext::error` builds it from the `ActorError` methods of the receiver's error type,
+/// and senders get it back as `SystemActorError::ErrorReply`. When the receiver fills in `data`
+/// with its serialized error, `decode` turns the reply back into the receiver's error type.
+///
+/// # Example
+///
+/// ```rust
+/// match relay_ctx.send_and_wait_reply::<Embeddings, GenerateEmbeddings>(message, &embeddings_id, options).await {
+///     Ok(embeddings) => { /* ... */ }
+///     Err(SystemActorError::ErrorReply(reply)) if reply.code == "TextEmbeddingNotInitialized" => { /* start the model */ }
+///     Err(SystemActorError::ErrorReply(reply)) if reply.kind == ErrorKind::Transient => { /* try again later */ }
+///     // Errors implementing `Deserialize` and replying their serialized self as data can be decoded
+///     Err(error) => match error.reply_as::<ValidationError>() {
+///         Some(ValidationError::OutOfRange { max, .. }) => { /* send a smaller value */ }
+///         _ => return Err(error.into()),
+///     },
+/// }
+/// ```
+#[derive(thiserror::Error, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[error("{code}: {message}")]
+pub struct ErrorReply {
+    /// Machine-readable code, such as the name of the error variant
+    pub code: Cow<'static, str>,
+    /// How the sender should treat the error
+    #[serde(default)]
+    pub kind: ErrorKind,
+    /// Human-readable description
+    pub message: String,
+    /// Error details, usually the serialized error of the receiver
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl ErrorReply {
+    /// Builds the reply for an error
+    pub fn new(error: &impl ActorError) -> Self {
+        Self { code: error.code(), kind: error.kind(), message: error.to_string(), data: error.data() }
+    }
+
+    /// Reads the error of a reply frame
+    ///
+    /// Errors stored as plain values, such as the strings replied before error replies were structured,
+    /// become a permanent error with the `Unknown` code.
+    pub fn from_value(value: Value) -> Self {
+        match serde_json::from_value::<ErrorReply>(value.clone()) {
+            Ok(reply) => reply,
+            Err(_) => {
+                let message = match value {
+                    Value::String(message) => message,
+                    value => value.to_string(),
+                };
+                Self { code: "Unknown".into(), kind: ErrorKind::Permanent, message, data: None }
+            }
+        }
+    }
+
+    /// The reply as stored in a reply frame
+    pub fn to_value(&self) -> Value {
+        serde_json::to_value(self).unwrap_or_else(|_| Value::String(self.to_string()))
+    }
+
+    /// Turns the reply back into the receiver's error type, if its data holds one
+    pub fn decode<E: DeserializeOwned>(&self) -> Option<E> {
+        self.data.clone().and_then(|data| serde_json::from_value(data).ok())
+    }
+}
+
+/// Name of the variant of an error, from its `Debug` output
+///
+/// The default `ActorError::code`, for implementations overriding the code of some variants only.
+/// The name is only stable with a derived `Debug`, errors with a custom `Debug` should implement `code` themselves.
+pub fn variant_name(error: &impl std::fmt::Debug) -> Cow<'static, str> {
+    let debug = format!("{:?}", error);
+    let name: String = debug.chars().take_while(|c| c.is_alphanumeric() || *c == '_').collect();
+    if name.is_empty() {
+        "Unknown".into()
+    } else {
+        name.into()
+    }
+}
+
+impl SystemActorError {
+    /// How a sender should treat the error
+    pub(crate) fn error_kind(&self) -> ErrorKind {
+        match self {
+            SystemActorError::ErrorReply(reply) => reply.kind,
+            SystemActorError::IoError(_)
+            | SystemActorError::EngineError(_)
+            | SystemActorError::LiveStream(_)
+            | SystemActorError::MessageTimeout(_, _)
+            | SystemActorError::TaskTimeout(_)
+            | SystemActorError::ObjectStore(_)
+            | SystemActorError::JoinHandle(_)
+            | SystemActorError::LeaseHeld(_)
+            | SystemActorError::CircuitOpen(_)
+            | SystemActorError::RateLimited(_)
+            | SystemActorError::NoWorkers(_)
+            | SystemActorError::UnhealthyActor(_) => ErrorKind::Transient,
+            SystemActorError::MessageTypeMismatch(_)
+            | SystemActorError::JsonSerde(_)
+            | SystemActorError::InvalidActorConfig(_, _)
+            | SystemActorError::MessageRejected(_)
+            | SystemActorError::Encoding(_) => ErrorKind::Invalid,
+            _ => ErrorKind::Permanent,
+        }
+    }
+
+    /// Decodes an error replied by another actor into its error type
+    ///
+    /// Returns `None` for errors that aren't error replies, or whose data doesn't hold an `E`.
+    pub fn reply_as<E: DeserializeOwned>(&self) -> Option<E> {
+        match self {
+            SystemActorError::ErrorReply(reply) => reply.decode(),
+            _ => None,
+        }
+    }
+}
@@ -1,6 +1,7 @@
+use crate::actor::{deactivate, ActivationRecord};
 use crate::prelude::*;
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 use tokio::{sync::RwLock, task::JoinHandle};
 use tracing::error;
@@ -15,6 +16,20 @@ pub trait ActorFactory: Send + Sync {
         id: ActorId,
         options: SpawnOptions,
     ) -> Result<ActorHandle, SystemActorError>;
+
+    /// JSON Schema of the config accepted by `spawn`
+    ///
+    /// Defaults to a schema that accepts any config.
+    fn config_schema(&self) -> serde_json::Value {
+        serde_json::Value::Bool(true)
+    }
+
+    /// Checks a config without spawning an actor
+    ///
+    /// Factories should check that the config can be deserialized the same way `spawn` does.
+    fn validate(&self, _config: &serde_json::Value) -> Result<(), SystemActorError> {
+        Ok(())
+    }
 }
 
 #[derive(Default, Clone)]
@@ -37,6 +52,46 @@ impl ActorTagRegistry {
         Ok(())
     }
 
+    /// JSON Schema of the config for an actor tag
+    pub async fn schema(&self, tag: impl Into<Cow<'static, str>>) -> Result<serde_json::Value, SystemActorError> {
+        let tag = tag.into();
+        let map = self.map.read().await;
+        let factory = map.get(&tag).ok_or(SystemActorError::ActorTagNotFound(tag.clone()))?;
+        Ok(factory.config_schema())
+    }
+
+    /// JSON Schemas of the configs for all the registered actor tags
+    pub async fn schemas(&self) -> BTreeMap<Cow<'static, str>, serde_json::Value> {
+        let map = self.map.read().await;
+        map.iter().map(|(tag, factory)| (tag.clone(), factory.config_schema())).collect()
+    }
+
+    /// Checks a config for an actor tag without spawning an actor
+    ///
+    /// # Errors
+    ///
+    /// * `ActorTagNotFound` if no factory is registered for the tag.
+    /// * `InvalidActorConfig` if the factory rejects the config.
+    pub async fn validate(
+        &self,
+        tag: impl Into<Cow<'static, str>>,
+        config: &serde_json::Value,
+    ) -> Result<(), SystemActorError> {
+        let tag = tag.into();
+        let map = self.map.read().await;
+        let factory = map.get(&tag).ok_or(SystemActorError::ActorTagNotFound(tag.clone()))?;
+        factory.validate(config).map_err(|e| SystemActorError::InvalidActorConfig(tag, e.to_string().into()))
+    }
+
+    /// Spawns an actor with the factory registered for a tag
+    ///
+    /// The config is validated before spawning.
+    ///
+    /// # Errors
+    ///
+    /// * `ActorTagNotFound` if no factory is registered for the tag.
+    /// * `InvalidActorConfig` if the factory rejects the config.
+    /// * `ActorSpawn` if the factory fails to spawn the actor.
     pub async fn spawn(
         &self,
         tag: impl Into<Cow<'static, str>>,
@@ -47,15 +102,79 @@ impl ActorTagRegistry {
     ) -> Result<ActorHandle, SystemActorError> {
         let tag = tag.into();
         let factory = self.map.read().await;
-        let factory = factory.get(&tag).ok_or(SystemActorError::ActorTagNotFound(tag))?;
-        let res = factory.spawn(engine, config, id, options);
-        if let Err(e) = &res {
-            panic!("Error spawning actor: {:?}", e);
+        let factory = factory.get(&tag).ok_or(SystemActorError::ActorTagNotFound(tag.clone()))?;
+        factory
+            .validate(&config)
+            .map_err(|e| SystemActorError::InvalidActorConfig(tag.clone(), e.to_string().into()))?;
+
+        // Keep what's needed to reactivate a virtual actor once it's passivated
+        let mut reactivating = false;
+        if let Some(passivation) = options.passivation() {
+            let activation_id = id.activation_id();
+            let existing: Option<ActivationRecord> = engine.db().lock().await.select(&activation_id).await?;
+            reactivating = existing.is_some();
+            let record = ActivationRecord {
+                id: activation_id.clone(),
+                tag: tag.clone(),
+                config: config.clone(),
+                health_config: options.health_config().cloned(),
+                lease: options.lease().cloned(),
+                concurrency: options.concurrency(),
+                rate_limit: options.rate_limit().cloned(),
+                passivation: passivation.clone(),
+                active: true,
+            };
+            let _: Option<ActivationRecord> = engine.db().lock().await.upsert(&activation_id).content(record).await?;
+            engine.virtual_actors().set(&id, true);
         }
-        res
+
+        let is_virtual = options.passivation().is_some();
+        match factory.spawn(engine.clone(), config, id.clone(), options) {
+            Ok(handle) if is_virtual => Ok(watch_activation(engine, id, handle)),
+            Ok(handle) => Ok(handle),
+            Err(e) => {
+                error!("Error spawning actor {} with tag {}: {}", id, tag, e);
+                // A virtual actor that fails to reactivate stays passivated, so a later message can try again
+                if reactivating {
+                    deactivate(&engine, &id).await?;
+                } else if is_virtual {
+                    let _: Option<ActivationRecord> = engine.db().lock().await.delete(&id.activation_id()).await?;
+                }
+                Err(SystemActorError::ActorSpawn(id, tag, Box::new(e)))
+            }
+        }
+    }
+}
+
+/// Aborts a task when dropped
+struct AbortOnDrop(ActorHandle);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
     }
 }
 
+/// Marks a virtual actor as passivated when its task fails, so the next message reactivates it
+///
+/// Aborting the returned handle aborts the actor's task.
+fn watch_activation(engine: Engine, id: ActorId, handle: ActorHandle) -> ActorHandle {
+    let mut task = AbortOnDrop(handle);
+    tokio::spawn(async move {
+        let result = match (&mut task.0).await {
+            Ok(result) => result,
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = &result {
+            error!("Virtual actor {} stopped with an error: {}", id, e);
+            if let Err(e) = deactivate(&engine, &id).await {
+                error!("Error passivating actor {}: {}", id, e);
+            }
+        }
+        result
+    })
+}
+
 impl std::fmt::Debug for ActorTagRegistry {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "ActorRegistry")
@@ -0,0 +1,145 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+use surrealdb::sql;
+
+/// How long a message is tracked without getting its final reply, so messages that never get one are forgotten
+const PENDING_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// Messages of one type sent from an actor to another during a window
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FlowEdge {
+    /// Name of the sender
+    pub tx: String,
+    /// Name of the receiver
+    pub rx: String,
+    /// Message name
+    pub name: Cow<'static, str>,
+    /// Messages sent
+    pub sent: u64,
+    /// Messages that got their final reply without an error
+    pub replied: u64,
+    /// Messages that got an error reply
+    pub failed: u64,
+    /// Average time from sending a message to its final reply, in milliseconds
+    ///
+    /// Only covers the messages sent while the flow was observed.
+    pub avg_latency_ms: Option<u64>,
+    /// Longest time from sending a message to its final reply, in milliseconds
+    pub max_latency_ms: Option<u64>,
+}
+
+/// Messages sent between actors during a window of time
+///
+/// Replies are counted in the window they arrive in, so an edge can have replies without messages sent.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MessageFlow {
+    /// End of the window
+    pub at: sql::Datetime,
+    /// Length of the window, in milliseconds
+    pub window_ms: u64,
+    /// Edges with messages or replies in the window, sorted by sender, receiver and message name
+    pub edges: Vec<FlowEdge>,
+}
+
+/// Message or reply frame, as observed by `Engine::message_flow`
+#[derive(Debug, Deserialize)]
+pub(crate) struct FlowFrame {
+    id: String,
+    #[serde(default)]
+    chunk: Option<u64>,
+    name: Cow<'static, str>,
+    tx: String,
+    rx: String,
+    #[serde(default)]
+    err: Value,
+}
+
+#[derive(Default)]
+struct EdgeStats {
+    sent: u64,
+    replied: u64,
+    failed: u64,
+    latencies: u64,
+    total_latency: Duration,
+    max_latency: Option<Duration>,
+}
+
+/// A message waiting for its final reply
+struct PendingMessage {
+    /// When the message was observed, or its first reply chunk for messages sent before
+    seen: Instant,
+    /// Whether the message itself was observed, so `seen` is when it was sent
+    sent: bool,
+    /// Whether a reply chunk had an error
+    failed: bool,
+}
+
+impl PendingMessage {
+    fn new(sent: bool) -> Self {
+        Self { seen: Instant::now(), sent, failed: false }
+    }
+}
+
+/// Aggregates messages and replies into edges until the end of a window
+#[derive(Default)]
+pub(crate) struct FlowAggregator {
+    edges: BTreeMap<(String, String, Cow<'static, str>), EdgeStats>,
+    /// Messages waiting for their final reply, by message id
+    pending: HashMap<String, PendingMessage>,
+}
+
+impl FlowAggregator {
+    pub(crate) fn sent(&mut self, frame: FlowFrame) {
+        self.pending.insert(frame.id, PendingMessage::new(true));
+        self.edges.entry((frame.tx, frame.rx, frame.name)).or_default().sent += 1;
+    }
+
+    pub(crate) fn replied(&mut self, frame: FlowFrame) {
+        let failed = !frame.err.is_null();
+        if frame.chunk.is_some() {
+            if failed {
+                self.pending.entry(frame.id).or_insert_with(|| PendingMessage::new(false)).failed = true;
+            }
+            return;
+        }
+
+        // Replies go from the receiver of the message back to its sender
+        let pending = self.pending.remove(&frame.id);
+        let failed = pending.as_ref().is_some_and(|pending| pending.failed) || failed;
+        let latency = pending.filter(|pending| pending.sent).map(|pending| pending.seen.elapsed());
+        let stats = self.edges.entry((frame.rx, frame.tx, frame.name)).or_default();
+        if failed {
+            stats.failed += 1;
+        } else {
+            stats.replied += 1;
+        }
+        if let Some(latency) = latency {
+            stats.latencies += 1;
+            stats.total_latency += latency;
+            stats.max_latency = stats.max_latency.max(Some(latency));
+        }
+    }
+
+    /// Ends the window, returning its flow and starting a new one
+    pub(crate) fn flush(&mut self, window: Duration) -> MessageFlow {
+        self.pending.retain(|_, pending| pending.seen.elapsed() < PENDING_MAX_AGE);
+        let edges = std::mem::take(&mut self.edges)
+            .into_iter()
+            .map(|((tx, rx, name), stats)| FlowEdge {
+                tx,
+                rx,
+                name,
+                sent: stats.sent,
+                replied: stats.replied,
+                failed: stats.failed,
+                avg_latency_ms: (stats.latencies > 0)
+                    .then(|| (stats.total_latency / stats.latencies as u32).as_millis() as u64),
+                max_latency_ms: stats.max_latency.map(|latency| latency.as_millis() as u64),
+            })
+            .collect();
+        MessageFlow { at: sql::Datetime::default(), window_ms: window.as_millis() as u64, edges }
+    }
+}
@@ -0,0 +1,98 @@
+use crate::actor::{FrameMessage, FrameReply, SystemActorError};
+use std::sync::{Arc, RwLock};
+use tracing::debug;
+
+/// Middleware around the messages going through an engine
+///
+/// Interceptors are added with `Engine::add_interceptor` and called in the order they were added,
+/// for every actor using the engine. They suit cross-cutting concerns such as audit logging,
+/// payload redaction, authorization, metrics or schema validation.
+/// The content of frames with a binary `Encoding` is a base64 string, decode it with `frame.encoding`.
+///
+/// Returning an error rejects the message:
+/// - In `on_send`, the send fails with the error and the message is not stored.
+/// - In `on_recv`, the actor never sees the message and the sender gets the error as reply.
+///
+/// # Example
+///
+/// ```rust
+/// struct Redact;
+///
+/// impl Interceptor for Redact {
+///     fn on_send(&self, frame: &mut FrameMessage) -> Result<(), SystemActorError> {
+///         if let Some(password) = frame.msg.get_mut("password") {
+///             *password = "***".into();
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// engine.add_interceptor(Redact);
+/// ```
+pub trait Interceptor: Send + Sync {
+    /// Called before a message is stored or its receiver is reactivated, it can modify or reject the message
+    fn on_send(&self, _frame: &mut FrameMessage) -> Result<(), SystemActorError> {
+        Ok(())
+    }
+
+    /// Called before `ActorContext::recv` yields a message, it can modify or reject the message
+    fn on_recv(&self, _frame: &mut FrameMessage) -> Result<(), SystemActorError> {
+        Ok(())
+    }
+
+    /// Called for every reply received while waiting for the replies to a message, including the final one
+    ///
+    /// Replies relayed by `ActorContext::forward` are seen by the forwarding actor's engine too.
+    fn on_reply(&self, _reply: &FrameReply) {}
+}
+
+/// The interceptors added to an engine
+#[derive(Clone, Default)]
+pub(crate) struct Interceptors {
+    chain: Arc<RwLock<Vec<Arc<dyn Interceptor>>>>,
+}
+
+impl std::fmt::Debug for Interceptors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Interceptors({})", self.chain().len())
+    }
+}
+
+impl Interceptors {
+    pub(crate) fn add(&self, interceptor: Arc<dyn Interceptor>) {
+        self.chain.write().unwrap_or_else(|e| e.into_inner()).push(interceptor);
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.chain.read().unwrap_or_else(|e| e.into_inner()).is_empty()
+    }
+
+    /// Snapshot of the chain, so no lock is held while interceptors run
+    fn chain(&self) -> Vec<Arc<dyn Interceptor>> {
+        self.chain.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub(crate) fn on_send(&self, frame: &mut FrameMessage) -> Result<(), SystemActorError> {
+        for interceptor in self.chain() {
+            interceptor
+                .on_send(frame)
+                .inspect_err(|e| debug!("msg-send-rejected {} {} {}", frame.name, frame.rx, e))?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn on_recv(&self, frame: &mut FrameMessage) -> Result<(), SystemActorError> {
+        for interceptor in self.chain() {
+            interceptor
+                .on_recv(frame)
+                .inspect_err(|e| debug!("msg-recv-rejected {} {} {}", frame.name, frame.rx, e))?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn on_reply(&self, reply: &FrameReply) {
+        for interceptor in self.chain() {
+            interceptor.on_reply(reply);
+        }
+    }
+}
@@ -1,14 +1,39 @@
 mod actor;
+mod encoding;
+mod encryption;
 mod engine;
+mod error;
 mod factory;
+mod flow;
+mod interceptor;
+mod migration;
+mod rate_limit;
+mod retry;
+mod router;
+mod schema;
 mod util;
 
 pub use crate::actor::{
-    Actor, ActorContext, ActorError, ActorId, FrameMessage, HealthConfig, Message, MessageType, SendOptions,
-    SpawnExistsOptions, SpawnOptions, SystemActorError,
+    Actor, ActorContext, ActorError, ActorHealth, ActorId, FrameMessage, FrameReply, GatherOptions, GatherReplies,
+    GatherStrategy, HealthConfig, HealthProbe, LeaseConfig, LeaseConflict, Message, MessageReplies, MessageType,
+    PassivationConfig, Readiness, RequestStream, SendOptions, SharedMessage, SpawnExistsOptions, SpawnOptions,
+    StateStream, StreamMessage, SystemActorError, TargetReply,
 };
-pub use crate::engine::{Engine, EngineOptions, Record};
+pub use crate::encoding::{Encoding, EncodingFormat};
+pub use crate::encryption::{Encryption, KeyProvider, StaticKeys};
+pub use crate::engine::{
+    ActorTable, ActorTree, Engine, EngineOptions, HealthReport, HealthStream, MessageFlowStream, ObjectStoreOptions,
+    Record,
+};
+pub use crate::error::{variant_name, ErrorKind, ErrorReply};
 pub use crate::factory::{ActorFactory, ActorHandle, ActorTagRegistry};
+pub use crate::flow::{FlowEdge, MessageFlow};
+pub use crate::interceptor::Interceptor;
+pub use crate::migration::{Migration, Migrations};
+pub use crate::rate_limit::{RateLimit, RateLimitAction, RateLimitStatus};
+pub use crate::retry::{CircuitBreakerConfig, RetryOn, RetryPolicy};
+pub use crate::router::{GetRouterStatus, ResizeRouter, RouteStrategy, Router, RouterStatus, WorkerStatus};
+pub use crate::schema::{MessageRegistry, MessageSchema};
 pub use crate::util::Relay;
 pub use futures::{Future, StreamExt};
 
@@ -0,0 +1,120 @@
+use crate::actor::SystemActorError;
+use std::borrow::Cow;
+use surrealdb::{engine::any::Any, Surreal};
+use tracing::info;
+
+/// A versioned SurrealQL script changing the schema of a component
+#[derive(Clone, Debug)]
+pub struct Migration {
+    /// Version the schema is at once the script ran, starting at 1
+    pub version: u32,
+    /// Short description of the change
+    pub name: Cow<'static, str>,
+    /// SurrealQL statements, without transaction statements
+    pub script: Cow<'static, str>,
+}
+
+/// Ordered migrations of a component's schema
+///
+/// The version of each component is tracked in the `schema_version` table, with a record per applied migration.
+/// Applying the migrations runs the ones newer than the current version, in order, each in its own transaction
+/// together with its `schema_version` record. A failed migration is rolled back and stops the ones after it,
+/// unless another engine applied it in the meantime, in which case it's skipped.
+///
+/// # Example
+///
+/// ```rust
+/// let migrations = Migrations::new("notes")
+///     .migration(1, "init", "DEFINE TABLE note TYPE NORMAL SCHEMALESS;")
+///     .migration(2, "note_title", "DEFINE FIELD title ON note TYPE string;");
+/// let applied = engine.migrate(&migrations).await?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct Migrations {
+    component: Cow<'static, str>,
+    migrations: Vec<Migration>,
+}
+
+impl Migrations {
+    /// Creates an empty list of migrations for a component, such as a crate or a table prefix
+    pub fn new(component: impl Into<Cow<'static, str>>) -> Self {
+        Self { component: component.into(), migrations: Vec::new() }
+    }
+
+    /// Adds a migration
+    pub fn migration(
+        mut self,
+        version: u32,
+        name: impl Into<Cow<'static, str>>,
+        script: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        self.migrations.push(Migration { version, name: name.into(), script: script.into() });
+        self
+    }
+
+    /// Component the migrations belong to
+    pub fn component(&self) -> &str {
+        &self.component
+    }
+
+    /// Migrations, in the order they were added
+    pub fn migrations(&self) -> &[Migration] {
+        &self.migrations
+    }
+
+    /// Version of the latest migration
+    pub fn latest(&self) -> Option<u32> {
+        self.migrations.iter().map(|migration| migration.version).max()
+    }
+
+    /// Applies the pending migrations, returning the versions applied
+    pub(crate) async fn apply(&self, db: &Surreal<Any>) -> Result<Vec<u32>, SystemActorError> {
+        let mut migrations: Vec<&Migration> = self.migrations.iter().collect();
+        migrations.sort_by_key(|migration| migration.version);
+        if let Some(pair) = migrations.windows(2).find(|pair| pair[0].version == pair[1].version) {
+            return Err(self.failed(pair[1].version, "duplicate version".to_string()));
+        }
+
+        let current = schema_version(db, &self.component).await?.unwrap_or(0);
+        let mut applied = Vec::new();
+        for migration in migrations.into_iter().filter(|migration| migration.version > current) {
+            let query = include_str!("../sql/migrate.surql").replace("{script}", &migration.script);
+            let mut res = db
+                .query(query)
+                .bind(("component", self.component.to_string()))
+                .bind(("version", migration.version))
+                .bind(("name", migration.name.to_string()))
+                .await?;
+            let mut errors: Vec<_> = res.take_errors().into_iter().collect();
+            if !errors.is_empty() {
+                // Another engine applied it first, its `schema_version` record then fails ours
+                let version = schema_version(db, &self.component).await?;
+                if version.is_some_and(|version| version >= migration.version) {
+                    info!("Migration {} {} of {} already applied", migration.version, migration.name, self.component);
+                    continue;
+                }
+                errors.sort_by_key(|(index, _)| *index);
+                let errors: Vec<String> = errors.into_iter().map(|(_, error)| error.to_string()).collect();
+                return Err(self.failed(migration.version, errors.join("; ")));
+            }
+            info!("Applied migration {} {} of {}", migration.version, migration.name, self.component);
+            applied.push(migration.version);
+        }
+        Ok(applied)
+    }
+
+    fn failed(&self, version: u32, error: String) -> SystemActorError {
+        SystemActorError::MigrationFailed(self.component.clone(), version, error)
+    }
+}
+
+/// Version of a component's schema, None if no migration was applied
+pub(crate) async fn schema_version(db: &Surreal<Any>, component: &str) -> Result<Option<u32>, SystemActorError> {
+    db.query(include_str!("../sql/schema_version.surql")).await?.check()?;
+    let mut res = db
+        .query("SELECT VALUE version FROM schema_version WHERE component = $component ORDER BY version DESC LIMIT 1")
+        .bind(("component", component.to_string()))
+        .await?;
+    let versions: Vec<u32> = res.take(0)?;
+    Ok(versions.first().copied())
+}
@@ -0,0 +1,207 @@
+use crate::actor::{reply_error, ActorId, FrameMessage, MessageStream, SystemActorError};
+use crate::engine::Engine;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use surrealdb::sql;
+use tokio::sync::mpsc;
+use tracing::debug;
+
+/// What happens to a message arriving while the actor's rate limit is exceeded
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitAction {
+    /// Hold the message until the bucket has a token, rejecting it if `max_queue` messages are already held
+    #[default]
+    Delay,
+    /// Reject the message right away
+    Reject,
+}
+
+/// Token-bucket rate limit on the messages an actor handles
+///
+/// The bucket holds up to `burst` tokens and gets `messages` tokens every `period`. Each message
+/// the actor receives takes a token. Messages arriving with the bucket empty are delayed or rejected,
+/// see `RateLimitAction`. Rejected senders get `SystemActorError::RateLimited` as an error reply,
+/// with a transient `ErrorKind`.
+///
+/// The current rate and the number of delayed messages are reported in the actor's health record
+/// on every health update, see `ActorHealth::rate_limit`.
+///
+/// # Example
+///
+/// ```rust
+/// // At most 2 requests per second to the chat model, with bursts of 5
+/// let rate_limit = RateLimit::builder().messages(2).burst(5).build();
+/// let options = SpawnOptions::builder().rate_limit(rate_limit).build();
+/// ```
+#[derive(bon::Builder, Clone, Debug, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// Messages allowed every `period`
+    pub messages: u32,
+    /// Period over which `messages` are allowed
+    #[builder(default = sql::Duration::from_secs(1))]
+    pub period: sql::Duration,
+    /// Messages allowed at once after a quiet time, `messages` if None
+    pub burst: Option<u32>,
+    /// What happens to messages arriving while the limit is exceeded
+    #[builder(default)]
+    #[serde(default)]
+    pub on_limit: RateLimitAction,
+    /// Maximum number of delayed messages
+    #[builder(default = 100)]
+    #[serde(default = "default_max_queue")]
+    pub max_queue: usize,
+}
+
+fn default_max_queue() -> usize {
+    100
+}
+
+/// State of an actor's rate limit, as reported in its health record
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct RateLimitStatus {
+    /// Messages let through during the last period
+    pub rate: u32,
+    /// Messages delayed, waiting for a token
+    pub queued: usize,
+    /// Messages rejected since the actor started
+    pub rejected: u64,
+}
+
+/// Tokens left, and when the last messages were let through
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled: Instant,
+    passed: VecDeque<Instant>,
+}
+
+/// The rate limit of a running actor
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    config: RateLimit,
+    bucket: Mutex<Bucket>,
+    queued: AtomicUsize,
+    rejected: AtomicU64,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(config: RateLimit) -> Self {
+        let tokens = config.burst.unwrap_or(config.messages).max(1) as f64;
+        let bucket = Bucket { tokens, refilled: Instant::now(), passed: VecDeque::new() };
+        Self { config, bucket: Mutex::new(bucket), queued: AtomicUsize::new(0), rejected: AtomicU64::new(0) }
+    }
+
+    fn period(&self) -> Duration {
+        self.config.period.into()
+    }
+
+    /// Takes a token if there is one, otherwise returns how long until there is
+    fn take(&self) -> Result<(), Duration> {
+        let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let period = self.period();
+        let capacity = self.config.burst.unwrap_or(self.config.messages).max(1) as f64;
+        let per_second = self.config.messages.max(1) as f64 / period.as_secs_f64().max(f64::EPSILON);
+
+        bucket.tokens = (bucket.tokens + now.duration_since(bucket.refilled).as_secs_f64() * per_second).min(capacity);
+        bucket.refilled = now;
+
+        if bucket.tokens < 1.0 {
+            return Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_second));
+        }
+        bucket.tokens -= 1.0;
+        bucket.passed.push_back(now);
+        while bucket.passed.front().is_some_and(|passed| now.duration_since(*passed) > period) {
+            bucket.passed.pop_front();
+        }
+        Ok(())
+    }
+
+    /// Waits for a token
+    async fn acquire(&self) {
+        while let Err(wait) = self.take() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    pub(crate) fn status(&self) -> RateLimitStatus {
+        let now = Instant::now();
+        let period = self.period();
+        let bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+        let rate = bucket.passed.iter().filter(|passed| now.duration_since(**passed) <= period).count();
+        RateLimitStatus {
+            rate: rate as u32,
+            queued: self.queued.load(Ordering::SeqCst),
+            rejected: self.rejected.load(Ordering::SeqCst),
+        }
+    }
+
+    /// Whether a message arriving now is let through or queued, rejecting it otherwise
+    fn admit(&self, engine: &Engine, id: &ActorId, frame: FrameMessage) -> Option<FrameMessage> {
+        let admitted = match self.config.on_limit {
+            RateLimitAction::Reject => self.take().is_ok(),
+            RateLimitAction::Delay => self
+                .queued
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |queued| {
+                    (queued < self.config.max_queue).then_some(queued + 1)
+                })
+                .is_ok(),
+        };
+        if admitted {
+            return Some(frame);
+        }
+        debug!("[{}] rate-limit-reject {} {}", id.record_id(), frame.name, frame.id());
+        self.rejected.fetch_add(1, Ordering::SeqCst);
+        reply_error(engine.clone(), frame, &SystemActorError::RateLimited(id.clone()));
+        None
+    }
+
+    /// Applies the rate limit to an actor's message stream
+    ///
+    /// Messages are taken from the stream as they arrive, so excess messages can be rejected right away,
+    /// and the ones let through are yielded once they have a token.
+    pub(crate) fn limit(self: Arc<Self>, engine: Engine, id: ActorId, mut stream: MessageStream) -> MessageStream {
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        let limiter = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let item = tokio::select! {
+                    item = stream.next() => item,
+                    // The actor stopped receiving messages
+                    _ = tx.closed() => break,
+                };
+                let item = match item {
+                    Some(Ok(frame)) => match limiter.admit(&engine, &id, frame) {
+                        Some(frame) => Ok(frame),
+                        None => continue,
+                    },
+                    Some(Err(e)) => Err(e),
+                    None => break,
+                };
+                if tx.send(item).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let received = futures::stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|item| (item, rx)) });
+        let stream = received.then(move |item| {
+            let limiter = self.clone();
+            async move {
+                // Delayed messages wait for their token
+                if item.is_ok() && limiter.config.on_limit == RateLimitAction::Delay {
+                    limiter.acquire().await;
+                    limiter.queued.fetch_sub(1, Ordering::SeqCst);
+                }
+                item
+            }
+        });
+        Box::pin(stream)
+    }
+}
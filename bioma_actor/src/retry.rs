@@ -0,0 +1,181 @@
+use crate::actor::{ActorId, SystemActorError};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// Kinds of `SystemActorError` a `RetryPolicy` can retry
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RetryOn {
+    /// `MessageTimeout`, the target didn't reply in time
+    Timeout,
+    /// `UnhealthyActor`, the target failed its health check
+    Unhealthy,
+    /// `CircuitOpen`, the target's circuit breaker is failing fast
+    CircuitOpen,
+    /// `ErrorReply`, the target replied with an error
+    Reply,
+    /// `EngineError`, the database failed
+    Engine,
+}
+
+impl RetryOn {
+    /// Whether an error is of this kind
+    pub fn matches(&self, error: &SystemActorError) -> bool {
+        matches!(
+            (self, error),
+            (RetryOn::Timeout, SystemActorError::MessageTimeout(_, _))
+                | (RetryOn::Unhealthy, SystemActorError::UnhealthyActor(_))
+                | (RetryOn::CircuitOpen, SystemActorError::CircuitOpen(_))
+                | (RetryOn::Reply, SystemActorError::ErrorReply(_))
+                | (RetryOn::Engine, SystemActorError::EngineError(_))
+        )
+    }
+}
+
+/// Policy for sending a message again when an attempt fails
+///
+/// An attempt fails when the message can't be sent, or when its first reply is an error.
+/// Once a reply was received the message is never sent again, so streamed replies aren't duplicated.
+/// Sends without an idempotency key get one for all their attempts, so a message whose reply timed out
+/// isn't handled twice. A new key is used after an error reply, since the target handled that attempt.
+///
+/// # Example
+///
+/// ```rust
+/// let retry = RetryPolicy::builder()
+///     .max_attempts(5)
+///     .initial_backoff(Duration::from_millis(200))
+///     .retry_on(vec![RetryOn::Timeout, RetryOn::Unhealthy])
+///     .build();
+/// let options = SendOptions::builder().timeout(Duration::from_secs(5)).retry(retry).build();
+/// ```
+#[derive(bon::Builder, Clone, Debug)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first one
+    #[builder(default = 3)]
+    pub max_attempts: u32,
+    /// Delay before the second attempt
+    #[builder(default = Duration::from_millis(100))]
+    pub initial_backoff: Duration,
+    /// Factor applied to the delay after each attempt
+    #[builder(default = 2.0)]
+    pub multiplier: f64,
+    /// Upper bound of the delay between attempts
+    #[builder(default = Duration::from_secs(10))]
+    pub max_backoff: Duration,
+    /// Kinds of errors worth another attempt
+    #[builder(default = vec![RetryOn::Timeout, RetryOn::Unhealthy])]
+    pub retry_on: Vec<RetryOn>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+impl RetryPolicy {
+    /// Whether to make another attempt after the given attempt failed with an error
+    pub fn should_retry(&self, attempt: u32, error: &SystemActorError) -> bool {
+        attempt < self.max_attempts && self.retry_on.iter().any(|kind| kind.matches(error))
+    }
+
+    /// Delay before the attempt following the given one
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = self.multiplier.powi(attempt.saturating_sub(1) as i32);
+        self.initial_backoff.mul_f64(factor).min(self.max_backoff)
+    }
+}
+
+/// Configuration of the circuit breaker guarding sends to a target
+///
+/// After `failure_threshold` timeouts or failed health checks in a row the circuit opens, and sends
+/// to the target fail right away with `CircuitOpen`. Once `cool_down` has passed a single send probes
+/// the target: the circuit closes again if the probe gets a reply, and opens for another `cool_down` if it fails.
+/// A probe that gets no outcome within `cool_down`, e.g. because it was cancelled, lets the next send probe instead.
+///
+/// Circuits are kept per target by the engine, so all the actors of a process share them.
+#[derive(bon::Builder, Clone, Debug)]
+pub struct CircuitBreakerConfig {
+    /// Number of failures in a row opening the circuit
+    #[builder(default = 5)]
+    pub failure_threshold: u32,
+    /// How long the circuit stays open before probing the target
+    #[builder(default = Duration::from_secs(30))]
+    pub cool_down: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+#[derive(Clone, Debug)]
+enum CircuitState {
+    /// Sends go through, counting failures in a row
+    Closed { failures: u32 },
+    /// Sends fail fast until the cool-down is over
+    Open { until: Instant },
+    /// A probe is in flight until the deadline, other sends fail fast
+    HalfOpen { until: Instant },
+}
+
+/// Circuit breakers of the targets of the sends in a process
+///
+/// Only targets with failures are tracked, a closed circuit without failures is dropped.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct CircuitBreakers {
+    circuits: Arc<Mutex<HashMap<ActorId, CircuitState>>>,
+}
+
+impl CircuitBreakers {
+    /// Checks whether a send to a target can go through
+    ///
+    /// Moves an open circuit whose cool-down is over to half-open, letting this send probe the target.
+    /// So does a half-open circuit whose probe is past its deadline.
+    pub(crate) fn check(&self, to: &ActorId, config: &CircuitBreakerConfig) -> Result<(), SystemActorError> {
+        let mut circuits = self.circuits.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(state) = circuits.get_mut(to) else {
+            return Ok(());
+        };
+        match *state {
+            CircuitState::Closed { .. } => Ok(()),
+            CircuitState::Open { until } | CircuitState::HalfOpen { until } if Instant::now() >= until => {
+                debug!("circuit-half-open {}", to);
+                *state = CircuitState::HalfOpen { until: Instant::now() + config.cool_down };
+                Ok(())
+            }
+            CircuitState::Open { .. } | CircuitState::HalfOpen { .. } => Err(SystemActorError::CircuitOpen(to.clone())),
+        }
+    }
+
+    /// Records the outcome of a send to a target
+    ///
+    /// Only timeouts and failed health checks count as failures, any other outcome shows the target is responsive.
+    pub(crate) fn record(&self, to: &ActorId, config: &CircuitBreakerConfig, error: Option<&SystemActorError>) {
+        let mut circuits = self.circuits.lock().unwrap_or_else(|e| e.into_inner());
+        let failed =
+            matches!(error, Some(SystemActorError::MessageTimeout(_, _) | SystemActorError::UnhealthyActor(_)));
+        if !failed {
+            circuits.remove(to);
+            return;
+        }
+        let state = circuits.entry(to.clone()).or_insert(CircuitState::Closed { failures: 0 });
+        *state = match &*state {
+            CircuitState::Closed { failures } if failures + 1 < config.failure_threshold => {
+                CircuitState::Closed { failures: failures + 1 }
+            }
+            _ => {
+                debug!("circuit-open {} {:?}", to, config.cool_down);
+                CircuitState::Open { until: Instant::now() + config.cool_down }
+            }
+        };
+    }
+
+    /// Drops the circuit of a target, such as a killed actor
+    pub(crate) fn forget(&self, id: &ActorId) {
+        self.circuits.lock().unwrap_or_else(|e| e.into_inner()).remove(id);
+    }
+}
@@ -0,0 +1,296 @@
+use crate::actor::kill_actor;
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::Arc;
+use surrealdb::sql;
+use tokio::sync::watch;
+use tracing::{debug, error};
+
+/// Number of points each worker gets on the consistent hash ring
+const HASH_RING_POINTS: usize = 64;
+
+/// How a router picks the worker for a message
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RouteStrategy {
+    /// Each worker in turn
+    #[default]
+    RoundRobin,
+    /// The worker with the fewest messages in flight
+    LeastPending,
+    /// The same worker for the same key, moving as few keys as possible when the pool is resized
+    ConsistentHash {
+        /// JSON pointer to the key in plain JSON messages, the whole message is the key if not set
+        #[serde(default)]
+        key: Option<String>,
+    },
+}
+
+/// Resizes the pool of a `Router`, replying with its status
+///
+/// New workers are spawned right away. Removed workers stop receiving messages,
+/// and are killed once their messages in flight are handled.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ResizeRouter {
+    pub size: usize,
+}
+
+/// Asks a `Router` for its status
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetRouterStatus;
+
+/// Status of a `Router` and its pool
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RouterStatus {
+    pub strategy: RouteStrategy,
+    pub workers: Vec<WorkerStatus>,
+}
+
+/// Status of a worker in the pool of a `Router`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WorkerStatus {
+    pub id: ActorId,
+    /// Messages forwarded to the worker and not replied yet
+    pub pending: usize,
+}
+
+/// A worker spawned by a router
+#[derive(Debug)]
+struct Worker {
+    id: ActorId,
+    handle: ActorHandle,
+    pending: Arc<watch::Sender<usize>>,
+}
+
+/// An actor spreading messages across a pool of workers of one tag
+///
+/// Workers are spawned through the `ActorTagRegistry` factory of the tag, as children of the router,
+/// so they stop along with it. Messages sent to the router are forwarded to a worker picked by the strategy,
+/// and the worker's replies are relayed back to the sender, so the router can be used like any of its workers.
+/// The router handles `ResizeRouter` and `GetRouterStatus` itself.
+///
+/// # Example
+///
+/// ```rust
+/// let router = Router::builder().tag("bioma_llm::embeddings::Embeddings").config(config).size(4).build();
+/// let router_id = ActorId::of::<Router>("/embeddings");
+/// let (mut ctx, mut router) = Actor::spawn(engine.clone(), router_id.clone(), router, options).await?;
+/// tokio::spawn(async move { router.start(&mut ctx).await });
+///
+/// // Send to the router as if it was an embeddings actor
+/// let embeddings = relay_ctx.send_and_wait_reply::<Embeddings, GenerateEmbeddings>(message, &router_id, options).await?;
+/// ```
+#[derive(bon::Builder, Debug, Serialize, Deserialize)]
+pub struct Router {
+    /// Tag of the workers, as registered in the `ActorTagRegistry`
+    #[builder(into)]
+    pub tag: Cow<'static, str>,
+    /// Config the workers are spawned with
+    #[builder(default)]
+    #[serde(default)]
+    pub config: Value,
+    /// Number of workers in the pool
+    pub size: usize,
+    /// How the worker for a message is picked
+    #[builder(default)]
+    #[serde(default)]
+    pub strategy: RouteStrategy,
+    /// How long a worker can take between replies
+    #[builder(default = default_worker_timeout())]
+    #[serde(default = "default_worker_timeout")]
+    pub timeout: sql::Duration,
+    /// Workers in the pool
+    #[builder(skip)]
+    #[serde(skip)]
+    workers: Vec<Worker>,
+    /// Number of workers spawned so far, to name new ones
+    #[builder(skip)]
+    #[serde(skip)]
+    spawned: usize,
+    /// Next worker for round-robin
+    #[builder(skip)]
+    #[serde(skip)]
+    next: usize,
+    /// Consistent hash ring, from hash to worker index
+    #[builder(skip)]
+    #[serde(skip)]
+    ring: BTreeMap<u64, usize>,
+}
+
+fn default_worker_timeout() -> sql::Duration {
+    sql::Duration::from_secs(30)
+}
+
+impl Router {
+    /// Spawns or removes workers to reach the given pool size
+    ///
+    /// The pool keeps the workers spawned before an error.
+    async fn resize(&mut self, ctx: &mut ActorContext<Self>, size: usize) -> Result<(), SystemActorError> {
+        debug!("[{}] router-resize {} {}->{}", ctx.id().record_id(), self.tag, self.workers.len(), size);
+
+        let mut result = Ok(());
+        while self.workers.len() < size {
+            let name = format!("worker_{}", self.spawned);
+            let options = SpawnOptions::builder().exists(SpawnExistsOptions::Reset).build();
+            match ctx.spawn_child_tagged(self.tag.clone(), name, self.config.clone(), options).await {
+                Ok((id, handle)) => {
+                    self.spawned += 1;
+                    self.workers.push(Worker { id, handle, pending: Arc::new(watch::channel(0).0) });
+                }
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            }
+        }
+
+        while self.workers.len() > size {
+            let Some(worker) = self.workers.pop() else {
+                break;
+            };
+            // Let the worker finish its messages in flight before stopping and killing it
+            let stop = ctx.detach_child(&worker.id);
+            let engine = ctx.engine().clone();
+            tokio::spawn(async move {
+                let mut pending = worker.pending.subscribe();
+                let _ = pending.wait_for(|pending| *pending == 0).await;
+                match stop {
+                    Some(stop) => {
+                        stop.send_replace(true);
+                        let _ = worker.handle.await;
+                    }
+                    None => worker.handle.abort(),
+                }
+                if let Err(e) = kill_actor(&engine, &worker.id).await {
+                    error!("router-worker-kill {} {}", worker.id, e);
+                }
+            });
+        }
+
+        self.next = 0;
+        self.ring = self
+            .workers
+            .iter()
+            .enumerate()
+            .flat_map(|(index, worker)| {
+                (0..HASH_RING_POINTS).map(move |point| (hash_of(&(worker.id.name(), point)), index))
+            })
+            .collect();
+        result
+    }
+
+    /// Picks the worker for a message
+    fn pick(&mut self, frame: &FrameMessage) -> Option<&Worker> {
+        if self.workers.is_empty() {
+            return None;
+        }
+        let index = match &self.strategy {
+            RouteStrategy::RoundRobin => {
+                let index = self.next % self.workers.len();
+                self.next = index + 1;
+                index
+            }
+            RouteStrategy::LeastPending => {
+                self.workers.iter().enumerate().min_by_key(|(_, worker)| *worker.pending.borrow()).map(|(i, _)| i)?
+            }
+            RouteStrategy::ConsistentHash { key } => {
+                let key = match key {
+                    Some(pointer) => frame.msg.pointer(pointer).unwrap_or(&Value::Null),
+                    None => &frame.msg,
+                };
+                let hash = hash_of(&key.to_string());
+                self.ring.range(hash..).next().or_else(|| self.ring.iter().next()).map(|(_, index)| *index)?
+            }
+        };
+        self.workers.get(index)
+    }
+
+    /// Forwards a message to a worker of the pool
+    async fn route(&mut self, ctx: &ActorContext<Self>, frame: FrameMessage) {
+        let options = SendOptions::builder().timeout(self.timeout.into()).build();
+        let Some((id, pending)) = self.pick(&frame).map(|worker| (worker.id.clone(), worker.pending.clone())) else {
+            ctx.reject(frame, &SystemActorError::NoWorkers(ctx.id().clone()));
+            return;
+        };
+
+        debug!("[{}] router-route {} {} {}", ctx.id().record_id(), frame.name, frame.id, id.record_id());
+
+        pending.send_modify(|pending| *pending += 1);
+        let relay = ctx.forward(frame, &id, options).await;
+        tokio::spawn(async move {
+            let _ = relay.await;
+            pending.send_modify(|pending| *pending -= 1);
+        });
+    }
+
+    fn status(&self) -> RouterStatus {
+        let workers = self
+            .workers
+            .iter()
+            .map(|worker| WorkerStatus { id: worker.id.clone(), pending: *worker.pending.borrow() })
+            .collect();
+        RouterStatus { strategy: self.strategy.clone(), workers }
+    }
+}
+
+fn hash_of(value: &impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl Message<ResizeRouter> for Router {
+    type Response = RouterStatus;
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, message: &ResizeRouter) -> Result<(), SystemActorError> {
+        self.resize(ctx, message.size).await?;
+        self.size = message.size;
+        ctx.reply(self.status()).await?;
+        Ok(())
+    }
+}
+
+impl Message<GetRouterStatus> for Router {
+    type Response = RouterStatus;
+
+    async fn handle(
+        &mut self,
+        ctx: &mut ActorContext<Self>,
+        _message: &GetRouterStatus,
+    ) -> Result<(), SystemActorError> {
+        ctx.reply(self.status()).await?;
+        Ok(())
+    }
+}
+
+impl Actor for Router {
+    type Error = SystemActorError;
+
+    async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), SystemActorError> {
+        self.resize(ctx, self.size).await?;
+
+        let mut stream = ctx.recv().await?;
+        while let Some(Ok(frame)) = stream.next().await {
+            // Errors are replied to the sender, the router keeps running
+            let result = if let Some(message) = frame.is::<ResizeRouter>() {
+                self.reply(ctx, &message, &frame).await
+            } else if let Some(message) = frame.is::<GetRouterStatus>() {
+                self.reply(ctx, &message, &frame).await
+            } else {
+                self.route(ctx, frame).await;
+                Ok(())
+            };
+            if let Err(e) = result {
+                error!("[{}] router-error {}", ctx.id().record_id(), e);
+            }
+        }
+
+        // Stop the workers along with the router
+        self.resize(ctx, 0).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,109 @@
+use crate::actor::{Actor, Message, MessageType};
+use schemars::{gen::SchemaSettings, JsonSchema};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, RwLock};
+
+/// Name, schemas and handlers of a message type
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MessageSchema {
+    /// Name of the message in frames, see `FrameMessage::name`
+    pub name: Cow<'static, str>,
+    /// Type names of the actors handling the message, which is their tag when spawned with `ActorId::of`
+    pub actors: BTreeSet<Cow<'static, str>>,
+    /// JSON Schema of the message, see `FrameMessage::msg`
+    pub request: Value,
+    /// JSON Schema of each reply to the message
+    pub response: Value,
+}
+
+/// Registry of the message types actors handle, with their JSON Schemas
+///
+/// Lets clients that aren't written in Rust build and validate frames. The name of a message is the name
+/// frames carry, and its schemas are generated with `schemars`. A message handled by several actors
+/// is registered once, with the type names of all of them.
+///
+/// # Example
+///
+/// ```rust
+/// engine.messages().add::<Embeddings, TopK>();
+/// engine.messages().add::<Rerank, RankTexts>();
+/// let document = engine.messages().export();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MessageRegistry {
+    map: Arc<RwLock<BTreeMap<Cow<'static, str>, MessageSchema>>>,
+}
+
+impl MessageRegistry {
+    /// Registers a message handled by an actor, generating the schemas of the message and its response
+    pub fn add<T, MT>(&self)
+    where
+        T: Message<MT>,
+        MT: MessageType + JsonSchema,
+        T::Response: JsonSchema,
+    {
+        self.add_with_schemas::<T, MT>(schema_for::<MT>(), schema_for::<T::Response>());
+    }
+
+    /// Registers a message handled by an actor, with given schemas
+    ///
+    /// For messages or responses that don't implement `JsonSchema`, such as types from other crates.
+    pub fn add_with_schemas<T, MT>(&self, request: Value, response: Value)
+    where
+        T: Message<MT>,
+        MT: MessageType,
+    {
+        let name: Cow<'static, str> = std::any::type_name::<MT>().into();
+        let actor: Cow<'static, str> = std::any::type_name::<T>().into();
+        let mut map = self.map.write().unwrap_or_else(|e| e.into_inner());
+        map.entry(name.clone())
+            .or_insert_with(|| MessageSchema { name, actors: BTreeSet::new(), request, response })
+            .actors
+            .insert(actor);
+    }
+
+    /// Schemas of a message, by its frame name
+    pub fn get(&self, name: &str) -> Option<MessageSchema> {
+        self.map.read().unwrap_or_else(|e| e.into_inner()).get(name).cloned()
+    }
+
+    /// Schemas of all the registered messages, sorted by name
+    pub fn schemas(&self) -> Vec<MessageSchema> {
+        self.map.read().unwrap_or_else(|e| e.into_inner()).values().cloned().collect()
+    }
+
+    /// Messages handled by an actor
+    pub fn handled_by<T: Actor>(&self) -> Vec<MessageSchema> {
+        let actor = std::any::type_name::<T>();
+        self.schemas().into_iter().filter(|schema| schema.actors.contains(actor)).collect()
+    }
+
+    /// All the registered messages as one JSON document
+    ///
+    /// The document has a `messages` object with an entry per message name, holding the type names of
+    /// the actors handling it and the self-contained `request` and `response` schemas.
+    pub fn export(&self) -> Value {
+        let messages: serde_json::Map<String, Value> = self
+            .schemas()
+            .into_iter()
+            .map(|schema| {
+                let entry = serde_json::json!({
+                    "actors": schema.actors,
+                    "request": schema.request,
+                    "response": schema.response,
+                });
+                (schema.name.into_owned(), entry)
+            })
+            .collect();
+        serde_json::json!({ "messages": messages })
+    }
+}
+
+/// Draft 7 JSON Schema of a type, with its definitions
+fn schema_for<T: JsonSchema>() -> Value {
+    let schema = SchemaSettings::draft07().into_generator().into_root_schema_for::<T>();
+    serde_json::to_value(schema).unwrap_or(Value::Bool(true))
+}
@@ -187,6 +187,47 @@ async fn test_actor_multiple_messages() -> Result<(), TestError> {
     Ok(())
 }
 
+#[test(tokio::test)]
+async fn test_actor_replies_for_sent_message() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+
+    let test_actor_id = ActorId::of::<TestActor>("/test");
+    let (mut test_actor_ctx, mut test_actor) =
+        Actor::spawn(engine.clone(), test_actor_id.clone(), TestActor { count: 0 }, SpawnOptions::default()).await?;
+
+    let test_handle = tokio::spawn(async move {
+        if let Err(e) = test_actor.start(&mut test_actor_ctx).await {
+            eprintln!("TestActor error: {}", e);
+        }
+    });
+
+    let relay_actor_id = ActorId::of::<Relay>("/relay");
+    let (relay_actor_ctx, _relay_actor) =
+        Actor::spawn(engine.clone(), relay_actor_id.clone(), Relay, SpawnOptions::default()).await?;
+
+    // Fire and forget, keeping only the message id
+    let message = TestMessage { content: "Fetch me later".to_string() };
+    let message_id = relay_actor_ctx.do_send::<TestActor, TestMessage>(message, &test_actor_id).await?;
+
+    // Let the actor reply while nobody is listening
+    sleep(Duration::from_millis(500)).await;
+
+    let mut replies = relay_actor_ctx.replies_for::<TestResponse>(&message_id, SendOptions::default()).await?;
+    assert!(replies.finished);
+
+    let mut responses = Vec::new();
+    while let Some(response) = replies.stream.next().await {
+        responses.push(response?);
+    }
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0].content, "Received: Fetch me later");
+
+    test_handle.abort();
+
+    dbg_export_db!(engine);
+    Ok(())
+}
+
 #[test(tokio::test)]
 async fn test_actor_lifecycle() -> Result<(), TestError> {
     let engine = Engine::test().await?;
@@ -266,6 +307,141 @@ async fn test_actor_error_handling() -> Result<(), TestError> {
     Ok(())
 }
 
+// Error type senders can decode from the error replies
+#[derive(Debug, thiserror::Error, Serialize, Deserialize)]
+enum ValidationError {
+    #[error("System error: {0}")]
+    #[serde(skip)]
+    System(#[from] SystemActorError),
+    #[error("Value {value} is over {max}")]
+    OutOfRange { value: i64, max: i64 },
+}
+
+impl ActorError for ValidationError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            ValidationError::System(error) => error.kind(),
+            ValidationError::OutOfRange { .. } => ErrorKind::Invalid,
+        }
+    }
+
+    fn data(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ValidatingActor {
+    max: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Validate {
+    value: i64,
+}
+
+impl Message<Validate> for ValidatingActor {
+    type Response = i64;
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, message: &Validate) -> Result<(), ValidationError> {
+        if message.value > self.max {
+            return Err(ValidationError::OutOfRange { value: message.value, max: self.max });
+        }
+        ctx.reply(message.value).await?;
+        Ok(())
+    }
+}
+
+impl Actor for ValidatingActor {
+    type Error = ValidationError;
+
+    async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), ValidationError> {
+        let mut stream = ctx.recv().await?;
+        while let Some(Ok(frame)) = stream.next().await {
+            if let Some(message) = frame.is::<Validate>() {
+                // Errors are replied to the sender, keep handling messages
+                let _ = self.reply(ctx, &message, &frame).await;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test(tokio::test)]
+async fn test_actor_error_reply() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+
+    let validating_id = ActorId::of::<ValidatingActor>("/validating");
+    let (mut validating_ctx, mut validating_actor) =
+        Actor::spawn(engine.clone(), validating_id.clone(), ValidatingActor { max: 100 }, SpawnOptions::default())
+            .await?;
+    let validating_handle = tokio::spawn(async move {
+        if let Err(e) = validating_actor.start(&mut validating_ctx).await {
+            error!("ValidatingActor error: {}", e);
+        }
+    });
+
+    let error_actor_id = ActorId::of::<ErrorActor>("/error_actor");
+    let (mut error_actor_ctx, mut error_actor) =
+        Actor::spawn(engine.clone(), error_actor_id.clone(), ErrorActor, SpawnOptions::default()).await?;
+    let error_handle = tokio::spawn(async move {
+        let _ = error_actor.start(&mut error_actor_ctx).await;
+    });
+
+    let relay_id = ActorId::of::<Relay>("/relay");
+    let (relay_ctx, _relay_actor) =
+        Actor::spawn(engine.clone(), relay_id.clone(), Relay, SpawnOptions::default()).await?;
+
+    let value = relay_ctx
+        .send_and_wait_reply::<ValidatingActor, Validate>(
+            Validate { value: 42 },
+            &validating_id,
+            SendOptions::default(),
+        )
+        .await?;
+    assert_eq!(value, 42);
+
+    // The error reply carries the code, kind and data of the receiver's error
+    let result = relay_ctx
+        .send_and_wait_reply::<ValidatingActor, Validate>(
+            Validate { value: 200 },
+            &validating_id,
+            SendOptions::default(),
+        )
+        .await;
+    let Err(error) = result else {
+        panic!("Expected an error reply");
+    };
+    match &error {
+        SystemActorError::ErrorReply(reply) => {
+            assert_eq!(reply.code, "OutOfRange");
+            assert_eq!(reply.kind, ErrorKind::Invalid);
+            assert_eq!(reply.message, "Value 200 is over 100");
+        }
+        error => panic!("Expected an error reply, got {}", error),
+    }
+    // And decodes back into the receiver's error type
+    assert!(matches!(error.reply_as::<ValidationError>(), Some(ValidationError::OutOfRange { value: 200, max: 100 })));
+
+    // Errors without data still have their code
+    let result = relay_ctx
+        .send_and_wait_reply::<ErrorActor, TriggerError>(TriggerError, &error_actor_id, SendOptions::default())
+        .await;
+    match result {
+        Err(SystemActorError::ErrorReply(reply)) => {
+            assert_eq!(reply.code, "FakeError");
+            assert_eq!(reply.kind, ErrorKind::Permanent);
+            assert!(reply.data.is_none());
+        }
+        _ => panic!("Expected an error reply"),
+    }
+
+    validating_handle.abort();
+    error_handle.abort();
+    dbg_export_db!(engine);
+    Ok(())
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct StatefulActor {
     count: u32,
@@ -299,7 +475,7 @@ impl Message<IncrementCount> for StatefulActor {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
 struct IncrementCount;
 
 #[test(tokio::test)]
@@ -375,6 +551,330 @@ async fn test_actor_state_persistence() -> Result<(), TestError> {
     Ok(())
 }
 
+#[test(tokio::test)]
+async fn test_actor_watch_state() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+
+    let actor_id = ActorId::of::<StatefulActor>("/watched_actor");
+    let (mut actor_ctx, mut actor) =
+        Actor::spawn(engine.clone(), actor_id.clone(), StatefulActor { count: 0 }, SpawnOptions::default()).await?;
+    let actor_handle = tokio::spawn(async move {
+        if let Err(e) = actor.start(&mut actor_ctx).await {
+            error!("StatefulActor error: {}", e);
+        }
+    });
+
+    let relay_id = ActorId::of::<Relay>("/watch_relay");
+    let (relay_ctx, _relay_actor) =
+        Actor::spawn(engine.clone(), relay_id.clone(), Relay, SpawnOptions::default()).await?;
+
+    // Read the state without messaging the actor
+    let state = relay_ctx.peek::<StatefulActor>(&actor_id).await?.expect("actor state");
+    assert_eq!(state.count, 0);
+    let missing = relay_ctx.peek::<StatefulActor>(&ActorId::of::<StatefulActor>("/missing")).await?;
+    assert!(missing.is_none());
+
+    // Observe the states the actor saves
+    let mut states = relay_ctx.watch_state::<StatefulActor>(&actor_id).await?;
+    for _ in 0..2 {
+        relay_ctx
+            .send_and_wait_reply::<StatefulActor, IncrementCount>(IncrementCount, &actor_id, SendOptions::default())
+            .await?;
+    }
+
+    let mut counts = vec![];
+    while counts.len() < 3 {
+        let state = tokio::time::timeout(Duration::from_secs(2), states.next()).await.expect("state change");
+        counts.push(state.expect("state stream ended")?.count);
+    }
+    assert_eq!(counts, vec![0, 1, 2]);
+
+    // The stream ends once the actor is killed
+    actor_handle.abort();
+    engine.kill_actor(&actor_id).await?;
+    let end = tokio::time::timeout(Duration::from_secs(2), states.next()).await.expect("state stream end");
+    assert!(end.is_none());
+
+    dbg_export_db!(engine);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_actor_hierarchy() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+    let options = || SpawnOptions::builder().exists(SpawnExistsOptions::Reset).build();
+
+    let parent_id = ActorId::of::<Relay>("/parent");
+    let (mut parent_ctx, _parent) = Actor::spawn(engine.clone(), parent_id.clone(), Relay, options()).await?;
+
+    // Spawn a child, and a child of the child
+    let (mut child_ctx, mut child) = parent_ctx.spawn_child("counter", StatefulActor { count: 0 }, options()).await?;
+    let (mut nested_ctx, mut nested) = child_ctx.spawn_child("nested", StatefulActor { count: 0 }, options()).await?;
+    let child_id = child_ctx.id().clone();
+    let nested_id = nested_ctx.id().clone();
+    assert_eq!(child_id, ActorId::of::<StatefulActor>("/parent/counter"));
+    assert_eq!(nested_id, ActorId::of::<StatefulActor>("/parent/counter/nested"));
+    assert_eq!(child_ctx.parent(), Some(&parent_id));
+    assert_eq!(parent_ctx.children().collect::<Vec<_>>(), vec![&child_id]);
+
+    let child_handle = tokio::spawn(async move { child.start(&mut child_ctx).await });
+    let nested_handle = tokio::spawn(async move { nested.start(&mut nested_ctx).await });
+
+    // A child that fails to spawn isn't linked to the parent
+    let taken_id = parent_id.child::<Relay>("taken");
+    Actor::spawn(engine.clone(), taken_id.clone(), Relay, options()).await?;
+    let error_options = SpawnOptions::builder().exists(SpawnExistsOptions::Error).build();
+    assert!(parent_ctx.spawn_child("taken", Relay, error_options).await.is_err());
+    assert_eq!(engine.children(&parent_id).await?, vec![child_id.clone()]);
+    engine.kill_actor(&taken_id).await?;
+
+    // Walk the tree
+    let tree = engine.actor_tree(&parent_id).await?;
+    assert_eq!(tree.id, parent_id);
+    assert_eq!(tree.children.len(), 1);
+    assert_eq!(tree.children[0].id, child_id);
+    assert_eq!(tree.children[0].children.len(), 1);
+    assert_eq!(tree.children[0].children[0].id, nested_id);
+    assert!(tree.children[0].children[0].children.is_empty());
+
+    let count = parent_ctx
+        .send_and_wait_reply::<StatefulActor, IncrementCount>(IncrementCount, &child_id, SendOptions::default())
+        .await?;
+    assert_eq!(count, 1);
+
+    // Stopping the parent stops its descendants
+    drop(parent_ctx);
+    tokio::time::timeout(Duration::from_secs(2), child_handle).await.expect("child didn't stop").unwrap()?;
+    tokio::time::timeout(Duration::from_secs(2), nested_handle).await.expect("nested child didn't stop").unwrap()?;
+
+    // Killing the parent kills its descendants
+    assert_eq!(engine.children(&parent_id).await?, vec![child_id.clone()]);
+    engine.kill_actor(&parent_id).await?;
+    let actors = engine.actors().await?;
+    assert!(!actors.contains(&child_id));
+    assert!(!actors.contains(&nested_id));
+    assert!(engine.children(&child_id).await?.is_empty());
+
+    dbg_export_db!(engine);
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+struct SlowEcho {
+    value: u32,
+}
+
+/// Counts the messages it's handling at once
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ConcurrentActor {
+    #[serde(skip)]
+    in_flight: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    #[serde(skip)]
+    max_in_flight: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+}
+
+impl Message<SlowEcho> for ConcurrentActor {
+    type Response = u32;
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, message: &SlowEcho) -> Result<(), Self::Error> {
+        self.handle_shared(ctx, message).await
+    }
+}
+
+impl SharedMessage<SlowEcho> for ConcurrentActor {
+    async fn handle_shared(&self, ctx: &mut ActorContext<Self>, message: &SlowEcho) -> Result<(), Self::Error> {
+        use std::sync::atomic::Ordering;
+        if message.value == u32::MAX {
+            return Err(SystemActorError::MessageReply("echo failed".into()));
+        }
+        let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+        sleep(Duration::from_millis(200)).await;
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        ctx.reply(message.value).await?;
+        Ok(())
+    }
+}
+
+impl Actor for ConcurrentActor {
+    type Error = SystemActorError;
+
+    async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), Self::Error> {
+        let stream = ctx.recv().await?;
+        let actor = &*self;
+        ctx.handle_concurrently(stream, |mut ctx, frame| async move {
+            if let Some(message) = frame.is::<SlowEcho>() {
+                actor.reply_shared(&mut ctx, &message, &frame).await?;
+            }
+            Ok(())
+        })
+        .await
+    }
+}
+
+#[test(tokio::test)]
+async fn test_actor_concurrency() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+
+    let actor_id = ActorId::of::<ConcurrentActor>("/concurrent");
+    let actor = ConcurrentActor::default();
+    let max_in_flight = actor.max_in_flight.clone();
+    let options = SpawnOptions::builder().concurrency(4).build();
+    let (mut actor_ctx, mut actor) = Actor::spawn(engine.clone(), actor_id.clone(), actor, options).await?;
+    let actor_handle = tokio::spawn(async move {
+        if let Err(e) = actor.start(&mut actor_ctx).await {
+            error!("ConcurrentActor error: {}", e);
+        }
+    });
+
+    let relay_id = ActorId::of::<Relay>("/concurrent_relay");
+    let (relay_ctx, _relay_actor) =
+        Actor::spawn(engine.clone(), relay_id.clone(), Relay, SpawnOptions::default()).await?;
+
+    // Each sender gets the reply to its own message
+    let requests = (0..8).map(|value| {
+        relay_ctx.send_and_wait_reply::<ConcurrentActor, SlowEcho>(
+            SlowEcho { value },
+            &actor_id,
+            SendOptions::default(),
+        )
+    });
+    let replies = futures::future::join_all(requests).await;
+    for (value, reply) in replies.into_iter().enumerate() {
+        assert_eq!(reply?, value as u32);
+    }
+
+    // Up to 4 messages were handled at once
+    let max_in_flight = max_in_flight.load(std::sync::atomic::Ordering::SeqCst);
+    assert!(max_in_flight > 1, "messages were handled one at a time");
+    assert!(max_in_flight <= 4, "{} messages were handled at once", max_in_flight);
+
+    actor_handle.abort();
+    dbg_export_db!(engine);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_actor_concurrency_handler_error() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+
+    let actor_id = ActorId::of::<ConcurrentActor>("/concurrent_failing");
+    let options = SpawnOptions::builder().concurrency(4).build();
+    let (mut actor_ctx, mut actor) =
+        Actor::spawn(engine.clone(), actor_id.clone(), ConcurrentActor::default(), options).await?;
+    let actor_handle = tokio::spawn(async move { actor.start(&mut actor_ctx).await });
+
+    let relay_id = ActorId::of::<Relay>("/concurrent_relay");
+    let (relay_ctx, _relay_actor) =
+        Actor::spawn(engine.clone(), relay_id.clone(), Relay, SpawnOptions::default()).await?;
+
+    // A handler fails while others are in flight
+    let mut first =
+        relay_ctx.send::<ConcurrentActor, SlowEcho>(SlowEcho { value: 1 }, &actor_id, SendOptions::default()).await?;
+    let mut second =
+        relay_ctx.send::<ConcurrentActor, SlowEcho>(SlowEcho { value: 2 }, &actor_id, SendOptions::default()).await?;
+    sleep(Duration::from_millis(50)).await;
+    let failed = relay_ctx
+        .send_and_wait_reply::<ConcurrentActor, SlowEcho>(
+            SlowEcho { value: u32::MAX },
+            &actor_id,
+            SendOptions::default(),
+        )
+        .await;
+    assert!(matches!(failed, Err(SystemActorError::ErrorReply(reply)) if reply.code == "MessageReply"));
+
+    // The messages in flight are still replied to
+    assert_eq!(first.next().await.transpose()?, Some(1));
+    assert_eq!(second.next().await.transpose()?, Some(2));
+
+    // Then the actor stops with the handler's error
+    let result = tokio::time::timeout(Duration::from_secs(1), actor_handle)
+        .await
+        .expect("actor stops")
+        .map_err(SystemActorError::from)?;
+    assert!(matches!(result, Err(SystemActorError::MessageReply(_))));
+
+    dbg_export_db!(engine);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_actor_rate_limit() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+
+    let relay_id = ActorId::of::<Relay>("/rate_limit_relay");
+    let (relay_ctx, _relay_actor) =
+        Actor::spawn(engine.clone(), relay_id.clone(), Relay, SpawnOptions::default()).await?;
+
+    // One message a minute, rejecting the rest
+    let rejecting_id = ActorId::of::<StatefulActor>("/rate_limit_reject");
+    let rate_limit =
+        RateLimit::builder().messages(1).period(sql::Duration::from_secs(60)).on_limit(RateLimitAction::Reject).build();
+    let health_config = HealthConfig::builder().update_interval(sql::Duration::from_millis(100)).build();
+    let options = SpawnOptions::builder().rate_limit(rate_limit).health_config(health_config).build();
+    let (mut rejecting_ctx, mut rejecting_actor) =
+        Actor::spawn(engine.clone(), rejecting_id.clone(), StatefulActor { count: 0 }, options).await?;
+    let rejecting_handle = tokio::spawn(async move {
+        if let Err(e) = rejecting_actor.start(&mut rejecting_ctx).await {
+            error!("StatefulActor error: {}", e);
+        }
+    });
+
+    let reply = relay_ctx
+        .send_and_wait_reply::<StatefulActor, IncrementCount>(IncrementCount, &rejecting_id, SendOptions::default())
+        .await?;
+    assert_eq!(reply, 1);
+    let result = relay_ctx
+        .send_and_wait_reply::<StatefulActor, IncrementCount>(IncrementCount, &rejecting_id, SendOptions::default())
+        .await;
+    assert!(matches!(
+        result,
+        Err(SystemActorError::ErrorReply(reply)) if reply.code == "RateLimited" && reply.kind == ErrorKind::Transient
+    ));
+
+    // The health record reports the rate limit
+    sleep(Duration::from_millis(300)).await;
+    let report = engine.health_report().await?;
+    let health = report.actors.iter().find(|health| health.id == rejecting_id).expect("no health record");
+    assert_eq!(health.rate_limit, Some(RateLimitStatus { rate: 1, queued: 0, rejected: 1 }));
+
+    // Five messages a second, one at a time, delaying the rest
+    let delaying_id = ActorId::of::<StatefulActor>("/rate_limit_delay");
+    let rate_limit = RateLimit::builder().messages(5).burst(1).build();
+    let options = SpawnOptions::builder().rate_limit(rate_limit).build();
+    let (mut delaying_ctx, mut delaying_actor) =
+        Actor::spawn(engine.clone(), delaying_id.clone(), StatefulActor { count: 0 }, options).await?;
+    let delaying_handle = tokio::spawn(async move {
+        if let Err(e) = delaying_actor.start(&mut delaying_ctx).await {
+            error!("StatefulActor error: {}", e);
+        }
+    });
+
+    // All messages are handled, spaced out by the rate limit
+    let started = std::time::Instant::now();
+    let requests = (0..3).map(|_| {
+        relay_ctx.send_and_wait_reply::<StatefulActor, IncrementCount>(
+            IncrementCount,
+            &delaying_id,
+            SendOptions::default(),
+        )
+    });
+    let mut replies = futures::future::join_all(requests).await.into_iter().collect::<Result<Vec<_>, _>>()?;
+    replies.sort();
+    assert_eq!(replies, vec![1, 2, 3]);
+    assert!(started.elapsed() >= Duration::from_millis(350), "messages weren't delayed");
+
+    rejecting_handle.abort();
+    delaying_handle.abort();
+    dbg_export_db!(engine);
+
+    Ok(())
+}
+
 use rand::Rng;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -493,6 +993,61 @@ async fn test_actor_large_message_db() -> Result<(), TestError> {
     Ok(())
 }
 
+#[test(tokio::test)]
+async fn test_actor_large_message_offload() -> Result<(), TestError> {
+    let engine_options = EngineOptions::builder().payload_offload_threshold(1024).build();
+    let engine = Engine::test_with_options(engine_options).await?;
+
+    let mut rng = rand::thread_rng();
+    let large_message = LargeMessage { data: (0..10_000).map(|_| rng.gen()).collect() };
+
+    // Spawn the stateful actor
+    let stateful_actor_id = ActorId::of::<StatefulActor>("/offload_actor");
+    let (mut stateful_actor_ctx, mut stateful_actor) =
+        Actor::spawn(engine.clone(), stateful_actor_id.clone(), StatefulActor { count: 0 }, SpawnOptions::default())
+            .await?;
+    let stateful_actor_handle = tokio::spawn(async move {
+        if let Err(e) = stateful_actor.start(&mut stateful_actor_ctx).await {
+            error!("StatefulActor error: {}", e);
+        }
+    });
+
+    let relay_id = ActorId::of::<Relay>("/relay");
+    let (relay_ctx, _relay_actor) =
+        Actor::spawn(engine.clone(), relay_id.clone(), Relay, SpawnOptions::default()).await?;
+
+    // The receiver gets the full message back from the object store
+    let response = relay_ctx
+        .send_and_wait_reply::<StatefulActor, LargeMessage>(large_message, &stateful_actor_id, SendOptions::default())
+        .await?;
+    assert_eq!(response, 10_000);
+
+    // Only a reference to the payload is stored with the message
+    let mut res = engine
+        .db()
+        .lock()
+        .await
+        .query("SELECT * FROM message WHERE rx = $rx")
+        .bind(("rx", stateful_actor_id.record_id()))
+        .await
+        .map_err(SystemActorError::from)?;
+    let frames: Vec<FrameMessage> = res.take(0).map_err(SystemActorError::from)?;
+    assert_eq!(frames.len(), 1);
+    assert!(frames[0].msg.is_null());
+    assert!(frames[0].payload.is_some());
+
+    // The payload is deleted once the message is handled
+    sleep(Duration::from_millis(200)).await;
+    let path = object_store::path::Path::from(frames[0].payload.as_deref().unwrap_or_default());
+    assert!(matches!(engine.store().head(&path).await, Err(object_store::Error::NotFound { .. })));
+
+    stateful_actor_handle.abort();
+
+    dbg_export_db!(engine);
+
+    Ok(())
+}
+
 #[test(tokio::test)]
 async fn test_actor_streaming_messages() -> Result<(), Box<dyn std::error::Error>> {
     let engine = Engine::test().await?;
@@ -620,6 +1175,11 @@ async fn test_health_monitoring_enabled() -> Result<(), TestError> {
     sleep(Duration::from_millis(200)).await;
     assert!(!sender_ctx.check_actor_health(&actor_id).await?);
 
+    // A stopped actor isn't reported as down
+    let report = engine.health_report().await?;
+    assert!(report.actors.iter().all(|actor| actor.id.name() != actor_id.name()));
+    assert!(report.is_healthy());
+
     Ok(())
 }
 
@@ -754,3 +1314,1298 @@ async fn test_health_record_persistence() -> Result<(), TestError> {
     actor_handle2.abort();
     Ok(())
 }
+
+struct StatefulActorFactory;
+
+impl ActorFactory for StatefulActorFactory {
+    fn spawn(
+        &self,
+        engine: Engine,
+        config: serde_json::Value,
+        id: ActorId,
+        options: SpawnOptions,
+    ) -> Result<ActorHandle, SystemActorError> {
+        let actor: StatefulActor = serde_json::from_value(config)?;
+        Ok(tokio::spawn(async move {
+            let (mut ctx, mut actor) = Actor::spawn(engine, id, actor, options).await?;
+            actor.start(&mut ctx).await?;
+            Ok(())
+        }))
+    }
+
+    fn config_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": { "count": { "type": "integer", "minimum": 0 } },
+            "required": ["count"]
+        })
+    }
+
+    fn validate(&self, config: &serde_json::Value) -> Result<(), SystemActorError> {
+        serde_json::from_value::<StatefulActor>(config.clone())?;
+        Ok(())
+    }
+}
+
+#[test(tokio::test)]
+async fn test_virtual_actor_passivation() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+    engine.registry().add("stateful", StatefulActorFactory).await?;
+
+    // Spawn a virtual actor that is passivated after 200ms without messages
+    let actor_id = ActorId::of::<StatefulActor>("/virtual");
+    let passivation = PassivationConfig::builder().idle_timeout(sql::Duration::from_millis(200)).build();
+    let options = SpawnOptions::builder().passivation(passivation).build();
+    let actor_handle = engine
+        .registry()
+        .spawn("stateful", engine.clone(), serde_json::json!({ "count": 0 }), actor_id.clone(), options)
+        .await?;
+
+    let relay_id = ActorId::of::<Relay>("/relay");
+    let (relay_actor_ctx, _relay_actor) =
+        Actor::spawn(engine.clone(), relay_id.clone(), Relay, SpawnOptions::default()).await?;
+
+    let count = relay_actor_ctx
+        .send_and_wait_reply::<StatefulActor, IncrementCount>(IncrementCount, &actor_id, SendOptions::default())
+        .await?;
+    assert_eq!(count, 1);
+
+    // The actor stops once idle
+    sleep(Duration::from_millis(600)).await;
+    assert!(actor_handle.is_finished());
+
+    // Sending again reactivates it with its saved state
+    let count = relay_actor_ctx
+        .send_and_wait_reply::<StatefulActor, IncrementCount>(IncrementCount, &actor_id, SendOptions::default())
+        .await?;
+    assert_eq!(count, 2);
+
+    dbg_export_db!(engine);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_virtual_actor_failed_reactivation() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+    engine.registry().add("stateful", StatefulActorFactory).await?;
+
+    let actor_id = ActorId::of::<StatefulActor>("/virtual-failing");
+    let passivation = PassivationConfig::builder().idle_timeout(sql::Duration::from_millis(200)).build();
+    let options = SpawnOptions::builder().passivation(passivation).build();
+    engine
+        .registry()
+        .spawn("stateful", engine.clone(), serde_json::json!({ "count": 0 }), actor_id.clone(), options)
+        .await?;
+
+    let relay_id = ActorId::of::<Relay>("/relay");
+    let (relay_actor_ctx, _relay_actor) =
+        Actor::spawn(engine.clone(), relay_id.clone(), Relay, SpawnOptions::default()).await?;
+
+    let count = relay_actor_ctx
+        .send_and_wait_reply::<StatefulActor, IncrementCount>(IncrementCount, &actor_id, SendOptions::default())
+        .await?;
+    assert_eq!(count, 1);
+    sleep(Duration::from_millis(600)).await;
+
+    // The saved state can't be restored, so the reactivated actor's task fails
+    let set_state = |state: serde_json::Value| {
+        let engine = engine.clone();
+        let id = actor_id.record_id();
+        async move {
+            engine
+                .db()
+                .lock()
+                .await
+                .query("UPDATE $id SET state = $state")
+                .bind(("id", id))
+                .bind(("state", state))
+                .await
+        }
+    };
+    set_state(serde_json::json!("invalid")).await?.check()?;
+    let options = SendOptions::builder().timeout(Duration::from_millis(300)).build();
+    let result =
+        relay_actor_ctx.send_and_wait_reply::<StatefulActor, IncrementCount>(IncrementCount, &actor_id, options).await;
+    assert!(result.is_err());
+
+    // The actor is left passivated instead of lost
+    let mut res = engine
+        .db()
+        .lock()
+        .await
+        .query("SELECT VALUE active FROM ONLY $id")
+        .bind(("id", actor_id.activation_id()))
+        .await?;
+    let active: Option<bool> = res.take(0)?;
+    assert_eq!(active, Some(false));
+
+    // So a later message reactivates it once it can be restored
+    set_state(serde_json::json!({ "count": 1 })).await?.check()?;
+    let count = relay_actor_ctx
+        .send_and_wait_reply::<StatefulActor, IncrementCount>(IncrementCount, &actor_id, SendOptions::default())
+        .await?;
+    assert!(count >= 2);
+
+    dbg_export_db!(engine);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_health_readiness_probe() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+
+    let health_config = HealthConfig::builder().update_interval(sql::Duration::from_millis(100)).build();
+    let options = SpawnOptions::builder().health_config(health_config).build();
+
+    let actor_id = ActorId::of::<TestActor>("/health-probe");
+    let (actor_ctx, _actor) = Actor::spawn(engine.clone(), actor_id.clone(), TestActor { count: 0 }, options).await?;
+
+    let sender_id = ActorId::of::<TestActor>("/sender");
+    let (sender_ctx, _) =
+        Actor::spawn(engine.clone(), sender_id.clone(), TestActor { count: 0 }, SpawnOptions::default()).await?;
+
+    let mut changes = engine.health_changes().await?;
+
+    // Live and ready until the probe says otherwise
+    assert!(sender_ctx.check_actor_health(&actor_id).await?);
+
+    let ready = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(true));
+    let probe_ready = ready.clone();
+    actor_ctx
+        .set_health_probe(move || {
+            if probe_ready.load(std::sync::atomic::Ordering::SeqCst) {
+                Readiness::Ready
+            } else {
+                Readiness::NotReady("Model failed to load".to_string())
+            }
+        })
+        .await?;
+
+    ready.store(false, std::sync::atomic::Ordering::SeqCst);
+    sleep(Duration::from_millis(300)).await;
+
+    let health = sender_ctx.actor_health(&actor_id).await?.expect("health record");
+    assert!(health.live);
+    assert!(!health.ready);
+    assert_eq!(health.reason.as_deref(), Some("Model failed to load"));
+    assert!(!sender_ctx.check_actor_health(&actor_id).await?);
+
+    // The change is notified
+    let change = tokio::time::timeout(Duration::from_secs(2), changes.next()).await.expect("health change");
+    let change = change.expect("health stream")?;
+    assert_eq!(change.id.name(), actor_id.name());
+    assert!(!change.ready);
+
+    // And shows up in the engine report
+    let report = engine.health_report().await?;
+    assert!(report.database);
+    assert!(!report.is_healthy());
+    assert!(report.actors.iter().any(|actor| actor.id.name() == actor_id.name() && !actor.ready));
+
+    // Without health updates, the probe is only evaluated on demand
+    let probe_ready = ready.clone();
+    sender_ctx
+        .set_health_probe(move || {
+            if probe_ready.load(std::sync::atomic::Ordering::SeqCst) {
+                Readiness::Ready
+            } else {
+                Readiness::NotReady("Model failed to load".to_string())
+            }
+        })
+        .await?;
+    assert!(!actor_ctx.check_actor_health(&sender_id).await?);
+
+    ready.store(true, std::sync::atomic::Ordering::SeqCst);
+    assert!(!actor_ctx.check_actor_health(&sender_id).await?);
+    sender_ctx.refresh_readiness().await?;
+    assert!(actor_ctx.check_actor_health(&sender_id).await?);
+
+    Ok(())
+}
+
+struct FailingFactory;
+
+impl ActorFactory for FailingFactory {
+    fn spawn(
+        &self,
+        _engine: Engine,
+        _config: serde_json::Value,
+        _id: ActorId,
+        _options: SpawnOptions,
+    ) -> Result<ActorHandle, SystemActorError> {
+        Err(SystemActorError::MessageReply("factory failure".into()))
+    }
+}
+
+#[test(tokio::test)]
+async fn test_registry_spawn_errors() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+    let registry = engine.registry();
+    registry.add("stateful", StatefulActorFactory).await?;
+    registry.add("failing", FailingFactory).await?;
+
+    let actor_id = ActorId::of::<StatefulActor>("/registry_errors");
+    let bad_config = serde_json::json!({ "count": "not a number" });
+
+    // Configs are validated without spawning
+    registry.validate("stateful", &serde_json::json!({ "count": 0 })).await?;
+    let result = registry.validate("stateful", &bad_config).await;
+    assert!(matches!(result, Err(SystemActorError::InvalidActorConfig(tag, _)) if tag == "stateful"));
+    assert_eq!(registry.schema("stateful").await?["required"], serde_json::json!(["count"]));
+    assert_eq!(registry.schemas().await.len(), 2);
+
+    // Spawning returns errors instead of panicking
+    let result =
+        registry.spawn("stateful", engine.clone(), bad_config, actor_id.clone(), SpawnOptions::default()).await;
+    assert!(matches!(result, Err(SystemActorError::InvalidActorConfig(_, _))));
+
+    let result = registry
+        .spawn("missing", engine.clone(), serde_json::json!({}), actor_id.clone(), SpawnOptions::default())
+        .await;
+    assert!(matches!(result, Err(SystemActorError::ActorTagNotFound(_))));
+
+    let result = registry
+        .spawn("failing", engine.clone(), serde_json::json!({}), actor_id.clone(), SpawnOptions::default())
+        .await;
+    assert!(matches!(result, Err(SystemActorError::ActorSpawn(id, _, _)) if id == actor_id));
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_actor_lease() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+    let actor_id = ActorId::of::<TestActor>("/leased");
+
+    let lease = LeaseConfig::builder().ttl(sql::Duration::from_secs(1)).build();
+    let options = SpawnOptions::builder().exists(SpawnExistsOptions::Restore).lease(lease).build();
+    let (owner_ctx, _owner) =
+        Actor::spawn(engine.clone(), actor_id.clone(), TestActor { count: 0 }, options.clone()).await?;
+
+    // Another owner can't spawn the actor while the lease is held
+    let result = Actor::spawn(engine.clone(), actor_id.clone(), TestActor { count: 0 }, options.clone()).await;
+    assert!(matches!(result, Err(TestError::System(SystemActorError::LeaseHeld(_)))));
+
+    // The lease is renewed past its ttl
+    sleep(Duration::from_millis(1500)).await;
+    let result = Actor::spawn(engine.clone(), actor_id.clone(), TestActor { count: 0 }, options.clone()).await;
+    assert!(matches!(result, Err(TestError::System(SystemActorError::LeaseHeld(_)))));
+
+    // A standby owner waits for the lease and takes over once the owner is gone
+    let lease = LeaseConfig::builder().ttl(sql::Duration::from_secs(1)).conflict(LeaseConflict::Wait).build();
+    let options = SpawnOptions::builder().exists(SpawnExistsOptions::Restore).lease(lease).build();
+    let standby = tokio::spawn({
+        let engine = engine.clone();
+        let actor_id = actor_id.clone();
+        async move { Actor::spawn(engine, actor_id, TestActor { count: 0 }, options).await }
+    });
+
+    sleep(Duration::from_millis(500)).await;
+    assert!(!standby.is_finished());
+
+    drop(owner_ctx);
+    let standby = tokio::time::timeout(Duration::from_secs(5), standby).await.expect("standby didn't take over");
+    let (standby_ctx, _standby) = standby.expect("standby task panicked")?;
+    assert_eq!(standby_ctx.id(), &actor_id);
+
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SummingActor;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Number(u64);
+
+impl StreamMessage<Number> for SummingActor {
+    type Response = u64;
+
+    async fn handle_stream(
+        &mut self,
+        ctx: &mut ActorContext<Self>,
+        mut numbers: RequestStream<Number>,
+    ) -> Result<(), TestError> {
+        let mut sum = 0;
+        while let Some(number) = numbers.next().await {
+            sum += number?.0;
+            ctx.reply(sum).await?;
+        }
+        Ok(())
+    }
+}
+
+impl Actor for SummingActor {
+    type Error = TestError;
+
+    async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), TestError> {
+        let mut stream = ctx.recv().await?;
+        while let Some(Ok(frame)) = stream.next().await {
+            if frame.is_stream::<Number>() {
+                self.reply_stream(ctx, &frame).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test(tokio::test)]
+async fn test_actor_send_stream() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+
+    let summing_id = ActorId::of::<SummingActor>("/summing");
+    let (mut summing_ctx, mut summing_actor) =
+        Actor::spawn(engine.clone(), summing_id.clone(), SummingActor, SpawnOptions::default()).await?;
+    let summing_handle = tokio::spawn(async move {
+        if let Err(e) = summing_actor.start(&mut summing_ctx).await {
+            error!("SummingActor error: {}", e);
+        }
+    });
+
+    let relay_id = ActorId::of::<Relay>("/relay");
+    let (relay_ctx, _relay_actor) =
+        Actor::spawn(engine.clone(), relay_id.clone(), Relay, SpawnOptions::default()).await?;
+
+    // Stream the numbers in as they're produced, as one request
+    let numbers = futures::stream::iter(1..=5).then(|n| async move {
+        sleep(Duration::from_millis(20)).await;
+        Number(n)
+    });
+    let replies = relay_ctx.send_stream::<SummingActor, Number>(numbers, &summing_id, SendOptions::default()).await?;
+    let sums = replies.collect::<Vec<_>>().await.into_iter().collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(sums, vec![1, 3, 6, 10, 15]);
+
+    // A streamed request isn't mistaken for a single message
+    let frames = engine
+        .db()
+        .lock()
+        .await
+        .query("SELECT * FROM message")
+        .await
+        .map_err(SystemActorError::from)?
+        .take::<Vec<FrameMessage>>(0)
+        .map_err(SystemActorError::from)?;
+    assert_eq!(frames.len(), 1);
+    assert!(frames[0].is_stream::<Number>());
+    assert!(frames[0].is::<Number>().is_none());
+    assert_eq!(frames[0].stream_timeout, Some(SendOptions::default().timeout.into()));
+
+    // The messages of the request are deleted once it's handled
+    sleep(Duration::from_millis(100)).await;
+    let chunks = engine
+        .db()
+        .lock()
+        .await
+        .query("SELECT * FROM message_chunk")
+        .await
+        .map_err(SystemActorError::from)?
+        .take::<Vec<serde_json::Value>>(0)
+        .map_err(SystemActorError::from)?;
+    assert!(chunks.is_empty());
+
+    summing_handle.abort();
+
+    dbg_export_db!(engine);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_actor_send_all() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+
+    // Two running actors, and one that never handles its messages
+    let mut ids = Vec::new();
+    let mut handles = Vec::new();
+    for i in 0..3 {
+        let id = ActorId::of::<TestActor>(format!("/gather_{}", i));
+        let (mut ctx, mut actor) =
+            Actor::spawn(engine.clone(), id.clone(), TestActor { count: 0 }, SpawnOptions::default()).await?;
+        if i < 2 {
+            handles.push(tokio::spawn(async move {
+                if let Err(e) = actor.start(&mut ctx).await {
+                    error!("TestActor error: {}", e);
+                }
+            }));
+        } else {
+            handles.push(tokio::spawn(async move {
+                let _ctx = ctx;
+                std::future::pending::<()>().await;
+            }));
+        }
+        ids.push(id);
+    }
+
+    let relay_id = ActorId::of::<Relay>("/relay");
+    let (relay_ctx, _relay_actor) =
+        Actor::spawn(engine.clone(), relay_id.clone(), Relay, SpawnOptions::default()).await?;
+
+    let message = TestMessage { content: "Hello".to_string() };
+    let send = SendOptions::builder().timeout(Duration::from_millis(500)).build();
+
+    // Wait for all, the idle target times out on its own
+    let options = GatherOptions::builder().send(send.clone()).build();
+    let gathered = relay_ctx.send_all::<TestActor, TestMessage>(ids.clone(), message.clone(), options).await;
+    assert!(!gathered.satisfied);
+    assert_eq!(gathered.successes().count(), 2);
+    assert_eq!(gathered.replies[0].id, ids[0]);
+    assert!(matches!(gathered.replies[2].reply, Some(Err(SystemActorError::MessageTimeout(_, _)))));
+
+    // The first success cancels the idle target
+    let options = GatherOptions::builder().strategy(GatherStrategy::FirstSuccess).send(send.clone()).build();
+    let gathered = relay_ctx.send_all::<TestActor, TestMessage>(ids.clone(), message.clone(), options).await;
+    assert!(gathered.satisfied);
+    assert!(gathered.replies[2].reply.is_none());
+
+    // A quorum of two is reached without the idle target
+    let options = GatherOptions::builder().strategy(GatherStrategy::Quorum(2)).send(send.clone()).build();
+    let gathered = relay_ctx.send_all::<TestActor, TestMessage>(ids.clone(), message.clone(), options).await;
+    assert!(gathered.satisfied);
+    assert_eq!(gathered.successes().count(), 2);
+    let cancelled = gathered.replies.into_iter().nth(2).unwrap().into_result();
+    assert!(matches!(cancelled, Err(SystemActorError::ReplyCancelled(_))));
+
+    // The messages of cancelled targets are deleted, only the one that timed out is left for the idle target
+    assert_eq!(engine.pending_messages(&ids[2]).await?.len(), 1);
+
+    // A quorum of three can't be reached
+    let options = GatherOptions::builder().strategy(GatherStrategy::Quorum(3)).send(send).build();
+    let requests = ids.iter().map(|id| (id.clone(), message.clone()));
+    let gathered = relay_ctx.send_each::<TestActor, TestMessage>(requests, options).await;
+    assert!(!gathered.satisfied);
+
+    for handle in handles {
+        handle.abort();
+    }
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_actor_message_encoding() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+
+    let test_id = ActorId::of::<TestActor>("/test_encoding");
+    let (mut test_ctx, mut test_actor) =
+        Actor::spawn(engine.clone(), test_id.clone(), TestActor { count: 0 }, SpawnOptions::default()).await?;
+    let test_handle = tokio::spawn(async move {
+        if let Err(e) = test_actor.start(&mut test_ctx).await {
+            error!("TestActor error: {}", e);
+        }
+    });
+
+    let relay_id = ActorId::of::<Relay>("/relay");
+    let (relay_ctx, _relay_actor) =
+        Actor::spawn(engine.clone(), relay_id.clone(), Relay, SpawnOptions::default()).await?;
+
+    let message = TestMessage { content: "encoded".to_string() };
+
+    // Encoding set through the send options
+    let cbor = Encoding::builder().format(EncodingFormat::Cbor).zstd(3).build();
+    let options = SendOptions::builder().encoding(cbor).build();
+    let response = relay_ctx.send_and_wait_reply::<TestActor, TestMessage>(message.clone(), &test_id, options).await?;
+    assert_eq!(response.content, "Received: encoded");
+    assert_eq!(response.count, 1);
+
+    // Encoding set for the message type
+    let bincode = Encoding::builder().format(EncodingFormat::Bincode).build();
+    engine.set_encoding::<TestMessage>(bincode);
+    let response = relay_ctx
+        .send_and_wait_reply::<TestActor, TestMessage>(message.clone(), &test_id, SendOptions::default())
+        .await?;
+    assert_eq!(response.content, "Received: encoded");
+    assert_eq!(response.count, 2);
+
+    // Messages and replies are stored encoded
+    let mut res = engine
+        .db()
+        .lock()
+        .await
+        .query("SELECT * FROM message WHERE rx = $rx; SELECT id.{id, chunk}, * FROM reply WHERE tx = $rx")
+        .bind(("rx", test_id.record_id()))
+        .await
+        .map_err(SystemActorError::from)?;
+    let frames: Vec<FrameMessage> = res.take(0).map_err(SystemActorError::from)?;
+    assert_eq!(frames.len(), 2);
+    assert!(frames.iter().all(|frame| frame.msg.is_string() && !frame.encoding.is_json()));
+    let replies: Vec<FrameReply> = res.take(1).map_err(SystemActorError::from)?;
+    assert!(replies.iter().filter(|reply| reply.chunk().is_some()).all(|reply| reply.msg.is_string()));
+    let mut res = engine
+        .db()
+        .lock()
+        .await
+        .query("SELECT VALUE type::is::bytes(msg) FROM message WHERE rx = $rx")
+        .bind(("rx", test_id.record_id()))
+        .await
+        .map_err(SystemActorError::from)?;
+    let bytes: Vec<bool> = res.take(0).map_err(SystemActorError::from)?;
+    assert_eq!(bytes, vec![true, true]);
+
+    test_handle.abort();
+    dbg_export_db!(engine);
+    Ok(())
+}
+
+// Interceptor corrupting the content of binary frames
+struct CorruptBinary;
+
+impl Interceptor for CorruptBinary {
+    fn on_send(&self, frame: &mut FrameMessage) -> Result<(), SystemActorError> {
+        if !frame.encoding.is_json() {
+            frame.msg = "AAAA".into();
+        }
+        Ok(())
+    }
+}
+
+#[test(tokio::test)]
+async fn test_actor_message_encoding_corrupt() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+    engine.add_interceptor(CorruptBinary);
+
+    let test_id = ActorId::of::<TestActor>("/test_encoding_corrupt");
+    let (mut test_ctx, mut test_actor) =
+        Actor::spawn(engine.clone(), test_id.clone(), TestActor { count: 0 }, SpawnOptions::default()).await?;
+    let test_handle = tokio::spawn(async move {
+        if let Err(e) = test_actor.start(&mut test_ctx).await {
+            error!("TestActor error: {}", e);
+        }
+    });
+
+    let relay_id = ActorId::of::<Relay>("/relay");
+    let (relay_ctx, _relay_actor) =
+        Actor::spawn(engine.clone(), relay_id.clone(), Relay, SpawnOptions::default()).await?;
+
+    // A frame that can't be decoded is replied with an error instead of being skipped
+    let bincode = Encoding::builder().format(EncodingFormat::Bincode).build();
+    let options = SendOptions::builder().encoding(bincode).timeout(std::time::Duration::from_secs(5)).build();
+    let message = TestMessage { content: "corrupt".to_string() };
+    let result = relay_ctx.send_and_wait_reply::<TestActor, TestMessage>(message, &test_id, options).await;
+    assert!(matches!(result, Err(SystemActorError::ErrorReply(reply)) if reply.code == "Encoding"));
+
+    test_handle.abort();
+    dbg_export_db!(engine);
+    Ok(())
+}
+
+// Key provider switching keys at runtime, like one backed by a secrets manager
+#[derive(Clone)]
+struct RotatingKeys {
+    keys: std::sync::Arc<std::sync::Mutex<(String, std::collections::HashMap<String, [u8; 32]>)>>,
+}
+
+impl RotatingKeys {
+    fn new(id: &str, key: [u8; 32]) -> Self {
+        let keys = (id.to_string(), std::collections::HashMap::from([(id.to_string(), key)]));
+        Self { keys: std::sync::Arc::new(std::sync::Mutex::new(keys)) }
+    }
+
+    fn rotate(&self, id: &str, key: [u8; 32]) {
+        let mut keys = self.keys.lock().unwrap();
+        keys.0 = id.to_string();
+        keys.1.insert(id.to_string(), key);
+    }
+
+    fn retire(&self, id: &str) {
+        self.keys.lock().unwrap().1.remove(id);
+    }
+}
+
+impl KeyProvider for RotatingKeys {
+    fn current_key_id(&self) -> std::borrow::Cow<'_, str> {
+        self.keys.lock().unwrap().0.clone().into()
+    }
+
+    fn key(&self, id: &str) -> Option<[u8; 32]> {
+        self.keys.lock().unwrap().1.get(id).copied()
+    }
+}
+
+/// Ids of the keys the payloads of an actor's messages, replies and state are encrypted with
+async fn payload_key_ids(engine: &Engine, id: &ActorId) -> Result<Vec<String>, SystemActorError> {
+    let mut res = engine
+        .db()
+        .lock()
+        .await
+        .query(
+            "SELECT VALUE msg.__encrypted.kid FROM message WHERE rx = $id; \
+             SELECT VALUE msg.__encrypted.kid FROM reply WHERE tx = $id; \
+             SELECT VALUE state.__encrypted.kid FROM actor WHERE id = $id",
+        )
+        .bind(("id", id.record_id()))
+        .await?;
+    let mut key_ids = Vec::new();
+    for statement in 0..3 {
+        let ids: Vec<Option<String>> = res.take(statement)?;
+        key_ids.extend(ids.into_iter().flatten());
+    }
+    Ok(key_ids)
+}
+
+#[test(tokio::test)]
+async fn test_engine_encryption() -> Result<(), TestError> {
+    let keys = RotatingKeys::new("k1", [1; 32]);
+    let options = EngineOptions::builder().encryption(Encryption::new(keys.clone())).build();
+    let engine = Engine::test_with_options(options).await?;
+
+    let actor_id = ActorId::of::<StatefulActor>("/encrypted");
+    let (mut actor_ctx, mut actor) =
+        Actor::spawn(engine.clone(), actor_id.clone(), StatefulActor { count: 0 }, SpawnOptions::default()).await?;
+    let actor_handle = tokio::spawn(async move {
+        if let Err(e) = actor.start(&mut actor_ctx).await {
+            error!("StatefulActor error: {}", e);
+        }
+    });
+
+    let relay_id = ActorId::of::<Relay>("/relay");
+    let (relay_ctx, _relay_actor) =
+        Actor::spawn(engine.clone(), relay_id.clone(), Relay, SpawnOptions::default()).await?;
+
+    // Payloads are decrypted transparently
+    let count = relay_ctx
+        .send_and_wait_reply::<StatefulActor, IncrementCount>(IncrementCount, &actor_id, SendOptions::default())
+        .await?;
+    assert_eq!(count, 1);
+    sleep(Duration::from_millis(100)).await;
+
+    // They are stored encrypted, while the routing fields stay queryable
+    let key_ids = payload_key_ids(&engine, &actor_id).await?;
+    assert_eq!(key_ids, vec!["k1", "k1", "k1"]);
+
+    // Rotate the key and re-encrypt the stored payloads, after which the earlier key can be retired
+    keys.rotate("k2", [2; 32]);
+    let updated = engine.rotate_encryption().await?;
+    assert!(updated >= 3);
+    let key_ids = payload_key_ids(&engine, &actor_id).await?;
+    assert_eq!(key_ids, vec!["k2", "k2", "k2"]);
+    keys.retire("k1");
+
+    // The state is restored with the new key
+    actor_handle.abort();
+    sleep(Duration::from_millis(100)).await;
+    let options = SpawnOptions::builder().exists(SpawnExistsOptions::Restore).build();
+    let (mut actor_ctx, mut actor) =
+        Actor::spawn(engine.clone(), actor_id.clone(), StatefulActor { count: 0 }, options).await?;
+    assert_eq!(actor.count, 1);
+    let actor_handle = tokio::spawn(async move {
+        if let Err(e) = actor.start(&mut actor_ctx).await {
+            error!("StatefulActor error: {}", e);
+        }
+    });
+
+    let count = relay_ctx
+        .send_and_wait_reply::<StatefulActor, IncrementCount>(IncrementCount, &actor_id, SendOptions::default())
+        .await?;
+    assert_eq!(count, 2);
+    actor_handle.abort();
+
+    // A payload copied to another record can't be decrypted there
+    let copy_id = ActorId::of::<StatefulActor>("/encrypted-copy");
+    engine
+        .db()
+        .lock()
+        .await
+        .query("CREATE $copy SET tag = $tag, state = (SELECT VALUE state FROM ONLY $id)")
+        .bind(("copy", copy_id.record_id()))
+        .bind(("tag", copy_id.tag().to_string()))
+        .bind(("id", actor_id.record_id()))
+        .await?
+        .check()?;
+    let options = SpawnOptions::builder().exists(SpawnExistsOptions::Restore).build();
+    let copied = Actor::spawn(engine.clone(), copy_id, StatefulActor { count: 0 }, options).await;
+    assert!(matches!(copied, Err(SystemActorError::Encryption(_))));
+
+    dbg_export_db!(engine);
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_engine_encryption_rotation_after_offload() -> Result<(), TestError> {
+    let keys = RotatingKeys::new("k1", [1; 32]);
+    let options =
+        EngineOptions::builder().payload_offload_threshold(1024).encryption(Encryption::new(keys.clone())).build();
+    let engine = Engine::test_with_options(options).await?;
+
+    let actor_id = ActorId::of::<StatefulActor>("/offload_encrypted");
+    let (mut actor_ctx, mut actor) =
+        Actor::spawn(engine.clone(), actor_id.clone(), StatefulActor { count: 0 }, SpawnOptions::default()).await?;
+    let actor_handle = tokio::spawn(async move {
+        if let Err(e) = actor.start(&mut actor_ctx).await {
+            error!("StatefulActor error: {}", e);
+        }
+    });
+
+    let relay_id = ActorId::of::<Relay>("/relay");
+    let (relay_ctx, _relay_actor) =
+        Actor::spawn(engine.clone(), relay_id.clone(), Relay, SpawnOptions::default()).await?;
+
+    let mut rng = rand::thread_rng();
+    let large_message = LargeMessage { data: (0..10_000).map(|_| rng.gen()).collect() };
+    let response = relay_ctx
+        .send_and_wait_reply::<StatefulActor, LargeMessage>(large_message, &actor_id, SendOptions::default())
+        .await?;
+    assert_eq!(response, 10_000);
+    sleep(Duration::from_millis(200)).await;
+
+    // The handled message no longer refers to its deleted payload
+    let mut res = engine
+        .db()
+        .lock()
+        .await
+        .query("SELECT * FROM message WHERE rx = $rx")
+        .bind(("rx", actor_id.record_id()))
+        .await
+        .map_err(SystemActorError::from)?;
+    let frames: Vec<FrameMessage> = res.take(0).map_err(SystemActorError::from)?;
+    assert_eq!(frames.len(), 1);
+    assert!(frames[0].payload.is_none());
+
+    keys.rotate("k2", [2; 32]);
+    engine.rotate_encryption().await?;
+
+    // Payloads deleted after their record was read are skipped too
+    engine
+        .db()
+        .lock()
+        .await
+        .query("UPDATE $id SET payload = 'payloads/message/deleted'")
+        .bind(("id", frames[0].id.clone()))
+        .await?
+        .check()?;
+    keys.rotate("k3", [3; 32]);
+    engine.rotate_encryption().await?;
+
+    actor_handle.abort();
+    dbg_export_db!(engine);
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_actor_idempotency_key() -> Result<(), TestError> {
+    let engine_options = EngineOptions::builder().idempotency_ttl(sql::Duration::from_millis(500)).build();
+    let engine = Engine::test_with_options(engine_options).await?;
+
+    let test_id = ActorId::of::<TestActor>("/test_idempotent");
+    let (mut test_ctx, mut test_actor) =
+        Actor::spawn(engine.clone(), test_id.clone(), TestActor { count: 0 }, SpawnOptions::default()).await?;
+    let test_handle = tokio::spawn(async move {
+        if let Err(e) = test_actor.start(&mut test_ctx).await {
+            error!("TestActor error: {}", e);
+        }
+    });
+
+    let relay_id = ActorId::of::<Relay>("/relay");
+    let (relay_ctx, _relay_actor) =
+        Actor::spawn(engine.clone(), relay_id.clone(), Relay, SpawnOptions::default()).await?;
+
+    let message = TestMessage { content: "Hello".to_string() };
+    let options = SendOptions::builder().idempotency_key("job-1".to_string()).build();
+
+    let first =
+        relay_ctx.send_and_wait_reply::<TestActor, TestMessage>(message.clone(), &test_id, options.clone()).await?;
+    assert_eq!(first.count, 1);
+
+    // A retry with the same key gets the original reply, without handling the message again
+    let retry = relay_ctx.send_and_wait_reply::<TestActor, TestMessage>(message.clone(), &test_id, options).await?;
+    assert_eq!(retry.count, 1);
+
+    // A new key is a new request
+    let options = SendOptions::builder().idempotency_key("job-2".to_string()).build();
+    let second = relay_ctx.send_and_wait_reply::<TestActor, TestMessage>(message.clone(), &test_id, options).await?;
+    assert_eq!(second.count, 2);
+
+    // Keys are forgotten once they expire
+    sleep(Duration::from_millis(600)).await;
+    let options = SendOptions::builder().idempotency_key("job-1".to_string()).build();
+    let third = relay_ctx.send_and_wait_reply::<TestActor, TestMessage>(message, &test_id, options).await?;
+    assert_eq!(third.count, 3);
+
+    test_handle.abort();
+    dbg_export_db!(engine);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_router_round_robin() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+    engine.registry().add("stateful", StatefulActorFactory).await?;
+
+    let router_id = ActorId::of::<Router>("/router");
+    let router = Router::builder().tag("stateful").config(serde_json::json!({ "count": 0 })).size(2).build();
+    let (mut router_ctx, mut router) =
+        Actor::spawn(engine.clone(), router_id.clone(), router, SpawnOptions::default()).await?;
+    let router_handle = tokio::spawn(async move {
+        if let Err(e) = router.start(&mut router_ctx).await {
+            error!("Router error: {}", e);
+        }
+    });
+
+    let relay_id = ActorId::of::<Relay>("/relay");
+    let (relay_ctx, _relay_actor) =
+        Actor::spawn(engine.clone(), relay_id.clone(), Relay, SpawnOptions::default()).await?;
+
+    // Each worker keeps its own count, and gets every other message
+    let mut counts = Vec::new();
+    for _ in 0..4 {
+        let count = relay_ctx
+            .send_and_wait_reply::<StatefulActor, IncrementCount>(IncrementCount, &router_id, SendOptions::default())
+            .await?;
+        counts.push(count);
+    }
+    assert_eq!(counts, vec![1, 1, 2, 2]);
+
+    // Grow the pool
+    let status = relay_ctx
+        .send_and_wait_reply::<Router, ResizeRouter>(ResizeRouter { size: 3 }, &router_id, SendOptions::default())
+        .await?;
+    assert_eq!(status.workers.len(), 3);
+    assert_eq!(status.workers[2].id.name(), "/router/worker_2");
+    assert_eq!(engine.children(&router_id).await?.len(), 3);
+
+    // Without workers, messages are rejected
+    relay_ctx
+        .send_and_wait_reply::<Router, ResizeRouter>(ResizeRouter { size: 0 }, &router_id, SendOptions::default())
+        .await?;
+    let result = relay_ctx
+        .send_and_wait_reply::<StatefulActor, IncrementCount>(IncrementCount, &router_id, SendOptions::default())
+        .await;
+    assert!(matches!(result, Err(SystemActorError::ErrorReply(reply)) if reply.code == "NoWorkers"));
+
+    let status = relay_ctx
+        .send_and_wait_reply::<Router, GetRouterStatus>(GetRouterStatus, &router_id, SendOptions::default())
+        .await?;
+    assert!(status.workers.is_empty());
+
+    router_handle.abort();
+    dbg_export_db!(engine);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_router_resize_error() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+
+    let router_id = ActorId::of::<Router>("/router");
+    let router = Router::builder().tag("missing").size(0).build();
+    let (mut router_ctx, mut router) =
+        Actor::spawn(engine.clone(), router_id.clone(), router, SpawnOptions::default()).await?;
+    let router_handle = tokio::spawn(async move { router.start(&mut router_ctx).await });
+
+    let relay_id = ActorId::of::<Relay>("/relay");
+    let (relay_ctx, _relay_actor) =
+        Actor::spawn(engine.clone(), relay_id.clone(), Relay, SpawnOptions::default()).await?;
+
+    // A failed resize is replied as an error
+    let result = relay_ctx
+        .send_and_wait_reply::<Router, ResizeRouter>(ResizeRouter { size: 1 }, &router_id, SendOptions::default())
+        .await;
+    assert!(matches!(result, Err(SystemActorError::ErrorReply(reply)) if reply.code == "ActorTagNotFound"));
+
+    // And the router keeps running
+    let status = relay_ctx
+        .send_and_wait_reply::<Router, GetRouterStatus>(GetRouterStatus, &router_id, SendOptions::default())
+        .await?;
+    assert!(status.workers.is_empty());
+    assert!(!router_handle.is_finished());
+
+    router_handle.abort();
+    dbg_export_db!(engine);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_actor_send_retry() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+
+    // The actor starts handling messages after the first attempt timed out
+    let test_id = ActorId::of::<TestActor>("/test_retry");
+    let (mut test_ctx, mut test_actor) =
+        Actor::spawn(engine.clone(), test_id.clone(), TestActor { count: 0 }, SpawnOptions::default()).await?;
+    let test_handle = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        if let Err(e) = test_actor.start(&mut test_ctx).await {
+            error!("TestActor error: {}", e);
+        }
+    });
+
+    let relay_id = ActorId::of::<Relay>("/relay");
+    let (relay_ctx, _relay_actor) =
+        Actor::spawn(engine.clone(), relay_id.clone(), Relay, SpawnOptions::default()).await?;
+
+    let retry = RetryPolicy::builder().max_attempts(3).initial_backoff(Duration::from_millis(10)).build();
+    let options = SendOptions::builder().timeout(Duration::from_millis(200)).retry(retry).build();
+    let response = relay_ctx
+        .send_and_wait_reply::<TestActor, TestMessage>(TestMessage { content: "Hello".to_string() }, &test_id, options)
+        .await?;
+    assert_eq!(response.content, "Received: Hello");
+
+    // The attempts share an idempotency key, so the message is only enqueued once
+    let frames = engine
+        .db()
+        .lock()
+        .await
+        .query("SELECT * FROM message WHERE rx = $rx")
+        .bind(("rx", test_id.record_id()))
+        .await
+        .map_err(SystemActorError::from)?
+        .take::<Vec<FrameMessage>>(0)
+        .map_err(SystemActorError::from)?;
+    assert_eq!(frames.len(), 1);
+
+    // Errors not in the policy are not retried
+    let retry = RetryPolicy::builder().retry_on(vec![RetryOn::Unhealthy]).build();
+    assert!(!retry.should_retry(1, &SystemActorError::MessageTimeout("test".into(), Duration::from_millis(1))));
+
+    test_handle.abort();
+    dbg_export_db!(engine);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_actor_circuit_breaker() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+
+    // An actor that never handles its messages
+    let idle_id = ActorId::of::<TestActor>("/test_idle");
+    let (idle_ctx, _idle_actor) =
+        Actor::spawn(engine.clone(), idle_id.clone(), TestActor { count: 0 }, SpawnOptions::default()).await?;
+
+    let relay_id = ActorId::of::<Relay>("/relay");
+    let (relay_ctx, _relay_actor) =
+        Actor::spawn(engine.clone(), relay_id.clone(), Relay, SpawnOptions::default()).await?;
+
+    let breaker = CircuitBreakerConfig::builder().failure_threshold(2).cool_down(Duration::from_millis(300)).build();
+    let options = SendOptions::builder().timeout(Duration::from_millis(100)).circuit_breaker(breaker).build();
+    let message = TestMessage { content: "Hello".to_string() };
+
+    // Two timeouts open the circuit
+    for _ in 0..2 {
+        let result =
+            relay_ctx.send_and_wait_reply::<TestActor, TestMessage>(message.clone(), &idle_id, options.clone()).await;
+        assert!(matches!(result, Err(SystemActorError::MessageTimeout(_, _))));
+    }
+
+    // Then sends fail fast
+    let start = std::time::Instant::now();
+    let result =
+        relay_ctx.send_and_wait_reply::<TestActor, TestMessage>(message.clone(), &idle_id, options.clone()).await;
+    assert!(matches!(result, Err(SystemActorError::CircuitOpen(_))));
+    assert!(start.elapsed() < Duration::from_millis(100));
+
+    // After the cool-down a probe reaches the actor, which handles messages again
+    drop(idle_ctx);
+    let (mut test_ctx, mut test_actor) = Actor::spawn(
+        engine.clone(),
+        idle_id.clone(),
+        TestActor { count: 0 },
+        SpawnOptions::builder().exists(SpawnExistsOptions::Reset).build(),
+    )
+    .await?;
+    let test_handle = tokio::spawn(async move {
+        if let Err(e) = test_actor.start(&mut test_ctx).await {
+            error!("TestActor error: {}", e);
+        }
+    });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let options = SendOptions { timeout: Duration::from_secs(5), ..options };
+    let response =
+        relay_ctx.send_and_wait_reply::<TestActor, TestMessage>(message.clone(), &idle_id, options.clone()).await?;
+    assert_eq!(response.content, "Received: Hello");
+
+    // The circuit is closed again
+    relay_ctx.send_and_wait_reply::<TestActor, TestMessage>(message, &idle_id, options).await?;
+
+    test_handle.abort();
+    dbg_export_db!(engine);
+
+    Ok(())
+}
+
+#[derive(Default)]
+struct TestInterceptor {
+    replies: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+}
+
+impl Interceptor for TestInterceptor {
+    fn on_send(&self, frame: &mut FrameMessage) -> Result<(), SystemActorError> {
+        if frame.msg["content"] == "secret" {
+            frame.msg["content"] = "***".into();
+        }
+        if frame.msg["content"] == "unsendable" {
+            return Err(SystemActorError::MessageRejected("unsendable".into()));
+        }
+        Ok(())
+    }
+
+    fn on_recv(&self, frame: &mut FrameMessage) -> Result<(), SystemActorError> {
+        if frame.msg["content"] == "forbidden" {
+            return Err(SystemActorError::MessageRejected("forbidden".into()));
+        }
+        Ok(())
+    }
+
+    fn on_reply(&self, _reply: &FrameReply) {
+        self.replies.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+#[test(tokio::test)]
+async fn test_engine_interceptor() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+    let interceptor = TestInterceptor::default();
+    let replies = interceptor.replies.clone();
+    engine.add_interceptor(interceptor);
+
+    let test_id = ActorId::of::<TestActor>("/test_intercepted");
+    let (mut test_ctx, mut test_actor) =
+        Actor::spawn(engine.clone(), test_id.clone(), TestActor { count: 0 }, SpawnOptions::default()).await?;
+    let test_handle = tokio::spawn(async move {
+        if let Err(e) = test_actor.start(&mut test_ctx).await {
+            error!("TestActor error: {}", e);
+        }
+    });
+
+    let relay_id = ActorId::of::<Relay>("/relay");
+    let (relay_ctx, _relay_actor) =
+        Actor::spawn(engine.clone(), relay_id.clone(), Relay, SpawnOptions::default()).await?;
+
+    let message = |content: &str| TestMessage { content: content.to_string() };
+    let options = SendOptions::default();
+
+    // Messages are modified before they are stored
+    let response =
+        relay_ctx.send_and_wait_reply::<TestActor, TestMessage>(message("secret"), &test_id, options.clone()).await?;
+    assert_eq!(response.content, "Received: ***");
+    // The reply and the final reply
+    assert_eq!(replies.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+    // Rejected on send, nothing is stored
+    let result =
+        relay_ctx.send_and_wait_reply::<TestActor, TestMessage>(message("unsendable"), &test_id, options.clone()).await;
+    assert!(matches!(result, Err(SystemActorError::MessageRejected(_))));
+
+    // Rejected on receive, the sender gets the error and the actor never sees the message
+    let result =
+        relay_ctx.send_and_wait_reply::<TestActor, TestMessage>(message("forbidden"), &test_id, options.clone()).await;
+    assert!(matches!(result, Err(SystemActorError::ErrorReply(reply)) if reply.code == "MessageRejected"));
+    let response =
+        relay_ctx.send_and_wait_reply::<TestActor, TestMessage>(message("hello"), &test_id, options.clone()).await?;
+    assert_eq!(response.count, 2);
+
+    test_handle.abort();
+    dbg_export_db!(engine);
+
+    Ok(())
+}
+
+/// Keeps the first bytes of the large messages forwarded to workers
+struct TruncateForwarded;
+
+impl Interceptor for TruncateForwarded {
+    fn on_send(&self, frame: &mut FrameMessage) -> Result<(), SystemActorError> {
+        if frame.rx.to_string().contains("worker") {
+            if let Some(data) = frame.msg["data"].as_array_mut() {
+                data.truncate(10);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test(tokio::test)]
+async fn test_router_forwards_offloaded_messages() -> Result<(), TestError> {
+    let engine_options = EngineOptions::builder().payload_offload_threshold(1024).build();
+    let engine = Engine::test_with_options(engine_options).await?;
+    engine.registry().add("stateful", StatefulActorFactory).await?;
+
+    let router_id = ActorId::of::<Router>("/router");
+    let router = Router::builder().tag("stateful").config(serde_json::json!({ "count": 0 })).size(1).build();
+    let (mut router_ctx, mut router) =
+        Actor::spawn(engine.clone(), router_id.clone(), router, SpawnOptions::default()).await?;
+    let router_handle = tokio::spawn(async move { router.start(&mut router_ctx).await });
+
+    let relay_id = ActorId::of::<Relay>("/relay");
+    let (relay_ctx, _relay_actor) =
+        Actor::spawn(engine.clone(), relay_id.clone(), Relay, SpawnOptions::default()).await?;
+
+    let mut rng = rand::thread_rng();
+    let mut large_message = || LargeMessage { data: (0..10_000).map(|_| rng.gen()).collect() };
+
+    // Without interceptors, the worker loads the content offloaded by the sender
+    let response = relay_ctx
+        .send_and_wait_reply::<StatefulActor, LargeMessage>(large_message(), &router_id, SendOptions::default())
+        .await?;
+    assert_eq!(response, 10_000);
+
+    // Interceptors see the content of forwarded messages, and their changes are kept
+    engine.add_interceptor(TruncateForwarded);
+    let response = relay_ctx
+        .send_and_wait_reply::<StatefulActor, LargeMessage>(large_message(), &router_id, SendOptions::default())
+        .await?;
+    assert_eq!(response, 10);
+
+    // The offloaded content is deleted once handled
+    sleep(Duration::from_millis(200)).await;
+    let prefix = object_store::path::Path::from("payloads/message");
+    let payloads: Vec<_> = engine.store().list(Some(&prefix)).collect().await;
+    assert!(payloads.is_empty());
+
+    router_handle.abort();
+    dbg_export_db!(engine);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_engine_migrations() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+
+    // The engine's migrations are applied on connect
+    let engine_migrations = Engine::migrations();
+    let engine_version = engine.schema_version(engine_migrations.component()).await?;
+    assert_eq!(engine_version, engine_migrations.latest());
+
+    // Pending migrations are applied in order, once
+    let migrations = Migrations::new("notes")
+        .migration(2, "note_title", "DEFINE FIELD title ON note TYPE string;")
+        .migration(1, "init", "DEFINE TABLE note TYPE NORMAL SCHEMALESS;");
+    assert_eq!(engine.schema_version("notes").await?, None);
+    assert_eq!(engine.migrate(&migrations).await?, vec![1, 2]);
+    assert_eq!(engine.migrate(&migrations).await?, Vec::<u32>::new());
+    assert_eq!(engine.schema_version("notes").await?, Some(2));
+
+    // A failed migration is rolled back and leaves the version as it was
+    let migrations = migrations
+        .migration(3, "note_body", "DEFINE FIELD body ON note TYPE string; DEFINE FIELD title ON note TYPE string;")
+        .migration(4, "note_index", "DEFINE INDEX note_title ON note FIELDS title;");
+    let result = engine.migrate(&migrations).await;
+    assert!(matches!(result, Err(SystemActorError::MigrationFailed(component, 3, _)) if component == "notes"));
+    assert_eq!(engine.schema_version("notes").await?, Some(2));
+    let mut res = engine.db().lock().await.query("INFO FOR TABLE note").await?;
+    let info: Option<serde_json::Value> = res.take(0)?;
+    assert!(!info.unwrap_or_default().to_string().contains("body"));
+
+    // Resetting the database applies the engine's migrations again
+    engine.reset().await?;
+    assert_eq!(engine.schema_version(engine_migrations.component()).await?, engine_migrations.latest());
+    assert_eq!(engine.schema_version("notes").await?, None);
+
+    dbg_export_db!(engine);
+
+    Ok(())
+}
+
+impl Message<SlowEcho> for StatefulActor {
+    type Response = u32;
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, message: &SlowEcho) -> Result<(), Self::Error> {
+        ctx.reply(message.value).await?;
+        Ok(())
+    }
+}
+
+#[test(tokio::test)]
+async fn test_message_schemas() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+
+    let messages = engine.messages();
+    messages.add::<ConcurrentActor, SlowEcho>();
+    messages.add::<StatefulActor, IncrementCount>();
+    messages.add_with_schemas::<TestActor, TestMessage>(serde_json::json!(true), serde_json::json!(true));
+
+    // Messages are named as in frames, with the schemas of the message and its response
+    let slow_echo = messages.get(std::any::type_name::<SlowEcho>()).expect("SlowEcho isn't registered");
+    assert_eq!(slow_echo.actors.len(), 1);
+    assert!(slow_echo.actors.contains(std::any::type_name::<ConcurrentActor>()));
+    assert_eq!(slow_echo.request["properties"]["value"]["type"], "integer");
+    assert_eq!(slow_echo.response["type"], "integer");
+    assert_eq!(messages.handled_by::<StatefulActor>().len(), 1);
+
+    // A message handled by several actors is registered once
+    messages.add::<StatefulActor, SlowEcho>();
+    assert_eq!(messages.schemas().len(), 3);
+    assert_eq!(messages.get(std::any::type_name::<SlowEcho>()).unwrap().actors.len(), 2);
+
+    // All the messages are exported as one document
+    let document = messages.export();
+    let exported = &document["messages"][std::any::type_name::<IncrementCount>()];
+    assert_eq!(exported["actors"], serde_json::json!([std::any::type_name::<StatefulActor>()]));
+    assert_eq!(exported["response"]["type"], "integer");
+    assert_eq!(document["messages"][std::any::type_name::<TestMessage>()]["request"], true);
+
+    dbg_export_db!(engine);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_message_flow() -> Result<(), TestError> {
+    let engine = Engine::test().await?;
+    let mut flow = engine.message_flow(Duration::from_millis(200)).await?;
+
+    let stateful_id = ActorId::of::<StatefulActor>("/flow_stateful");
+    let (mut stateful_ctx, mut stateful_actor) =
+        Actor::spawn(engine.clone(), stateful_id.clone(), StatefulActor { count: 0 }, SpawnOptions::default()).await?;
+    let stateful_handle = tokio::spawn(async move {
+        if let Err(e) = stateful_actor.start(&mut stateful_ctx).await {
+            error!("StatefulActor error: {}", e);
+        }
+    });
+
+    let error_id = ActorId::of::<ErrorActor>("/flow_error");
+    let (mut error_ctx, mut error_actor) =
+        Actor::spawn(engine.clone(), error_id.clone(), ErrorActor, SpawnOptions::default()).await?;
+    let error_handle = tokio::spawn(async move {
+        if let Err(e) = error_actor.start(&mut error_ctx).await {
+            error!("ErrorActor error: {}", e);
+        }
+    });
+
+    let relay_id = ActorId::of::<Relay>("/flow_relay");
+    let (relay_ctx, _relay_actor) =
+        Actor::spawn(engine.clone(), relay_id.clone(), Relay, SpawnOptions::default()).await?;
+
+    for _ in 0..2 {
+        relay_ctx
+            .send_and_wait_reply::<StatefulActor, IncrementCount>(IncrementCount, &stateful_id, SendOptions::default())
+            .await?;
+    }
+    let result = relay_ctx
+        .send_and_wait_reply::<ErrorActor, TriggerError>(TriggerError, &error_id, SendOptions::default())
+        .await;
+    assert!(result.is_err());
+
+    // Add up the edges of the windows until all the replies are in
+    let mut edges: std::collections::HashMap<(String, String, String), FlowEdge> = std::collections::HashMap::new();
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+    while edges.values().map(|edge| edge.replied + edge.failed).sum::<u64>() < 3 {
+        let window = tokio::time::timeout_at(deadline, flow.next()).await.expect("no flow").expect("flow ended")?;
+        assert_eq!(window.window_ms, 200);
+        for edge in window.edges {
+            let total = edges.entry((edge.tx.clone(), edge.rx.clone(), edge.name.to_string())).or_default();
+            total.sent += edge.sent;
+            total.replied += edge.replied;
+            total.failed += edge.failed;
+            total.max_latency_ms = total.max_latency_ms.max(edge.max_latency_ms);
+        }
+    }
+
+    let key = |rx: &str, name: &str| ("/flow_relay".to_string(), rx.to_string(), name.to_string());
+    let stateful = &edges[&key("/flow_stateful", std::any::type_name::<IncrementCount>())];
+    assert_eq!((stateful.sent, stateful.replied, stateful.failed), (2, 2, 0));
+    assert!(stateful.max_latency_ms.is_some());
+    let failing = &edges[&key("/flow_error", std::any::type_name::<TriggerError>())];
+    assert_eq!((failing.sent, failing.replied, failing.failed), (1, 0, 1));
+
+    stateful_handle.abort();
+    error_handle.abort();
+    dbg_export_db!(engine);
+
+    Ok(())
+}
@@ -1,22 +1,26 @@
 use crate::prelude::*;
 use bioma_actor::prelude::*;
 use bon::Builder;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use tracing::{debug, error, info, warn};
 
 /// Logs a message at the specified level.
 ///
-/// The `Log` action logs a message when ticked and always returns success.
-#[derive(Builder, Debug, Serialize, Deserialize)]
+/// The `Log` action logs a message when ticked and always returns success. With a `key`, the
+/// blackboard value of that key is logged after the text, if it's set.
+#[derive(Builder, Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Log {
     pub level: LogLevel,
     pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
     #[serde(skip)]
     #[builder(skip)]
     pub node: behavior::Action,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub enum LogLevel {
     Error,
     Warn,
@@ -40,8 +44,8 @@ impl ActorFactory for LogFactory {
         options: SpawnOptions,
     ) -> Result<ActorHandle, SystemActorError> {
         let engine = engine.clone();
-        let node: tree::ActionNode = serde_json::from_value(config.clone()).unwrap();
-        let config: Log = serde_json::from_value(node.data.config.clone())?;
+        let (node, mut config) = tree::ActionNode::parse::<Log>(&config)?;
+        config.node.copy_blackboard(&node);
         Ok(tokio::spawn(async move {
             let (mut ctx, mut actor) = Actor::spawn(engine, id, config, options).await?;
             debug!("LogFactory::spawn: start {}", ctx.id());
@@ -50,22 +54,43 @@ impl ActorFactory for LogFactory {
             Ok(())
         }))
     }
+
+    crate::behavior_factory_config!(ActionNode, Log);
 }
 
 impl Message<BehaviorTick> for Log {
     type Response = BehaviorStatus;
 
     async fn handle(&mut self, ctx: &mut ActorContext<Self>, _msg: &BehaviorTick) -> Result<(), Self::Error> {
+        let mut text = self.text.clone();
+        if let (Some(key), Some(blackboard)) = (&self.key, self.node.blackboard()) {
+            if let Some(value) = blackboard.get::<serde_json::Value>(ctx.engine(), key).await? {
+                let value = match value {
+                    serde_json::Value::String(value) => value,
+                    value => value.to_string(),
+                };
+                text = format!("{} {}", text, value);
+            }
+        }
         match self.level {
-            LogLevel::Error => error!("{}", self.text),
-            LogLevel::Warn => warn!("{}", self.text),
-            LogLevel::Info => info!("{}", self.text),
+            LogLevel::Error => error!("{}", text),
+            LogLevel::Warn => warn!("{}", text),
+            LogLevel::Info => info!("{}", text),
         }
         ctx.reply(BehaviorStatus::Success).await?;
         Ok(())
     }
 }
 
+impl Message<BehaviorCancel> for Log {
+    type Response = ();
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, _msg: &BehaviorCancel) -> Result<(), Self::Error> {
+        ctx.reply(()).await?;
+        Ok(())
+    }
+}
+
 impl Actor for Log {
     type Error = SystemActorError;
 
@@ -74,7 +99,8 @@ impl Actor for Log {
         while let Some(Ok(frame)) = stream.next().await {
             if let Some(BehaviorTick) = frame.is::<BehaviorTick>() {
                 self.reply(ctx, &BehaviorTick, &frame).await?;
-                break;
+            } else if let Some(BehaviorCancel) = frame.is::<BehaviorCancel>() {
+                self.reply(ctx, &BehaviorCancel, &frame).await?;
             }
         }
         Ok(())
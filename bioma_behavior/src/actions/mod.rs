@@ -1,5 +1,7 @@
 pub mod log;
+mod set;
 mod wait;
 
 pub use log::{Log, LogFactory};
+pub use set::{Set, SetFactory};
 pub use wait::{Wait, WaitFactory};
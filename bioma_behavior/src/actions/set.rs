@@ -0,0 +1,89 @@
+use crate::prelude::*;
+use bioma_actor::prelude::*;
+use bon::Builder;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+/// Sets a value on the blackboard.
+///
+/// The `Set` action writes its value to the given blackboard key when ticked and returns success,
+/// so that later nodes can use it. It fails outside a tree, where there is no blackboard.
+#[derive(Builder, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct Set {
+    pub key: String,
+    pub value: serde_json::Value,
+    #[serde(skip)]
+    #[builder(skip)]
+    pub node: behavior::Action,
+}
+
+impl Behavior for Set {
+    fn node(&self) -> behavior::Node {
+        behavior::Node::Action(&self.node)
+    }
+}
+
+pub struct SetFactory;
+
+impl ActorFactory for SetFactory {
+    fn spawn(
+        &self,
+        engine: Engine,
+        config: serde_json::Value,
+        id: ActorId,
+        options: SpawnOptions,
+    ) -> Result<ActorHandle, SystemActorError> {
+        let engine = engine.clone();
+        let (node, mut config) = tree::ActionNode::parse::<Set>(&config)?;
+        config.node.copy_blackboard(&node);
+        Ok(tokio::spawn(async move {
+            let (mut ctx, mut actor) = Actor::spawn(engine, id, config, options).await?;
+            debug!("SetFactory::spawn: start {}", ctx.id());
+            actor.start(&mut ctx).await?;
+            debug!("SetFactory::spawn: end {}", ctx.id());
+            Ok(())
+        }))
+    }
+
+    crate::behavior_factory_config!(ActionNode, Set);
+}
+
+impl Message<BehaviorTick> for Set {
+    type Response = BehaviorStatus;
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, _msg: &BehaviorTick) -> Result<(), Self::Error> {
+        let Some(blackboard) = self.node.blackboard() else {
+            ctx.reply(BehaviorStatus::Failure).await?;
+            return Ok(());
+        };
+        blackboard.set(ctx.engine(), &self.key, &self.value).await?;
+        ctx.reply(BehaviorStatus::Success).await?;
+        Ok(())
+    }
+}
+
+impl Message<BehaviorCancel> for Set {
+    type Response = ();
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, _msg: &BehaviorCancel) -> Result<(), Self::Error> {
+        ctx.reply(()).await?;
+        Ok(())
+    }
+}
+
+impl Actor for Set {
+    type Error = SystemActorError;
+
+    async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), Self::Error> {
+        let mut stream = ctx.recv().await?;
+        while let Some(Ok(frame)) = stream.next().await {
+            if let Some(BehaviorTick) = frame.is::<BehaviorTick>() {
+                self.reply(ctx, &BehaviorTick, &frame).await?;
+            } else if let Some(BehaviorCancel) = frame.is::<BehaviorCancel>() {
+                self.reply(ctx, &BehaviorCancel, &frame).await?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,21 +1,27 @@
 use crate::prelude::*;
 use bioma_actor::prelude::*;
 use bon::Builder;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tracing::debug;
 
 /// Waits for a specified duration, then succeeds.
 ///
-/// The `Wait` action pauses for the given duration when ticked and always returns success after the
-/// delay period has elapsed.
-#[derive(Builder, Debug, Serialize, Deserialize)]
+/// The `Wait` action starts waiting on its first tick and returns running until the given duration has
+/// elapsed, then it returns success. Cancelling it resets the wait.
+#[derive(Builder, Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Wait {
     #[serde(with = "humantime_serde")]
+    #[schemars(with = "String")]
     pub duration: Duration,
     #[serde(skip)]
     #[builder(skip)]
     pub node: behavior::Action,
+    /// When the current wait started
+    #[serde(skip)]
+    #[builder(skip)]
+    started: Option<Instant>,
 }
 
 impl Behavior for Wait {
@@ -35,8 +41,8 @@ impl ActorFactory for WaitFactory {
         options: SpawnOptions,
     ) -> Result<ActorHandle, SystemActorError> {
         let engine = engine.clone();
-        let node: tree::ActionNode = serde_json::from_value(config.clone()).unwrap();
-        let config: Wait = serde_json::from_value(node.data.config.clone())?;
+        let (node, mut config) = tree::ActionNode::parse::<Wait>(&config)?;
+        config.node.copy_blackboard(&node);
         Ok(tokio::spawn(async move {
             let (mut ctx, mut actor) = Actor::spawn(engine, id, config, options).await?;
             debug!("WaitFactory::spawn: start {}", ctx.id());
@@ -45,14 +51,31 @@ impl ActorFactory for WaitFactory {
             Ok(())
         }))
     }
+
+    crate::behavior_factory_config!(ActionNode, Wait);
 }
 
 impl Message<BehaviorTick> for Wait {
     type Response = BehaviorStatus;
 
     async fn handle(&mut self, ctx: &mut ActorContext<Self>, _msg: &BehaviorTick) -> Result<(), Self::Error> {
-        tokio::time::sleep(self.duration).await;
-        ctx.reply(BehaviorStatus::Success).await?;
+        let started = *self.started.get_or_insert_with(Instant::now);
+        if started.elapsed() < self.duration {
+            ctx.reply(BehaviorStatus::Running).await?;
+        } else {
+            self.started = None;
+            ctx.reply(BehaviorStatus::Success).await?;
+        }
+        Ok(())
+    }
+}
+
+impl Message<BehaviorCancel> for Wait {
+    type Response = ();
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, _msg: &BehaviorCancel) -> Result<(), Self::Error> {
+        self.started = None;
+        ctx.reply(()).await?;
         Ok(())
     }
 }
@@ -65,7 +88,8 @@ impl Actor for Wait {
         while let Some(Ok(frame)) = stream.next().await {
             if let Some(BehaviorTick) = frame.is::<BehaviorTick>() {
                 self.reply(ctx, &BehaviorTick, &frame).await?;
-                break;
+            } else if let Some(BehaviorCancel) = frame.is::<BehaviorCancel>() {
+                self.reply(ctx, &BehaviorCancel, &frame).await?;
             }
         }
         Ok(())
@@ -1,14 +1,17 @@
+use crate::blackboard::Blackboard;
 use crate::tree;
 use bioma_actor::prelude::*;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::fmt::Debug;
+use tracing::debug;
 
 /// Represents a behavior in a behavior tree.
 ///
 /// This trait defines the core functionality for behaviors in a behavior tree system.
 /// Implementors of this trait can be used as nodes in a behavior tree.
-pub trait Behavior: Sized + Message<BehaviorTick> {
+pub trait Behavior: Sized + Message<BehaviorTick> + Message<BehaviorCancel> {
     /// Returns the node structure of this behavior.
     ///
     /// This method provides the actual node structure, which includes both the type
@@ -33,27 +36,55 @@ pub trait Behavior: Sized + Message<BehaviorTick> {
 ///
 /// This struct is typically sent to behavior nodes to initiate their processing
 /// during a behavior tree traversal.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 pub struct BehaviorTick;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// A message used to halt a running behavior.
+///
+/// Sent to a node that replied `Running` to its last tick when its parent no longer needs it,
+/// for example when an earlier child of a reactive `Sequence` fails. The node resets, so its
+/// next tick starts it again, halts its own running children, and then replies `()`.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 pub struct BehaviorCancel;
 
-/// Represents the final status of a behavior after execution.
+/// Represents the status of a behavior after a tick.
 ///
-/// Due to the asynchronous nature of behavior execution, behaviors that haven't
-/// returned a status yet are implicitly considered to be still running. This enum
-/// only represents the final, conclusive states of a behavior.
-///
-/// In an asynchronous context:
-/// - A behavior that hasn't completed its execution is considered "running".
-/// - Once a behavior completes, it will return either `Success` or `Failure`.
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+/// A behavior replies to every tick without waiting for its work to complete. Long behaviors reply
+/// `Running` and are ticked again by their parent, until they return `Success` or `Failure`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub enum BehaviorStatus {
     /// The behavior has completed successfully.
     Success,
     /// The behavior has failed to complete.
     Failure,
+    /// The behavior hasn't completed yet and should be ticked again.
+    Running,
+}
+
+/// Ticks a child behavior, waiting for its status.
+///
+/// A child that can't be reached or doesn't reply is considered to have failed.
+pub async fn tick<T: Actor>(ctx: &ActorContext<T>, child: &ActorId) -> BehaviorStatus {
+    match ctx
+        .send_as_and_wait_reply::<BehaviorTick, BehaviorStatus>(BehaviorTick, child.clone(), SendOptions::default())
+        .await
+    {
+        Ok(status) => status,
+        Err(e) => {
+            debug!("[{}] tick {} failed: {}", ctx.id().name(), child.name(), e);
+            BehaviorStatus::Failure
+        }
+    }
+}
+
+/// Halts a child behavior, waiting for it to be halted.
+///
+/// Waiting keeps the child from handling the parent's next tick before it's halted.
+pub async fn cancel<T: Actor>(ctx: &ActorContext<T>, child: &ActorId) {
+    let options = SendOptions::default();
+    if let Err(e) = ctx.send_as_and_wait_reply::<BehaviorCancel, ()>(BehaviorCancel, child.clone(), options).await {
+        debug!("[{}] cancel {} failed: {}", ctx.id().name(), child.name(), e);
+    }
 }
 
 /// Represents a node in a behavior tree.
@@ -95,7 +126,22 @@ impl<'a> Node<'a> {
 ///
 /// Action nodes are leaf nodes that perform specific tasks when executed.
 #[derive(Default, Debug)]
-pub struct Action {}
+pub struct Action {
+    /// The blackboard of the tree this node belongs to.
+    blackboard: Option<Blackboard>,
+}
+
+impl Action {
+    /// Copies the blackboard of a node, with its key remapping.
+    pub fn copy_blackboard(&mut self, node: &tree::ActionNode) {
+        self.blackboard = node.data.blackboard();
+    }
+
+    /// Returns the blackboard of the tree this node belongs to, None outside a tree.
+    pub fn blackboard(&self) -> Option<&Blackboard> {
+        self.blackboard.as_ref()
+    }
+}
 
 /// Represents a Decorator node in a behavior tree.
 ///
@@ -104,19 +150,30 @@ pub struct Action {}
 pub struct Decorator {
     /// The ActorId of the child node.
     child: Option<ActorId>,
-    /// The handle of the child node.
-    child_handle: Option<ActorHandle>,
     /// The data of the child node.
     child_data: Option<tree::Node>,
+    /// The blackboard of the tree this node belongs to.
+    blackboard: Option<Blackboard>,
 }
 
 impl Decorator {
     pub fn new() -> Self {
-        Self { child: None, child_handle: None, child_data: None }
+        Self { child: None, child_data: None, blackboard: None }
     }
 
+    /// Copies the child of a node, which shares the blackboard of the node.
     pub fn copy_child(&mut self, node: &tree::DecoratorNode) {
-        self.child_data = node.child.as_ref().map(|boxed_node| (**boxed_node).clone());
+        self.blackboard = node.data.blackboard();
+        self.child_data = node.child.as_ref().map(|boxed_node| {
+            let mut child = (**boxed_node).clone();
+            child.data_mut().blackboard = node.data.blackboard.clone();
+            child
+        });
+    }
+
+    /// Returns the blackboard of the tree this node belongs to, None outside a tree.
+    pub fn blackboard(&self) -> Option<&Blackboard> {
+        self.blackboard.as_ref()
     }
 
     /// Spawns or retrieves the child of this decorator node.
@@ -130,6 +187,8 @@ impl Decorator {
     /// 2. It uses the `ActorTagRegistry` to find the corresponding factory for that tag.
     /// 3. The factory then creates and spawns the actor with the provided configuration.
     ///
+    /// The child is spawned as a child actor of the decorator, so it stops along with it.
+    ///
     /// # Arguments
     ///
     /// * `ctx` - The actor context, which provides access to the engine and registry.
@@ -137,53 +196,43 @@ impl Decorator {
     ///
     /// # Returns
     ///
-    /// A `Future` that resolves to a `Result` containing:
+    /// A `Result` containing:
     /// - `Some(ActorId)` if a child exists or was successfully spawned.
     /// - `None` if there's no child data to spawn from.
     /// - `Err(SystemActorError)` if spawning fails.
-    ///
-    /// # Note
-    ///
-    /// If a child is successfully spawned, its handle is stored in `self.child_handle`.
-    pub fn child<'a, T: Actor>(
-        &'a mut self,
+    pub async fn child<T: Actor>(
+        &mut self,
         ctx: &mut ActorContext<T>,
         options: SpawnOptions,
-    ) -> impl Future<Output = Result<Option<ActorId>, SystemActorError>> + 'a {
-        let child_id = self.child.clone();
-        let child_data = self.child_data.clone();
-        let registry = ctx.engine().registry().clone();
-        let ctx_id = ctx.id().clone();
-        let engine = ctx.engine().clone();
+    ) -> Result<Option<ActorId>, SystemActorError> {
+        if let Some(child_id) = &self.child {
+            Ok(Some(child_id.clone()))
+        } else if let Some(child_data) = &self.child_data {
+            let data = child_data.data();
+            let (child_id, _handle) =
+                ctx.spawn_child_tagged(data.tag.clone(), data.uid.as_ref(), child_data.value(), options).await?;
+            self.child = Some(child_id.clone());
+            Ok(Some(child_id))
+        } else {
+            Ok(None)
+        }
+    }
 
-        async move {
-            if let Some(child_id) = child_id {
-                Ok(Some(child_id))
-            } else if let Some(child_data) = child_data {
-                let child_id = child_data.id(Some(&ctx_id));
-                let child_tag = child_data.data().tag.clone();
-                let child_config = child_data.value();
-                let child_handle = registry.spawn(child_tag, engine, child_config, child_id.clone(), options).await?;
-                self.child_handle = Some(child_handle);
-                Ok(Some(child_id))
-            } else {
-                Ok(None)
-            }
+    /// Halts the child of this decorator node, if it has been spawned.
+    pub async fn child_cancel<T: Actor>(&self, ctx: &ActorContext<T>) {
+        if let Some(child_id) = &self.child {
+            cancel(ctx, child_id).await;
         }
     }
 
     /// Stops the child of this decorator node.
     ///
-    /// This method removes the child's handle and ID from the decorator node's internal fields.
-    /// By releasing the handle, we expect the child and all its descendants to be stopped by the tokio runtime.
-    ///
-    /// # Note
-    ///
-    /// This method clears both the `child_handle` and `child` fields, effectively
-    /// disconnecting the decorator from its child node.
-    pub fn child_stop(&mut self) {
-        self.child_handle = None;
-        self.child = None;
+    /// The child's message stream ends, and its own children stop in turn.
+    /// The child is spawned again the next time `child` is called.
+    pub fn child_stop<T: Actor>(&mut self, ctx: &mut ActorContext<T>) {
+        if let Some(child_id) = self.child.take() {
+            ctx.stop_child(&child_id);
+        }
     }
 }
 
@@ -194,19 +243,29 @@ impl Decorator {
 pub struct Composite {
     /// A vector of ActorIds representing the children of this composite node.
     children: Vec<ActorId>,
-    /// A vector of handles for the children of this composite node.
-    children_handles: Vec<ActorHandle>,
     /// A vector of child data for the children of this composite node.
     children_data: Vec<tree::Node>,
+    /// The blackboard of the tree this node belongs to.
+    blackboard: Option<Blackboard>,
 }
 
 impl Composite {
     pub fn new() -> Self {
-        Self { children: Vec::new(), children_handles: Vec::new(), children_data: Vec::new() }
+        Self { children: Vec::new(), children_data: Vec::new(), blackboard: None }
     }
 
+    /// Copies the children of a node, which share the blackboard of the node.
     pub fn copy_children(&mut self, node: &tree::CompositeNode) {
+        self.blackboard = node.data.blackboard();
         self.children_data = node.children.clone();
+        for child in &mut self.children_data {
+            child.data_mut().blackboard = node.data.blackboard.clone();
+        }
+    }
+
+    /// Returns the blackboard of the tree this node belongs to, None outside a tree.
+    pub fn blackboard(&self) -> Option<&Blackboard> {
+        self.blackboard.as_ref()
     }
 
     /// Spawns or retrieves the children of this composite node.
@@ -220,6 +279,8 @@ impl Composite {
     /// 2. It uses the `ActorTagRegistry` to find the corresponding factory for that tag.
     /// 3. The factory then creates and spawns the actor with the provided configuration.
     ///
+    /// The children are spawned as child actors of the composite, so they stop along with it.
+    ///
     /// # Arguments
     ///
     /// * `ctx` - The actor context, which provides access to the engine and registry.
@@ -227,61 +288,57 @@ impl Composite {
     ///
     /// # Returns
     ///
-    /// A `Future` that resolves to a `Result` containing a vector of `ActorId`s for the children,
+    /// A `Result` containing a vector of `ActorId`s for the children,
     /// or a `SystemActorError` if spawning fails.
-    pub fn children<'a, T: Actor>(
-        &'a mut self,
+    pub async fn children<T: Actor>(
+        &mut self,
         ctx: &mut ActorContext<T>,
         options: SpawnOptions,
-    ) -> impl Future<Output = Result<Vec<ActorId>, SystemActorError>> + 'a {
-        let children = self.children.clone();
-        let children_data = self.children_data.clone();
-        let registry = ctx.engine().registry().clone();
-        let ctx_id = ctx.id().clone();
-        let engine = ctx.engine().clone();
-
-        async move {
-            let mut result = Vec::new();
-
-            if !children.is_empty() {
-                result = children;
-            } else {
-                for child_data in children_data {
-                    let child_id = child_data.id(Some(&ctx_id));
-                    let child_tag = child_data.data().tag.clone();
-                    let child_config = child_data.value();
-                    let child_handle = registry
-                        .spawn(child_tag, engine.clone(), child_config, child_id.clone(), options.clone())
-                        .await?;
-                    self.children_handles.push(child_handle);
-                    result.push(child_id);
-                }
-                self.children = result.clone();
+    ) -> Result<Vec<ActorId>, SystemActorError> {
+        if self.children.is_empty() {
+            for child_data in &self.children_data {
+                let data = child_data.data();
+                let (child_id, _handle) = ctx
+                    .spawn_child_tagged(data.tag.clone(), data.uid.as_ref(), child_data.value(), options.clone())
+                    .await?;
+                self.children.push(child_id);
             }
-
-            Ok(result)
         }
+        Ok(self.children.clone())
     }
 
     /// Stops a child of this composite node by its index.
     ///
-    /// This method removes the child's handle and ID from the composite node's internal lists.
-    /// By releasing the handle, we expect the child and all its children to be stopped by the tokio runtime.
+    /// The child's message stream ends, and its own children stop in turn.
     ///
     /// # Arguments
     ///
+    /// * `ctx` - The actor context the child was spawned from.
     /// * `idx` - The index of the child to stop.
     ///
     /// # Note
     ///
-    /// If the provided index is out of bounds for either the handles or children lists,
-    /// the method will silently do nothing for that list.
-    pub fn child_stop(&mut self, idx: usize) {
-        if idx < self.children_handles.len() {
-            self.children_handles.remove(idx);
-        }
+    /// If the provided index is out of bounds, the method silently does nothing.
+    pub fn child_stop<T: Actor>(&mut self, ctx: &mut ActorContext<T>, idx: usize) {
         if idx < self.children.len() {
-            self.children.remove(idx);
+            let child_id = self.children.remove(idx);
+            ctx.stop_child(&child_id);
+        }
+    }
+
+    /// Halts a child of this composite node by its index, if it has been spawned.
+    pub async fn child_cancel<T: Actor>(&self, ctx: &ActorContext<T>, idx: usize) {
+        if let Some(child_id) = self.children.get(idx) {
+            cancel(ctx, child_id).await;
+        }
+    }
+
+    /// Stops all the children of this composite node.
+    ///
+    /// The children are spawned again the next time `children` is called.
+    pub fn children_stop<T: Actor>(&mut self, ctx: &mut ActorContext<T>) {
+        for child_id in self.children.drain(..) {
+            ctx.stop_child(&child_id);
         }
     }
 
@@ -0,0 +1,172 @@
+use bioma_actor::prelude::*;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use surrealdb::{sql, RecordId};
+
+/// Component of the blackboard migrations, see `Engine::schema_version`
+const BLACKBOARD_SCHEMA: &str = "bioma_behavior";
+
+/// Field holding the value of a blackboard key, encrypted when the engine encrypts its payloads
+const VALUE_FIELD: &str = "value";
+
+/// Key-value store shared by the nodes of a behavior tree.
+///
+/// Each `BehaviorTree` actor has its own blackboard, persisted in the `blackboard` table with a record
+/// per key, so actions can pass results to later nodes. Nodes get the blackboard of their tree from
+/// their node helper, with the key remapping of their `NodeData` applied: a node using the key `text`
+/// with `"remap": { "text": "greeting" }` reads and writes the `greeting` key.
+///
+/// Values are encrypted like the other payloads of the engine. The blackboard is deleted with its tree,
+/// when the tree is killed or spawned with `SpawnExistsOptions::Reset`.
+///
+/// # Example
+///
+/// ```rust
+/// if let Some(blackboard) = self.node.blackboard() {
+///     blackboard.set(ctx.engine(), "count", &3).await?;
+///     let count: Option<u64> = blackboard.get(ctx.engine(), "count").await?;
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Blackboard {
+    /// Name of the tree the blackboard belongs to
+    tree: Cow<'static, str>,
+    /// Blackboard keys by the key a node uses for them
+    remap: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BlackboardEntry {
+    id: RecordId,
+    key: String,
+    #[serde(default)]
+    value: Value,
+}
+
+impl Blackboard {
+    /// Blackboard of a tree, without key remapping
+    pub fn of(tree: &ActorId) -> Self {
+        Self::new(tree.name().to_string())
+    }
+
+    /// Blackboard of a tree by its name, without key remapping
+    pub fn new(tree: impl Into<Cow<'static, str>>) -> Self {
+        Self { tree: tree.into(), remap: BTreeMap::new() }
+    }
+
+    /// Maps keys used by a node to blackboard keys
+    pub fn with_remap(mut self, remap: BTreeMap<String, String>) -> Self {
+        self.remap = remap;
+        self
+    }
+
+    /// Name of the tree the blackboard belongs to
+    pub fn tree(&self) -> &str {
+        &self.tree
+    }
+
+    /// Blackboard key for a key used by a node
+    pub fn key<'a>(&'a self, key: &'a str) -> &'a str {
+        self.remap.get(key).map(String::as_str).unwrap_or(key)
+    }
+
+    /// Id of the record of a key, the same as `type::thing('blackboard', [$tree, $key])`
+    fn record_id(&self, key: &str) -> RecordId {
+        let strand = |value: &str| surrealdb::Value::from_inner(sql::Value::Strand(sql::Strand::from(value)));
+        RecordId::from_table_key("blackboard", vec![strand(&self.tree), strand(self.key(key))])
+    }
+
+    /// Migrations of the blackboard table, applied by `register_blackboard`
+    pub fn migrations() -> Migrations {
+        Migrations::new(BLACKBOARD_SCHEMA).migration(
+            1,
+            "blackboard",
+            include_str!("../sql/migrations/0001_blackboard.surql"),
+        )
+    }
+
+    /// The blackboard table, whose records belong to their tree
+    pub fn actor_table() -> ActorTable {
+        ActorTable { table: "blackboard".into(), owner: "tree".into(), encrypted: vec![VALUE_FIELD.into()] }
+    }
+
+    /// Gets the value of a key, None if it isn't set
+    pub async fn get<V: DeserializeOwned>(&self, engine: &Engine, key: &str) -> Result<Option<V>, SystemActorError> {
+        let query = "SELECT id, key, value FROM type::thing('blackboard', [$tree, $key])";
+        let mut res = engine
+            .db()
+            .lock()
+            .await
+            .query(query)
+            .bind(("tree", self.tree.to_string()))
+            .bind(("key", self.key(key).to_string()))
+            .await?;
+        let entries: Vec<BlackboardEntry> = res.take(0)?;
+        match entries.into_iter().next() {
+            Some(entry) => Ok(Some(serde_json::from_value(decrypt(engine, entry)?.1)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Sets the value of a key
+    pub async fn set<V: Serialize>(&self, engine: &Engine, key: &str, value: &V) -> Result<(), SystemActorError> {
+        // The encrypted value is bound to the id of its record
+        let id = self.record_id(key);
+        let value = engine.encrypt(serde_json::to_value(value)?, &id.to_string(), VALUE_FIELD)?;
+
+        let query = "UPSERT $id CONTENT { tree: $tree, key: $key, value: $value }";
+        engine
+            .db()
+            .lock()
+            .await
+            .query(query)
+            .bind(("id", id))
+            .bind(("tree", self.tree.to_string()))
+            .bind(("key", self.key(key).to_string()))
+            .bind(("value", value))
+            .await?
+            .check()?;
+        Ok(())
+    }
+
+    /// Removes a key
+    pub async fn remove(&self, engine: &Engine, key: &str) -> Result<(), SystemActorError> {
+        let query = "DELETE type::thing('blackboard', [$tree, $key])";
+        engine
+            .db()
+            .lock()
+            .await
+            .query(query)
+            .bind(("tree", self.tree.to_string()))
+            .bind(("key", self.key(key).to_string()))
+            .await?
+            .check()?;
+        Ok(())
+    }
+
+    /// All the values of the blackboard, by blackboard key
+    pub async fn values(&self, engine: &Engine) -> Result<BTreeMap<String, Value>, SystemActorError> {
+        let query = "SELECT id, key, value FROM blackboard WHERE tree = $tree";
+        let mut res = engine.db().lock().await.query(query).bind(("tree", self.tree.to_string())).await?;
+        let entries: Vec<BlackboardEntry> = res.take(0)?;
+        entries.into_iter().map(|entry| decrypt(engine, entry)).collect()
+    }
+
+    /// Sets the values of the keys that aren't set yet, keeping values persisted by an earlier run
+    pub(crate) async fn init(&self, engine: &Engine, values: &BTreeMap<String, Value>) -> Result<(), SystemActorError> {
+        for (key, value) in values {
+            if self.get::<Value>(engine, key).await?.is_none() {
+                self.set(engine, key, value).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Key and decrypted value of a blackboard entry
+fn decrypt(engine: &Engine, entry: BlackboardEntry) -> Result<(String, Value), SystemActorError> {
+    let value = engine.decrypt(entry.value, &entry.id.to_string(), VALUE_FIELD)?;
+    Ok((entry.key, value))
+}
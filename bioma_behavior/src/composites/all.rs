@@ -2,19 +2,26 @@ use crate::prelude::*;
 use bioma_actor::prelude::*;
 use bon::Builder;
 use futures::StreamExt;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 use tracing::debug;
 
 /// Executes all child nodes in parallel and succeeds only if all succeed.
 ///
 /// The `All` composite node runs each of its child nodes concurrently. If any child node fails, the `All` node
-/// immediately fails and all other child nodes are interrupted; otherwise, it succeeds once all
-/// child nodes have successfully completed.
-#[derive(Builder, Debug, Serialize, Deserialize)]
+/// immediately fails and all other child nodes are cancelled; otherwise, it succeeds once all
+/// child nodes have successfully completed, and returns running until then. Children that succeeded
+/// aren't ticked again until the node completes.
+#[derive(Builder, Debug, Serialize, Deserialize, JsonSchema)]
 pub struct All {
     #[serde(skip)]
     #[builder(skip)]
     pub node: behavior::Composite,
+    /// Indices of the children that succeeded since the node was last ticked to completion
+    #[serde(skip)]
+    #[builder(skip)]
+    succeeded: BTreeSet<usize>,
 }
 
 impl Behavior for All {
@@ -34,8 +41,7 @@ impl ActorFactory for AllFactory {
         options: SpawnOptions,
     ) -> Result<ActorHandle, SystemActorError> {
         let engine = engine.clone();
-        let node: tree::CompositeNode = serde_json::from_value(config.clone()).unwrap();
-        let mut config: All = serde_json::from_value(node.data.config.clone())?;
+        let (node, mut config) = tree::CompositeNode::parse::<All>(&config)?;
         config.node.copy_children(&node);
         Ok(tokio::spawn(async move {
             let (mut ctx, mut actor) = Actor::spawn(engine, id, config, options).await?;
@@ -45,6 +51,8 @@ impl ActorFactory for AllFactory {
             Ok(())
         }))
     }
+
+    crate::behavior_factory_config!(CompositeNode, All);
 }
 
 impl Message<BehaviorTick> for All {
@@ -53,39 +61,50 @@ impl Message<BehaviorTick> for All {
     async fn handle(&mut self, ctx: &mut ActorContext<Self>, _msg: &BehaviorTick) -> Result<(), Self::Error> {
         let children = self.node.children(ctx, SpawnOptions::default()).await?;
 
-        // Create a future for each child and pin it
+        // Create a future for each child that hasn't succeeded yet and pin it
+        let tick_ctx = &*ctx;
         let futures = children
             .iter()
-            .map(|child| Box::pin(ctx.send_as_and_wait_reply(BehaviorTick, child.clone(), SendOptions::default())))
+            .enumerate()
+            .filter(|(idx, _)| !self.succeeded.contains(idx))
+            .map(|(idx, child)| Box::pin(async move { (idx, behavior::tick(tick_ctx, child).await) }))
             .collect::<Vec<_>>();
 
         // Use futures::future::select_all to run all futures concurrently
         let mut remaining_futures = futures;
-        let mut overall_status = BehaviorStatus::Success;
+        let mut decided = None;
 
-        // Wait for all futures to complete
+        // Wait for all futures to complete, or for one to fail
         while !remaining_futures.is_empty() {
-            let (result, _index, remaining) = futures::future::select_all(remaining_futures).await;
+            let ((idx, status), _index, remaining) = futures::future::select_all(remaining_futures).await;
             remaining_futures = remaining;
 
-            match result {
-                Ok(BehaviorStatus::Failure) => {
-                    overall_status = BehaviorStatus::Failure;
-                    break;
+            match status {
+                BehaviorStatus::Success => {
+                    self.succeeded.insert(idx);
                 }
-                Err(_) => {
-                    overall_status = BehaviorStatus::Failure;
+                BehaviorStatus::Failure => {
+                    decided = Some(idx);
                     break;
                 }
-                _ => continue,
+                BehaviorStatus::Running => continue,
             }
         }
+        drop(remaining_futures);
 
-        if overall_status == BehaviorStatus::Failure {
-            // Stop all children
-            for child_idx in 0..children.len() {
-                self.node.child_stop(child_idx);
+        let overall_status = if let Some(decided) = decided {
+            // Interrupt all other children
+            for idx in (0..children.len()).filter(|idx| *idx != decided && !self.succeeded.contains(idx)) {
+                self.node.child_cancel(ctx, idx).await;
             }
+            BehaviorStatus::Failure
+        } else if self.succeeded.len() == children.len() {
+            BehaviorStatus::Success
+        } else {
+            BehaviorStatus::Running
+        };
+        if overall_status != BehaviorStatus::Running {
+            self.succeeded.clear();
         }
 
         ctx.reply(overall_status).await?;
@@ -93,6 +112,19 @@ impl Message<BehaviorTick> for All {
     }
 }
 
+impl Message<BehaviorCancel> for All {
+    type Response = ();
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, _msg: &BehaviorCancel) -> Result<(), Self::Error> {
+        for idx in (0..self.node.num_children()).filter(|idx| !self.succeeded.contains(idx)) {
+            self.node.child_cancel(ctx, idx).await;
+        }
+        self.succeeded.clear();
+        ctx.reply(()).await?;
+        Ok(())
+    }
+}
+
 impl Actor for All {
     type Error = SystemActorError;
 
@@ -101,6 +133,8 @@ impl Actor for All {
         while let Some(Ok(frame)) = stream.next().await {
             if let Some(BehaviorTick) = frame.is::<BehaviorTick>() {
                 self.reply(ctx, &BehaviorTick, &frame).await?;
+            } else if let Some(BehaviorCancel) = frame.is::<BehaviorCancel>() {
+                self.reply(ctx, &BehaviorCancel, &frame).await?;
             }
         }
         Ok(())
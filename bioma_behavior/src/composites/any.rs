@@ -1,19 +1,26 @@
 use crate::prelude::*;
 use bioma_actor::prelude::*;
 use bon::Builder;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 use tracing::debug;
 
 /// Executes all child nodes in parallel and succeeds if any one of them succeeds.
 ///
 /// The `Any` composite node runs each of its child nodes concurrently. If any child node succeeds, the `Any` node
-/// immediately succeeds and interrupts all other running child nodes; if all child nodes fail,
-/// then the `Any` node fails.
-#[derive(Builder, Debug, Serialize, Deserialize)]
+/// immediately succeeds and cancels all other running child nodes; if all child nodes fail,
+/// then the `Any` node fails, and it returns running until then. Children that failed
+/// aren't ticked again until the node completes.
+#[derive(Builder, Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Any {
     #[serde(skip)]
     #[builder(skip)]
     pub node: behavior::Composite,
+    /// Indices of the children that failed since the node was last ticked to completion
+    #[serde(skip)]
+    #[builder(skip)]
+    failed: BTreeSet<usize>,
 }
 
 impl Behavior for Any {
@@ -33,8 +40,7 @@ impl ActorFactory for AnyFactory {
         options: SpawnOptions,
     ) -> Result<ActorHandle, SystemActorError> {
         let engine = engine.clone();
-        let node: tree::CompositeNode = serde_json::from_value(config.clone()).unwrap();
-        let mut config: Any = serde_json::from_value(node.data.config.clone())?;
+        let (node, mut config) = tree::CompositeNode::parse::<Any>(&config)?;
         config.node.copy_children(&node);
         Ok(tokio::spawn(async move {
             let (mut ctx, mut actor) = Actor::spawn(engine, id, config, options).await?;
@@ -44,6 +50,8 @@ impl ActorFactory for AnyFactory {
             Ok(())
         }))
     }
+
+    crate::behavior_factory_config!(CompositeNode, Any);
 }
 
 impl Message<BehaviorTick> for Any {
@@ -52,36 +60,50 @@ impl Message<BehaviorTick> for Any {
     async fn handle(&mut self, ctx: &mut ActorContext<Self>, _msg: &BehaviorTick) -> Result<(), Self::Error> {
         let children = self.node.children(ctx, SpawnOptions::default()).await?;
 
-        // Create a future for each child and pin it
+        // Create a future for each child that hasn't failed yet and pin it
+        let tick_ctx = &*ctx;
         let futures = children
             .iter()
-            .map(|child| Box::pin(ctx.send_as_and_wait_reply(BehaviorTick, child.clone(), SendOptions::default())))
+            .enumerate()
+            .filter(|(idx, _)| !self.failed.contains(idx))
+            .map(|(idx, child)| Box::pin(async move { (idx, behavior::tick(tick_ctx, child).await) }))
             .collect::<Vec<_>>();
 
         // Use futures::future::select_all to run all futures concurrently
         let mut remaining_futures = futures;
-        let mut overall_status = BehaviorStatus::Failure;
+        let mut decided = None;
 
-        // Wait for any future to complete
+        // Wait for all futures to complete, or for one to succeed
         while !remaining_futures.is_empty() {
-            let (result, _index, remaining) = futures::future::select_all(remaining_futures).await;
+            let ((idx, status), _index, remaining) = futures::future::select_all(remaining_futures).await;
             remaining_futures = remaining;
 
-            match result {
-                Ok(BehaviorStatus::Success) => {
-                    overall_status = BehaviorStatus::Success;
+            match status {
+                BehaviorStatus::Failure => {
+                    self.failed.insert(idx);
+                }
+                BehaviorStatus::Success => {
+                    decided = Some(idx);
                     break;
                 }
-                Err(_) => continue,
-                _ => continue,
+                BehaviorStatus::Running => continue,
             }
         }
+        drop(remaining_futures);
 
-        if overall_status == BehaviorStatus::Success {
-            // Stop all children
-            for child_idx in 0..children.len() {
-                self.node.child_stop(child_idx);
+        let overall_status = if let Some(decided) = decided {
+            // Interrupt all other children
+            for idx in (0..children.len()).filter(|idx| *idx != decided && !self.failed.contains(idx)) {
+                self.node.child_cancel(ctx, idx).await;
             }
+            BehaviorStatus::Success
+        } else if self.failed.len() == children.len() {
+            BehaviorStatus::Failure
+        } else {
+            BehaviorStatus::Running
+        };
+        if overall_status != BehaviorStatus::Running {
+            self.failed.clear();
         }
 
         ctx.reply(overall_status).await?;
@@ -89,6 +111,19 @@ impl Message<BehaviorTick> for Any {
     }
 }
 
+impl Message<BehaviorCancel> for Any {
+    type Response = ();
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, _msg: &BehaviorCancel) -> Result<(), Self::Error> {
+        for idx in (0..self.node.num_children()).filter(|idx| !self.failed.contains(idx)) {
+            self.node.child_cancel(ctx, idx).await;
+        }
+        self.failed.clear();
+        ctx.reply(()).await?;
+        Ok(())
+    }
+}
+
 impl Actor for Any {
     type Error = SystemActorError;
 
@@ -97,6 +132,8 @@ impl Actor for Any {
         while let Some(Ok(frame)) = stream.next().await {
             if let Some(BehaviorTick) = frame.is::<BehaviorTick>() {
                 self.reply(ctx, &BehaviorTick, &frame).await?;
+            } else if let Some(BehaviorCancel) = frame.is::<BehaviorCancel>() {
+                self.reply(ctx, &BehaviorCancel, &frame).await?;
             }
         }
         Ok(())
@@ -1,6 +1,7 @@
 use crate::prelude::*;
 use bioma_actor::prelude::*;
 use bon::Builder;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use tracing::debug;
 
@@ -8,12 +9,24 @@ use tracing::debug;
 ///
 /// The `Sequence` composite node processes its children one by one in order. It returns success only if
 /// all child nodes succeed. If a child fails, the `Sequence` node immediately fails. If a child
-/// returns running, the `Sequence` node also returns running.
-#[derive(Builder, Debug, Serialize, Deserialize)]
+/// returns running, the `Sequence` node also returns running, and resumes from that child on the next tick.
+///
+/// A `reactive` sequence starts again from its first child on every tick instead, so earlier children
+/// are re-evaluated while a later one is running. If one of them fails or returns running, the running
+/// child is cancelled.
+#[derive(Builder, Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Sequence {
+    /// Whether to tick the children before the running one again on every tick
+    #[serde(default)]
+    #[builder(default)]
+    pub reactive: bool,
     #[serde(skip)]
     #[builder(skip)]
     pub node: behavior::Composite,
+    /// Index of the child that returned running on the last tick
+    #[serde(skip)]
+    #[builder(skip)]
+    running: Option<usize>,
 }
 
 impl Behavior for Sequence {
@@ -33,8 +46,7 @@ impl ActorFactory for SequenceFactory {
         options: SpawnOptions,
     ) -> Result<ActorHandle, SystemActorError> {
         let engine = engine.clone();
-        let node: tree::CompositeNode = serde_json::from_value(config.clone()).unwrap();
-        let mut config: Sequence = serde_json::from_value(node.data.config.clone())?;
+        let (node, mut config) = tree::CompositeNode::parse::<Sequence>(&config)?;
         config.node.copy_children(&node);
         Ok(tokio::spawn(async move {
             let (mut ctx, mut actor) = Actor::spawn(engine, id, config, options).await?;
@@ -44,32 +56,49 @@ impl ActorFactory for SequenceFactory {
             Ok(())
         }))
     }
+
+    crate::behavior_factory_config!(CompositeNode, Sequence);
 }
 
 impl Message<BehaviorTick> for Sequence {
     type Response = BehaviorStatus;
 
     async fn handle(&mut self, ctx: &mut ActorContext<Self>, _msg: &BehaviorTick) -> Result<(), Self::Error> {
-        // Iterate over all children until one fails
-        for child in self.node.children(ctx, SpawnOptions::default()).await? {
-            let status = ctx.send_as_and_wait_reply(BehaviorTick, child, SendOptions::default()).await;
-            match status {
-                Ok(BehaviorStatus::Success) => continue,
-                Ok(BehaviorStatus::Failure) => {
-                    ctx.reply(BehaviorStatus::Failure).await?;
-                    return Ok(());
-                }
-                Err(_e) => {
-                    ctx.reply(BehaviorStatus::Failure).await?;
-                    return Ok(());
-                }
+        let children = self.node.children(ctx, SpawnOptions::default()).await?;
+        let first = if self.reactive { 0 } else { self.running.unwrap_or(0) };
+
+        // Iterate over the children until one fails or is running
+        for (idx, child) in children.iter().enumerate().skip(first) {
+            let status = behavior::tick(ctx, child).await;
+            if status == BehaviorStatus::Success {
+                continue;
+            }
+            // An earlier child took over from the running one
+            if let Some(running) = self.running.filter(|running| *running > idx) {
+                self.node.child_cancel(ctx, running).await;
             }
+            self.running = (status == BehaviorStatus::Running).then_some(idx);
+            ctx.reply(status).await?;
+            return Ok(());
         }
+        self.running = None;
         ctx.reply(BehaviorStatus::Success).await?;
         Ok(())
     }
 }
 
+impl Message<BehaviorCancel> for Sequence {
+    type Response = ();
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, _msg: &BehaviorCancel) -> Result<(), Self::Error> {
+        if let Some(running) = self.running.take() {
+            self.node.child_cancel(ctx, running).await;
+        }
+        ctx.reply(()).await?;
+        Ok(())
+    }
+}
+
 impl Actor for Sequence {
     type Error = SystemActorError;
 
@@ -78,7 +107,8 @@ impl Actor for Sequence {
         while let Some(Ok(frame)) = stream.next().await {
             if let Some(BehaviorTick) = frame.is::<BehaviorTick>() {
                 self.reply(ctx, &BehaviorTick, &frame).await?;
-                break;
+            } else if let Some(BehaviorCancel) = frame.is::<BehaviorCancel>() {
+                self.reply(ctx, &BehaviorCancel, &frame).await?;
             }
         }
         Ok(())
@@ -1,14 +1,16 @@
 use crate::prelude::*;
 use bioma_actor::prelude::*;
 use bon::Builder;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use tracing::debug;
 
 /// Always returns a specified status, regardless of its child node's result.
 ///
 /// The `Always` decorator node executes its child node but always returns the
-/// configured status (Success or Failure), ignoring the child's result.
-#[derive(Builder, Debug, Serialize, Deserialize)]
+/// configured status (Success or Failure) once the child completes, ignoring the child's result.
+/// While the child is running, it returns running.
+#[derive(Builder, Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Always {
     pub success: bool,
     #[serde(skip)]
@@ -32,8 +34,7 @@ impl ActorFactory for AlwaysFactory {
         id: ActorId,
         options: SpawnOptions,
     ) -> Result<ActorHandle, SystemActorError> {
-        let node: crate::tree::DecoratorNode = serde_json::from_value(config.clone()).unwrap();
-        let mut config: Always = serde_json::from_value(node.data.config.clone())?;
+        let (node, mut config) = crate::tree::DecoratorNode::parse::<Always>(&config)?;
         config.node.copy_child(&node);
         Ok(tokio::spawn(async move {
             let (mut ctx, mut actor) = Actor::spawn(engine, id, config, options).await?;
@@ -43,6 +44,8 @@ impl ActorFactory for AlwaysFactory {
             Ok(())
         }))
     }
+
+    crate::behavior_factory_config!(DecoratorNode, Always);
 }
 
 impl Message<BehaviorTick> for Always {
@@ -54,21 +57,37 @@ impl Message<BehaviorTick> for Always {
             return Ok(());
         };
         // Execute the child node but ignore its result
-        let _status: Result<BehaviorStatus, SystemActorError> =
-            ctx.send_as_and_wait_reply(BehaviorTick, child.clone(), SendOptions::default()).await;
+        if behavior::tick(ctx, &child).await == BehaviorStatus::Running {
+            ctx.reply(BehaviorStatus::Running).await?;
+            return Ok(());
+        }
         // Return the configured status
         ctx.reply(self.get_configured_status()).await?;
         Ok(())
     }
 }
 
+impl Message<BehaviorCancel> for Always {
+    type Response = ();
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, _msg: &BehaviorCancel) -> Result<(), Self::Error> {
+        self.node.child_cancel(ctx).await;
+        ctx.reply(()).await?;
+        Ok(())
+    }
+}
+
 impl Actor for Always {
     type Error = SystemActorError;
 
     async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), Self::Error> {
         let mut stream = ctx.recv().await?;
         while let Some(Ok(frame)) = stream.next().await {
-            self.reply(ctx, &BehaviorTick, &frame).await?;
+            if let Some(BehaviorTick) = frame.is::<BehaviorTick>() {
+                self.reply(ctx, &BehaviorTick, &frame).await?;
+            } else if let Some(BehaviorCancel) = frame.is::<BehaviorCancel>() {
+                self.reply(ctx, &BehaviorCancel, &frame).await?;
+            }
         }
         Ok(())
     }
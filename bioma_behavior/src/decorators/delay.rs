@@ -1,21 +1,27 @@
 use crate::prelude::*;
 use bioma_actor::prelude::*;
 use bon::Builder;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tracing::debug;
 
 /// Delays execution before proceeding with its child node.
 ///
-/// The `Delay` decorator node pauses for a specified duration before executing its child node. It returns the result
-/// of the child node's execution.
-#[derive(Builder, Debug, Serialize, Deserialize)]
+/// The `Delay` decorator node returns running for a specified duration after its first tick, then it ticks its
+/// child node and returns the result of the child node's execution. The delay starts again once the child completes.
+#[derive(Builder, Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Delay {
     #[serde(with = "humantime_serde")]
+    #[schemars(with = "String")]
     pub duration: Duration,
     #[serde(skip)]
     #[builder(skip)]
     pub node: behavior::Decorator,
+    /// When the current delay started
+    #[serde(skip)]
+    #[builder(skip)]
+    started: Option<Instant>,
 }
 
 impl Behavior for Delay {
@@ -34,8 +40,7 @@ impl ActorFactory for DelayFactory {
         id: ActorId,
         options: SpawnOptions,
     ) -> Result<ActorHandle, SystemActorError> {
-        let node: crate::tree::DecoratorNode = serde_json::from_value(config.clone()).unwrap();
-        let mut config: Delay = serde_json::from_value(node.data.config.clone())?;
+        let (node, mut config) = crate::tree::DecoratorNode::parse::<Delay>(&config)?;
         config.node.copy_child(&node);
         Ok(tokio::spawn(async move {
             let (mut ctx, mut actor) = Actor::spawn(engine, id, config, options).await?;
@@ -45,27 +50,43 @@ impl ActorFactory for DelayFactory {
             Ok(())
         }))
     }
+
+    crate::behavior_factory_config!(DecoratorNode, Delay);
 }
 
 impl Message<BehaviorTick> for Delay {
     type Response = BehaviorStatus;
 
     async fn handle(&mut self, ctx: &mut ActorContext<Self>, _msg: &BehaviorTick) -> Result<(), Self::Error> {
-        tokio::time::sleep(self.duration).await;
+        let started = *self.started.get_or_insert_with(Instant::now);
+        if started.elapsed() < self.duration {
+            ctx.reply(BehaviorStatus::Running).await?;
+            return Ok(());
+        }
 
         let Some(child) = self.node.child(ctx, SpawnOptions::default()).await? else {
+            self.started = None;
             ctx.reply(BehaviorStatus::Success).await?;
             return Ok(());
         };
 
-        match ctx
-            .send_as_and_wait_reply::<BehaviorTick, BehaviorStatus>(BehaviorTick, child.clone(), SendOptions::default())
-            .await
-        {
-            Ok(status) => ctx.reply(status).await?,
-            Err(_) => ctx.reply(BehaviorStatus::Failure).await?,
+        let status = behavior::tick(ctx, &child).await;
+        if status != BehaviorStatus::Running {
+            self.started = None;
         }
+        ctx.reply(status).await?;
+        Ok(())
+    }
+}
 
+impl Message<BehaviorCancel> for Delay {
+    type Response = ();
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, _msg: &BehaviorCancel) -> Result<(), Self::Error> {
+        if self.started.take().is_some() {
+            self.node.child_cancel(ctx).await;
+        }
+        ctx.reply(()).await?;
         Ok(())
     }
 }
@@ -76,7 +97,11 @@ impl Actor for Delay {
     async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), Self::Error> {
         let mut stream = ctx.recv().await?;
         while let Some(Ok(frame)) = stream.next().await {
-            self.reply(ctx, &BehaviorTick, &frame).await?;
+            if let Some(BehaviorTick) = frame.is::<BehaviorTick>() {
+                self.reply(ctx, &BehaviorTick, &frame).await?;
+            } else if let Some(BehaviorCancel) = frame.is::<BehaviorCancel>() {
+                self.reply(ctx, &BehaviorCancel, &frame).await?;
+            }
         }
         Ok(())
     }
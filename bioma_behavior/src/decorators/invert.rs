@@ -1,6 +1,7 @@
 use crate::prelude::*;
 use bioma_actor::prelude::*;
 use bon::Builder;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use tracing::debug;
 
@@ -8,7 +9,7 @@ use tracing::debug;
 ///
 /// The `Invert` decorator node executes its child node and then inverts the result:
 /// Success becomes Failure, Failure becomes Success, and Running remains unchanged.
-#[derive(Builder, Debug, Serialize, Deserialize)]
+#[derive(Builder, Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Invert {
     #[serde(skip)]
     #[builder(skip)]
@@ -31,8 +32,7 @@ impl ActorFactory for InvertFactory {
         id: ActorId,
         options: SpawnOptions,
     ) -> Result<ActorHandle, SystemActorError> {
-        let node: crate::tree::DecoratorNode = serde_json::from_value(config.clone()).unwrap();
-        let mut config: Invert = serde_json::from_value(node.data.config.clone())?;
+        let (node, mut config) = crate::tree::DecoratorNode::parse::<Invert>(&config)?;
         config.node.copy_child(&node);
         Ok(tokio::spawn(async move {
             let (mut ctx, mut actor) = Actor::spawn(engine, id, config, options).await?;
@@ -42,6 +42,8 @@ impl ActorFactory for InvertFactory {
             Ok(())
         }))
     }
+
+    crate::behavior_factory_config!(DecoratorNode, Invert);
 }
 
 impl Message<BehaviorTick> for Invert {
@@ -54,13 +56,10 @@ impl Message<BehaviorTick> for Invert {
         };
 
         // Execute the child node and invert its result
-        let status = match ctx
-            .send_as_and_wait_reply::<BehaviorTick, BehaviorStatus>(BehaviorTick, child.clone(), SendOptions::default())
-            .await
-        {
-            Ok(BehaviorStatus::Success) => BehaviorStatus::Failure,
-            Ok(BehaviorStatus::Failure) => BehaviorStatus::Success,
-            Err(_) => BehaviorStatus::Failure,
+        let status = match behavior::tick(ctx, &child).await {
+            BehaviorStatus::Success => BehaviorStatus::Failure,
+            BehaviorStatus::Failure => BehaviorStatus::Success,
+            BehaviorStatus::Running => BehaviorStatus::Running,
         };
 
         ctx.reply(status).await?;
@@ -68,13 +67,27 @@ impl Message<BehaviorTick> for Invert {
     }
 }
 
+impl Message<BehaviorCancel> for Invert {
+    type Response = ();
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, _msg: &BehaviorCancel) -> Result<(), Self::Error> {
+        self.node.child_cancel(ctx).await;
+        ctx.reply(()).await?;
+        Ok(())
+    }
+}
+
 impl Actor for Invert {
     type Error = SystemActorError;
 
     async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), Self::Error> {
         let mut stream = ctx.recv().await?;
         while let Some(Ok(frame)) = stream.next().await {
-            self.reply(ctx, &BehaviorTick, &frame).await?;
+            if let Some(BehaviorTick) = frame.is::<BehaviorTick>() {
+                self.reply(ctx, &BehaviorTick, &frame).await?;
+            } else if let Some(BehaviorCancel) = frame.is::<BehaviorCancel>() {
+                self.reply(ctx, &BehaviorCancel, &frame).await?;
+            }
         }
         Ok(())
     }
@@ -1,23 +1,30 @@
 use crate::prelude::*;
 use bioma_actor::prelude::*;
 use bon::Builder;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::time::timeout;
 use tracing::debug;
 
 /// Executes its child node with a timeout.
 ///
-/// The `Timeout` decorator node attempts to execute its child node within a specified duration.
-/// If the child node completes before the timeout, it returns the child's result.
-/// If the timeout occurs first, it returns a failure status.
-#[derive(Builder, Debug, Serialize, Deserialize)]
+/// The `Timeout` decorator node attempts to execute its child node within a specified duration,
+/// counted from the first tick. If the child node completes before the timeout, it returns the child's result,
+/// and returns running while the child is running. If the timeout occurs first, it cancels the child
+/// and returns a failure status.
+#[derive(Builder, Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Timeout {
     #[serde(with = "humantime_serde")]
+    #[schemars(with = "String")]
     pub duration: Duration,
     #[serde(skip)]
     #[builder(skip)]
     pub node: behavior::Decorator,
+    /// When the child was first ticked
+    #[serde(skip)]
+    #[builder(skip)]
+    started: Option<Instant>,
 }
 
 impl Behavior for Timeout {
@@ -36,8 +43,7 @@ impl ActorFactory for TimeoutFactory {
         id: ActorId,
         options: SpawnOptions,
     ) -> Result<ActorHandle, SystemActorError> {
-        let node: crate::tree::DecoratorNode = serde_json::from_value(config.clone()).unwrap();
-        let mut config: Timeout = serde_json::from_value(node.data.config.clone())?;
+        let (node, mut config) = crate::tree::DecoratorNode::parse::<Timeout>(&config)?;
         config.node.copy_child(&node);
         Ok(tokio::spawn(async move {
             let (mut ctx, mut actor) = Actor::spawn(engine, id, config, options).await?;
@@ -47,6 +53,8 @@ impl ActorFactory for TimeoutFactory {
             Ok(())
         }))
     }
+
+    crate::behavior_factory_config!(DecoratorNode, Timeout);
 }
 
 impl Message<BehaviorTick> for Timeout {
@@ -58,33 +66,47 @@ impl Message<BehaviorTick> for Timeout {
             return Ok(());
         };
 
-        let status = match timeout(
-            self.duration,
-            ctx.send_as_and_wait_reply::<BehaviorTick, BehaviorStatus>(
-                BehaviorTick,
-                child.clone(),
-                SendOptions::default(),
-            ),
-        )
-        .await
-        {
-            Ok(Ok(status)) => status,
-            Ok(Err(_)) => BehaviorStatus::Failure,
-            Err(_) => BehaviorStatus::Failure,
+        let started = *self.started.get_or_insert_with(Instant::now);
+        let remaining = self.duration.saturating_sub(started.elapsed());
+        let status = match timeout(remaining, behavior::tick(ctx, &child)).await {
+            Ok(status) => status,
+            Err(_) => {
+                self.node.child_cancel(ctx).await;
+                BehaviorStatus::Failure
+            }
         };
+        if status != BehaviorStatus::Running {
+            self.started = None;
+        }
 
         ctx.reply(status).await?;
         Ok(())
     }
 }
 
+impl Message<BehaviorCancel> for Timeout {
+    type Response = ();
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, _msg: &BehaviorCancel) -> Result<(), Self::Error> {
+        if self.started.take().is_some() {
+            self.node.child_cancel(ctx).await;
+        }
+        ctx.reply(()).await?;
+        Ok(())
+    }
+}
+
 impl Actor for Timeout {
     type Error = SystemActorError;
 
     async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), Self::Error> {
         let mut stream = ctx.recv().await?;
         while let Some(Ok(frame)) = stream.next().await {
-            self.reply(ctx, &BehaviorTick, &frame).await?;
+            if let Some(BehaviorTick) = frame.is::<BehaviorTick>() {
+                self.reply(ctx, &BehaviorTick, &frame).await?;
+            } else if let Some(BehaviorCancel) = frame.is::<BehaviorCancel>() {
+                self.reply(ctx, &BehaviorCancel, &frame).await?;
+            }
         }
         Ok(())
     }
@@ -6,6 +6,8 @@ use bioma_actor::{ActorError, SystemActorError};
 pub enum BehaviorError {
     #[error("System error: {0}")]
     System(#[from] SystemActorError),
+    #[error("Invalid tree: {0}")]
+    InvalidTree(std::borrow::Cow<'static, str>),
 }
 
 impl ActorError for BehaviorError {}
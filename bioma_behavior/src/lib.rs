@@ -1,4 +1,5 @@
 pub mod behavior;
+pub mod blackboard;
 mod error;
 pub mod tree;
 
@@ -9,6 +10,7 @@ pub mod decorators;
 pub mod prelude {
     pub use crate::actions;
     pub use crate::behavior::{self, Behavior, BehaviorCancel, BehaviorStatus, BehaviorTick};
+    pub use crate::blackboard::Blackboard;
     pub use crate::composites;
     pub use crate::decorators;
     pub use crate::error::BehaviorError;
@@ -22,6 +24,7 @@ pub async fn register_behaviors(registry: &bioma_actor::ActorTagRegistry) -> Res
     // Actions
     registry.add(actions::Wait::tag(), actions::WaitFactory).await?;
     registry.add(actions::Log::tag(), actions::LogFactory).await?;
+    registry.add(actions::Set::tag(), actions::SetFactory).await?;
 
     // Decorators
     registry.add(decorators::Delay::tag(), decorators::DelayFactory).await?;
@@ -33,3 +36,43 @@ pub async fn register_behaviors(registry: &bioma_actor::ActorTagRegistry) -> Res
     registry.add(composites::Sequence::tag(), composites::SequenceFactory).await?;
     Ok(())
 }
+
+/// Sets up the blackboard table of the behavior trees, so their values are encrypted and deleted with them
+pub async fn register_blackboard(engine: &bioma_actor::Engine) -> Result<(), bioma_actor::SystemActorError> {
+    engine.migrate(&blackboard::Blackboard::migrations()).await?;
+    engine.add_actor_table(blackboard::Blackboard::actor_table());
+    Ok(())
+}
+
+/// Registers the messages handled by the behavior nodes, with their JSON Schemas
+pub fn register_messages(registry: &bioma_actor::MessageRegistry) {
+    use crate::behavior::{BehaviorCancel, BehaviorTick};
+
+    // Actions
+    registry.add::<actions::Wait, BehaviorTick>();
+    registry.add::<actions::Wait, BehaviorCancel>();
+    registry.add::<actions::Log, BehaviorTick>();
+    registry.add::<actions::Log, BehaviorCancel>();
+    registry.add::<actions::Set, BehaviorTick>();
+    registry.add::<actions::Set, BehaviorCancel>();
+
+    // Decorators
+    registry.add::<decorators::Always, BehaviorTick>();
+    registry.add::<decorators::Always, BehaviorCancel>();
+    registry.add::<decorators::Delay, BehaviorTick>();
+    registry.add::<decorators::Delay, BehaviorCancel>();
+    registry.add::<decorators::Invert, BehaviorTick>();
+    registry.add::<decorators::Invert, BehaviorCancel>();
+    registry.add::<decorators::Timeout, BehaviorTick>();
+    registry.add::<decorators::Timeout, BehaviorCancel>();
+
+    // Composites
+    registry.add::<composites::All, BehaviorTick>();
+    registry.add::<composites::All, BehaviorCancel>();
+    registry.add::<composites::Any, BehaviorTick>();
+    registry.add::<composites::Any, BehaviorCancel>();
+    registry.add::<composites::Fallback, BehaviorTick>();
+    registry.add::<composites::Fallback, BehaviorCancel>();
+    registry.add::<composites::Sequence, BehaviorTick>();
+    registry.add::<composites::Sequence, BehaviorCancel>();
+}
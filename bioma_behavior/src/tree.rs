@@ -1,15 +1,20 @@
-use crate::behavior::{self, Behavior, BehaviorTick};
+use crate::behavior::{self, Behavior, BehaviorStatus};
+use crate::blackboard::Blackboard;
 use crate::error::BehaviorError;
 use bioma_actor::prelude::*;
-use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::time::Duration;
 use tokio::sync::oneshot;
+use tokio::time::MissedTickBehavior;
 use tracing::debug;
 
 /// Behavior tree node type designed to be ergonomic and easy to view and edit in json.
 /// Any weirdness is due to the need to serialize/deserialize the node type as part of the node definition.
 /// Custom behavior properties are kept under the `config` field.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 #[serde(tag = "type")]
 pub enum Node {
     /// An action node in the behavior tree.
@@ -21,7 +26,7 @@ pub enum Node {
 }
 
 /// Represents an action node in the behavior tree.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub struct ActionNode {
     /// The data associated with this action node.
     #[serde(flatten)]
@@ -29,7 +34,7 @@ pub struct ActionNode {
 }
 
 /// Represents a decorator node in the behavior tree.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub struct DecoratorNode {
     /// The data associated with this decorator node.
     #[serde(flatten)]
@@ -39,7 +44,7 @@ pub struct DecoratorNode {
 }
 
 /// Represents a composite node in the behavior tree.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub struct CompositeNode {
     /// The data associated with this composite node.
     #[serde(flatten)]
@@ -49,7 +54,7 @@ pub struct CompositeNode {
 }
 
 /// Common data shared by all node types.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub struct NodeData {
     /// The tag associated with this node.
     pub tag: Cow<'static, str>,
@@ -57,9 +62,148 @@ pub struct NodeData {
     pub uid: Cow<'static, str>,
     /// The configuration data for this node.
     pub config: serde_json::Value,
+    /// Blackboard keys used by this node, by the key the node uses for them.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub remap: BTreeMap<String, String>,
+    /// Name of the tree whose blackboard this node uses, set when the tree spawns its nodes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blackboard: Option<Cow<'static, str>>,
+}
+
+/// Action node with its behavior config typed as `T`, used to generate its JSON Schema.
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct TypedActionNode<T> {
+    tag: String,
+    uid: String,
+    config: T,
+    #[serde(default)]
+    remap: BTreeMap<String, String>,
+}
+
+/// Decorator node with its behavior config typed as `T`, used to generate its JSON Schema.
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct TypedDecoratorNode<T> {
+    tag: String,
+    uid: String,
+    config: T,
+    #[serde(default)]
+    remap: BTreeMap<String, String>,
+    child: Option<Box<Node>>,
+}
+
+/// Composite node with its behavior config typed as `T`, used to generate its JSON Schema.
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct TypedCompositeNode<T> {
+    tag: String,
+    uid: String,
+    config: T,
+    #[serde(default)]
+    remap: BTreeMap<String, String>,
+    children: Vec<Node>,
+}
+
+/// Converts a generated schema into a JSON value.
+fn schema_value(schema: schemars::schema::RootSchema) -> serde_json::Value {
+    serde_json::to_value(schema).unwrap_or(serde_json::Value::Bool(true))
+}
+
+impl ActionNode {
+    /// Parses an action node passed to a factory, along with its behavior config.
+    ///
+    /// # Returns
+    ///
+    /// The node and its behavior config, or a `SystemActorError` if either is invalid.
+    pub fn parse<T: DeserializeOwned>(value: &serde_json::Value) -> Result<(Self, T), SystemActorError> {
+        let node: Self = serde_json::from_value(value.clone())?;
+        let config = node.data.parse_config()?;
+        Ok((node, config))
+    }
+
+    /// Returns the JSON Schema of an action node with behavior config `T`.
+    pub fn schema<T: JsonSchema>() -> serde_json::Value {
+        schema_value(schemars::schema_for!(TypedActionNode<T>))
+    }
+}
+
+impl DecoratorNode {
+    /// Parses a decorator node passed to a factory, along with its behavior config.
+    ///
+    /// # Returns
+    ///
+    /// The node and its behavior config, or a `SystemActorError` if either is invalid.
+    pub fn parse<T: DeserializeOwned>(value: &serde_json::Value) -> Result<(Self, T), SystemActorError> {
+        let node: Self = serde_json::from_value(value.clone())?;
+        let config = node.data.parse_config()?;
+        Ok((node, config))
+    }
+
+    /// Returns the JSON Schema of a decorator node with behavior config `T`.
+    pub fn schema<T: JsonSchema>() -> serde_json::Value {
+        schema_value(schemars::schema_for!(TypedDecoratorNode<T>))
+    }
+}
+
+impl CompositeNode {
+    /// Parses a composite node passed to a factory, along with its behavior config.
+    ///
+    /// # Returns
+    ///
+    /// The node and its behavior config, or a `SystemActorError` if either is invalid.
+    pub fn parse<T: DeserializeOwned>(value: &serde_json::Value) -> Result<(Self, T), SystemActorError> {
+        let node: Self = serde_json::from_value(value.clone())?;
+        let config = node.data.parse_config()?;
+        Ok((node, config))
+    }
+
+    /// Returns the JSON Schema of a composite node with behavior config `T`.
+    pub fn schema<T: JsonSchema>() -> serde_json::Value {
+        schema_value(schemars::schema_for!(TypedCompositeNode<T>))
+    }
+}
+
+/// Implements `config_schema` and `validate` in the `ActorFactory` of a behavior.
+///
+/// Takes the node kind and the behavior config type, checking configs the same way `parse` does.
+///
+/// # Example
+///
+/// ```rust
+/// impl ActorFactory for WaitFactory {
+///     fn spawn(...) -> Result<ActorHandle, SystemActorError> { ... }
+///
+///     bioma_behavior::behavior_factory_config!(ActionNode, Wait);
+/// }
+/// ```
+#[macro_export]
+macro_rules! behavior_factory_config {
+    ($node:ident, $config:ty) => {
+        fn config_schema(&self) -> serde_json::Value {
+            $crate::tree::$node::schema::<$config>()
+        }
+
+        fn validate(&self, config: &serde_json::Value) -> Result<(), ::bioma_actor::SystemActorError> {
+            $crate::tree::$node::parse::<$config>(config)?;
+            Ok(())
+        }
+    };
 }
 
 impl NodeData {
+    /// Deserializes the behavior config of this node.
+    pub fn parse_config<T: DeserializeOwned>(&self) -> Result<T, SystemActorError> {
+        Ok(serde_json::from_value(self.config.clone())?)
+    }
+
+    /// Returns the blackboard of the tree this node belongs to, with the key remapping of this node.
+    ///
+    /// None for nodes that weren't spawned by a `BehaviorTree`.
+    pub fn blackboard(&self) -> Option<Blackboard> {
+        self.blackboard.clone().map(|tree| Blackboard::new(tree).with_remap(self.remap.clone()))
+    }
+
     /// Generates an `ActorId` for this node.
     ///
     /// # Arguments
@@ -95,29 +239,26 @@ impl Node {
         node: T,
         children: Vec<Node>,
     ) -> Result<Self, BehaviorError> {
-        let data = serde_json::to_value(&node).unwrap();
+        let config = serde_json::to_value(&node).map_err(SystemActorError::from)?;
         let tag = T::tag();
+        let data = NodeData { tag: tag.clone(), uid: uid.into(), config, remap: BTreeMap::new(), blackboard: None };
         match node.node().node_type() {
             behavior::NodeType::Action => {
-                if children.len() > 0 {
-                    panic!("Action nodes cannot have children");
+                if !children.is_empty() {
+                    return Err(BehaviorError::InvalidTree(format!("Action node {} cannot have children", tag).into()));
                 }
-                Ok(Node::Action(ActionNode { data: NodeData { tag: tag.into(), uid: uid.into(), config: data } }))
+                Ok(Node::Action(ActionNode { data }))
             }
             behavior::NodeType::Decorator => {
                 if children.len() > 1 {
-                    panic!("Decorator nodes can have only one child");
+                    return Err(BehaviorError::InvalidTree(
+                        format!("Decorator node {} can have only one child", tag).into(),
+                    ));
                 }
                 let child = children.first().cloned().map(Box::new);
-                Ok(Node::Decorator(DecoratorNode {
-                    data: NodeData { tag: tag.into(), uid: uid.into(), config: data },
-                    child,
-                }))
+                Ok(Node::Decorator(DecoratorNode { data, child }))
             }
-            behavior::NodeType::Composite => Ok(Node::Composite(CompositeNode {
-                data: NodeData { tag: tag.into(), uid: uid.into(), config: data },
-                children,
-            })),
+            behavior::NodeType::Composite => Ok(Node::Composite(CompositeNode { data, children })),
         }
     }
 
@@ -130,6 +271,15 @@ impl Node {
         }
     }
 
+    /// Returns a mutable reference to the `NodeData` of this node.
+    pub fn data_mut(&mut self) -> &mut NodeData {
+        match self {
+            Node::Action(node) => &mut node.data,
+            Node::Decorator(node) => &mut node.data,
+            Node::Composite(node) => &mut node.data,
+        }
+    }
+
     /// Returns the serialized value of this node.
     pub fn value(&self) -> serde_json::Value {
         match self {
@@ -169,17 +319,35 @@ impl Node {
     }
 }
 
+/// Behavior tree, ticking its root until it completes.
+///
+/// The root is ticked every `tick_rate` while it returns running, and the tree stops once the root
+/// returns success or failure, or stops on its own. The nodes of the tree share its `Blackboard`,
+/// which starts with the values of `blackboard` for the keys that aren't set yet.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BehaviorTree {
     pub root: Node,
     pub logs: Vec<String>,
+    /// Time between ticks of the root
+    #[serde(default = "default_tick_rate", with = "humantime_serde")]
+    pub tick_rate: Duration,
+    /// Initial values of the blackboard
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub blackboard: BTreeMap<String, serde_json::Value>,
+    /// Status of the root after its last tick
+    #[serde(skip)]
+    pub status: Option<BehaviorStatus>,
     #[serde(skip)]
     pub root_handle: Option<ActorHandle>,
 }
 
+fn default_tick_rate() -> Duration {
+    Duration::from_millis(100)
+}
+
 impl PartialEq for BehaviorTree {
     fn eq(&self, other: &Self) -> bool {
-        self.root == other.root
+        self.root == other.root && self.tick_rate == other.tick_rate && self.blackboard == other.blackboard
     }
 }
 
@@ -187,14 +355,18 @@ impl Actor for BehaviorTree {
     type Error = BehaviorError;
 
     async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), Self::Error> {
+        // Set up the blackboard before any node can use it
+        Blackboard::of(ctx.id()).init(ctx.engine(), &self.blackboard).await?;
+
+        // The nodes of the tree use its blackboard
+        let mut root = self.root.clone();
+        root.data_mut().blackboard = Some(ctx.id().name().to_string().into());
+
         let (tx, mut rx) = oneshot::channel();
-        let root_id = self.root.data().id(Some(&ctx.id()));
-        let root_tag = self.root.data().tag.clone();
-        let root_config = self.root.value();
-        let registry = ctx.engine().registry();
-        let root_handle = registry
-            .spawn(root_tag, ctx.engine().clone(), root_config, root_id.clone(), SpawnOptions::default())
-            .await?;
+        let root_tag = root.data().tag.clone();
+        let root_config = root.value();
+        let (root_id, root_handle) =
+            ctx.spawn_child_tagged(root_tag, root.data().uid.as_ref(), root_config, SpawnOptions::default()).await?;
 
         debug!("BehaviorTree::start {}", ctx.id());
 
@@ -205,29 +377,30 @@ impl Actor for BehaviorTree {
         });
         self.root_handle = Some(root_handle);
 
-        // Send a tick to the root
-        let _ = ctx.do_send_as(BehaviorTick, &root_id).await;
-
-        let mut stream = ctx.recv().await?;
-        let result = Ok(());
+        // Tick the root until it completes
+        let mut ticks = tokio::time::interval(self.tick_rate.max(Duration::from_millis(1)));
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        self.status = None;
 
         loop {
             tokio::select! {
-                Some(Ok(_frame)) = stream.next() => {
-                    // Handle the frame - continue loop after processing
-                },
-                root_result = &mut rx => {
-                    match root_result {
-                        Ok(_) => break,
-                        Err(_) => break,
+                _ = ticks.tick() => {
+                    let status = behavior::tick(ctx, &root_id).await;
+                    debug!("BehaviorTree::tick {} {:?}", ctx.id(), status);
+                    self.status = Some(status.clone());
+                    if status != BehaviorStatus::Running {
+                        break;
                     }
-                }
+                },
+                _ = &mut rx => break,
             }
         }
 
+        ctx.stop_child(&root_id);
+
         debug!("BehaviorTree::start: end {}", ctx.id());
 
-        result
+        Ok(())
     }
 }
 
@@ -2,8 +2,11 @@ use actions::log::LogLevel::Info;
 use bioma_actor::prelude::*;
 use bioma_behavior::prelude::*;
 use bioma_behavior::tree::Node;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::io::Write;
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 use test_log::test;
 use tracing::debug;
 use tracing_subscriber::{fmt::MakeWriter, layer::SubscriberExt, Layer};
@@ -43,6 +46,9 @@ async fn test_tree_roundtrip() -> Result<(), SystemActorError> {
     let tree = BehaviorTree {
         root: all_0,
         logs: vec!["Log 0".to_string(), "Log 1".to_string(), "Log 2".to_string()],
+        tick_rate: Duration::from_millis(100),
+        blackboard: BTreeMap::new(),
+        status: None,
         root_handle: None,
     };
 
@@ -134,3 +140,288 @@ impl Clone for TestWriter {
         TestWriter(self.0.clone())
     }
 }
+
+#[test(tokio::test)]
+async fn test_tree_node_validation() -> Result<(), Box<dyn std::error::Error>> {
+    let engine = Engine::test().await?;
+    let registry = engine.registry();
+    bioma_behavior::register_behaviors(registry).await?;
+
+    let wait = Node::from("wait_0", actions::Wait::builder().duration(Duration::from_secs(1)).build(), vec![])?;
+    let tag = wait.data().tag.clone();
+    registry.validate(tag.clone(), &wait.value()).await?;
+
+    // The config schema describes the behavior config of the node
+    let schema = registry.schema(tag.clone()).await?;
+    assert!(schema.to_string().contains("duration"));
+
+    // A bad node config is an error, not a panic
+    let bad_config = serde_json::json!({ "tag": tag, "uid": "wait_1", "config": { "duration": "soon" } });
+    let result = registry.validate(tag.clone(), &bad_config).await;
+    assert!(matches!(result, Err(SystemActorError::InvalidActorConfig(_, _))));
+
+    let result = registry
+        .spawn(tag, engine.clone(), bad_config, ActorId::of::<actions::Wait>("wait_1"), SpawnOptions::default())
+        .await;
+    assert!(matches!(result, Err(SystemActorError::InvalidActorConfig(_, _))));
+
+    // Tree structure errors are reported when building nodes
+    let log = Node::from("log_0", actions::Log::builder().level(Info).text("Log".to_string()).build(), vec![])?;
+    let result = Node::from("wait_2", actions::Wait::builder().duration(Duration::from_secs(1)).build(), vec![log]);
+    assert!(matches!(result, Err(BehaviorError::InvalidTree(_))));
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_tree_running() -> Result<(), Box<dyn std::error::Error>> {
+    let engine = Engine::test().await?;
+    let registry = engine.registry();
+    bioma_behavior::register_behaviors(registry).await?;
+    registry.add(decorators::Timeout::tag(), decorators::TimeoutFactory).await?;
+
+    // ASCII diagram of the behavior tree:
+    //
+    //      Timeout (timeout_0)
+    //             |
+    //        Wait (wait_0)
+
+    let wait = Node::from("wait_0", actions::Wait::builder().duration(Duration::from_secs(10)).build(), vec![])?;
+    let timeout = decorators::Timeout::builder().duration(Duration::from_millis(300)).build();
+    let timeout = Node::from("timeout_0", timeout, vec![wait])?;
+
+    let tree = BehaviorTree {
+        root: timeout,
+        logs: vec![],
+        tick_rate: Duration::from_millis(50),
+        blackboard: BTreeMap::new(),
+        status: None,
+        root_handle: None,
+    };
+    let tree_id = ActorId::of::<BehaviorTree>("tree_running");
+    let (mut tree_ctx, mut tree_actor) = Actor::spawn(engine.clone(), tree_id, tree, SpawnOptions::default()).await?;
+
+    // The wait keeps running until the timeout cancels it
+    let start = Instant::now();
+    tree_actor.start(&mut tree_ctx).await?;
+    let elapsed = start.elapsed();
+    assert_eq!(tree_actor.status, Some(BehaviorStatus::Failure));
+    assert!(elapsed >= Duration::from_millis(300), "Tree completed before the timeout: {:?}", elapsed);
+    assert!(elapsed < Duration::from_secs(5), "Tree waited for the running action: {:?}", elapsed);
+
+    dbg_export_db!(engine);
+
+    Ok(())
+}
+
+/// Set by `test_tree_reactive` to make the `Flag` condition succeed
+static FLAG: AtomicBool = AtomicBool::new(false);
+
+/// Condition succeeding once `FLAG` is set
+#[derive(Debug, Serialize, Deserialize)]
+struct Flag {
+    #[serde(skip)]
+    node: behavior::Action,
+}
+
+impl Behavior for Flag {
+    fn node(&self) -> behavior::Node {
+        behavior::Node::Action(&self.node)
+    }
+}
+
+impl Message<BehaviorTick> for Flag {
+    type Response = BehaviorStatus;
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, _msg: &BehaviorTick) -> Result<(), Self::Error> {
+        let status = if FLAG.load(Ordering::SeqCst) { BehaviorStatus::Success } else { BehaviorStatus::Failure };
+        ctx.reply(status).await?;
+        Ok(())
+    }
+}
+
+impl Message<BehaviorCancel> for Flag {
+    type Response = ();
+
+    async fn handle(&mut self, ctx: &mut ActorContext<Self>, _msg: &BehaviorCancel) -> Result<(), Self::Error> {
+        ctx.reply(()).await?;
+        Ok(())
+    }
+}
+
+impl Actor for Flag {
+    type Error = SystemActorError;
+
+    async fn start(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), Self::Error> {
+        let mut stream = ctx.recv().await?;
+        while let Some(Ok(frame)) = stream.next().await {
+            if let Some(BehaviorTick) = frame.is::<BehaviorTick>() {
+                self.reply(ctx, &BehaviorTick, &frame).await?;
+            } else if let Some(BehaviorCancel) = frame.is::<BehaviorCancel>() {
+                self.reply(ctx, &BehaviorCancel, &frame).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+struct FlagFactory;
+
+impl ActorFactory for FlagFactory {
+    fn spawn(
+        &self,
+        engine: Engine,
+        config: serde_json::Value,
+        id: ActorId,
+        options: SpawnOptions,
+    ) -> Result<ActorHandle, SystemActorError> {
+        let (_, config) = tree::ActionNode::parse::<Flag>(&config)?;
+        Ok(tokio::spawn(async move {
+            let (mut ctx, mut actor) = Actor::spawn(engine, id, config, options).await?;
+            actor.start(&mut ctx).await?;
+            Ok(())
+        }))
+    }
+}
+
+#[test(tokio::test)]
+async fn test_tree_reactive() -> Result<(), Box<dyn std::error::Error>> {
+    let engine = Engine::test().await?;
+    let registry = engine.registry();
+    bioma_behavior::register_behaviors(registry).await?;
+    registry.add(Flag::tag(), FlagFactory).await?;
+
+    // ASCII diagram of the behavior tree:
+    //
+    //       Fallback (fallback_0)
+    //          /          \
+    //    Flag (flag_0)   Wait (wait_0)
+
+    let flag = Node::from("flag_0", Flag { node: behavior::Action::default() }, vec![])?;
+    let wait = Node::from("wait_0", actions::Wait::builder().duration(Duration::from_secs(10)).build(), vec![])?;
+    let fallback = Node::from("fallback_0", composites::Fallback::builder().reactive(true).build(), vec![flag, wait])?;
+
+    let tree = BehaviorTree {
+        root: fallback,
+        logs: vec![],
+        tick_rate: Duration::from_millis(50),
+        blackboard: BTreeMap::new(),
+        status: None,
+        root_handle: None,
+    };
+    let tree_id = ActorId::of::<BehaviorTree>("tree_reactive");
+    let (mut tree_ctx, mut tree_actor) = Actor::spawn(engine.clone(), tree_id, tree, SpawnOptions::default()).await?;
+
+    // The flag is checked again on every tick while the wait is running
+    tokio::spawn(async {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        FLAG.store(true, Ordering::SeqCst);
+    });
+
+    let start = Instant::now();
+    tree_actor.start(&mut tree_ctx).await?;
+    let elapsed = start.elapsed();
+    assert_eq!(tree_actor.status, Some(BehaviorStatus::Success));
+    assert!(elapsed < Duration::from_secs(5), "Tree waited for the running action: {:?}", elapsed);
+
+    dbg_export_db!(engine);
+
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_tree_blackboard() -> Result<(), Box<dyn std::error::Error>> {
+    let engine = Engine::test().await?;
+    bioma_behavior::register_behaviors(engine.registry()).await?;
+    bioma_behavior::register_blackboard(&engine).await?;
+
+    // The set action writes its `answer` key to the `result` key of the blackboard
+    let tree_json = serde_json::json!({
+        "root": {
+            "type": "Composite",
+            "tag": "Sequence",
+            "uid": "sequence_0",
+            "config": {},
+            "children": [
+                {
+                    "type": "Action",
+                    "tag": "Set",
+                    "uid": "set_0",
+                    "config": { "key": "answer", "value": 42 },
+                    "remap": { "answer": "result" }
+                },
+                {
+                    "type": "Action",
+                    "tag": "Log",
+                    "uid": "log_0",
+                    "config": { "level": "Info", "text": "Greeting", "key": "greeting" }
+                }
+            ]
+        },
+        "logs": [],
+        "tick_rate": "50ms",
+        "blackboard": { "greeting": "Hello", "count": 1 }
+    });
+    let tree: BehaviorTree = serde_json::from_value(tree_json)?;
+
+    let tree_id = ActorId::of::<BehaviorTree>("tree_blackboard");
+    let (mut tree_ctx, mut tree_actor) =
+        Actor::spawn(engine.clone(), tree_id.clone(), tree, SpawnOptions::default()).await?;
+    tree_actor.start(&mut tree_ctx).await?;
+    assert_eq!(tree_actor.status, Some(BehaviorStatus::Success));
+
+    // Initial values and results are persisted under the blackboard keys
+    let blackboard = Blackboard::of(&tree_id);
+    let values = blackboard.values(&engine).await?;
+    assert_eq!(values.len(), 3);
+    assert_eq!(values.get("greeting"), Some(&serde_json::json!("Hello")));
+    assert_eq!(blackboard.get::<u64>(&engine, "result").await?, Some(42));
+    assert_eq!(blackboard.get::<u64>(&engine, "answer").await?, None);
+
+    // Remapped keys read and write the blackboard keys
+    let remapped = blackboard.clone().with_remap(BTreeMap::from([("answer".to_string(), "result".to_string())]));
+    remapped.set(&engine, "answer", &43).await?;
+    assert_eq!(blackboard.get::<u64>(&engine, "result").await?, Some(43));
+    remapped.remove(&engine, "answer").await?;
+    assert_eq!(blackboard.get::<u64>(&engine, "result").await?, None);
+
+    // Initial values don't replace the values set by an earlier run of a restored tree
+    assert_eq!(engine.schema_version("bioma_behavior").await?, Some(1));
+    blackboard.set(&engine, "count", &2).await?;
+    let count_tree = || {
+        serde_json::from_value::<BehaviorTree>(serde_json::json!({
+            "root": { "type": "Action", "tag": "Log", "uid": "log_1", "config": { "level": "Info", "text": "Count" } },
+            "logs": [],
+            "blackboard": { "count": 1 }
+        }))
+    };
+    let (mut tree_ctx, mut tree_actor) = Actor::spawn(
+        engine.clone(),
+        tree_id.clone(),
+        count_tree()?,
+        SpawnOptions::builder().exists(SpawnExistsOptions::Restore).build(),
+    )
+    .await?;
+    tree_actor.start(&mut tree_ctx).await?;
+    assert_eq!(blackboard.get::<u64>(&engine, "count").await?, Some(2));
+
+    // A reset tree starts over with a new blackboard
+    let (mut tree_ctx, mut tree_actor) = Actor::spawn(
+        engine.clone(),
+        tree_id.clone(),
+        count_tree()?,
+        SpawnOptions::builder().exists(SpawnExistsOptions::Reset).build(),
+    )
+    .await?;
+    tree_actor.start(&mut tree_ctx).await?;
+    let values = blackboard.values(&engine).await?;
+    assert_eq!(values, BTreeMap::from([("count".to_string(), serde_json::json!(1))]));
+
+    // The blackboard is deleted with its tree
+    engine.kill_actor(&tree_id).await?;
+    assert!(blackboard.values(&engine).await?.is_empty());
+
+    dbg_export_db!(engine);
+
+    Ok(())
+}
@@ -1,6 +1,5 @@
 use bioma_actor::prelude::*;
 use bioma_llm::prelude::*;
-use futures::future::join_all;
 use tracing::{error, info};
 
 #[tokio::main]
@@ -70,47 +69,32 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .map(|chunk| chunk.to_vec())
         .collect();
 
-    let mut embedding_futures = Vec::new();
+    let requests = embeddings_actors.iter().cloned().zip(
+        chunks.into_iter().map(|chunk| StoreEmbeddings { content: EmbeddingContent::Text(chunk), metadata: None }),
+    );
+    let all_embeddings = relay_ctx.send_each::<Embeddings, StoreEmbeddings>(requests, GatherOptions::default()).await;
 
-    for (i, chunk) in chunks.iter().enumerate() {
-        let embeddings_id = &embeddings_actors[i];
-        let future = relay_ctx.send_and_wait_reply::<Embeddings, StoreEmbeddings>(
-            StoreEmbeddings { content: EmbeddingContent::Text(chunk.clone()), metadata: None },
-            embeddings_id,
-            SendOptions::default(),
-        );
-        embedding_futures.push(future);
-    }
-
-    let all_embeddings = join_all(embedding_futures).await;
-
-    for (i, embeddings_result) in all_embeddings.into_iter().enumerate() {
-        let embeddings_ids = embeddings_result?;
+    for (i, embeddings_reply) in all_embeddings.replies.into_iter().enumerate() {
+        let embeddings_ids = embeddings_reply.into_result()?;
         for id in embeddings_ids.ids.iter() {
             info!("Actor {}: Stored embeddings for text: {}", i, id);
         }
     }
 
     // Get similarities from all actors
-    let mut similarity_futures = Vec::new();
-
-    for (i, embeddings_id) in embeddings_actors.iter().enumerate() {
-        let top_k = embeddings::TopK {
-            query: embeddings::Query::Text("Hello, how are you?".to_string()),
-            threshold: -0.5,
-            k: 5,
-            source: None,
-        };
-        info!("Query for actor {}: {:?}", i, top_k);
-        let future =
-            relay_ctx.send_and_wait_reply::<Embeddings, embeddings::TopK>(top_k, embeddings_id, SendOptions::default());
-        similarity_futures.push(future);
-    }
-
-    let all_similarities = join_all(similarity_futures).await;
-
-    for (i, similarities_result) in all_similarities.into_iter().enumerate() {
-        let similarities = similarities_result?;
+    let top_k = embeddings::TopK {
+        query: embeddings::Query::Text("Hello, how are you?".to_string()),
+        threshold: -0.5,
+        k: 5,
+        source: None,
+    };
+    info!("Query for all actors: {:?}", top_k);
+    let all_similarities = relay_ctx
+        .send_all::<Embeddings, embeddings::TopK>(embeddings_actors.clone(), top_k, GatherOptions::default())
+        .await;
+
+    for (i, similarities_reply) in all_similarities.replies.into_iter().enumerate() {
+        let similarities = similarities_reply.into_result()?;
         for similarity in similarities {
             info!("Actor {}: Similarity: {:?}   {}", i, similarity.text, similarity.similarity);
         }
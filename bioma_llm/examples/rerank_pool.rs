@@ -1,6 +1,5 @@
 use bioma_actor::prelude::*;
 use bioma_llm::rerank::{RankTexts, Rerank};
-use futures::future::join_all;
 use tracing::{error, info};
 
 #[tokio::main]
@@ -83,22 +82,19 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     ];
 
     // Distribute queries among rerank actors
-    let mut rerank_futures = Vec::new();
-
-    for (i, query) in queries.iter().enumerate() {
-        let rerank_id = &rerank_actors[i % num_rerank_actors];
-        let future = relay_ctx.send_and_wait_reply::<Rerank, RankTexts>(
-            RankTexts::builder().query(query.to_string()).texts(texts.iter().map(|s| s.to_string()).collect()).build(),
-            rerank_id,
-            SendOptions::builder().timeout(std::time::Duration::from_secs(100)).build(),
-        );
-        rerank_futures.push(future);
-    }
-
-    let all_rankings = join_all(rerank_futures).await;
-
-    for (i, ranking_result) in all_rankings.into_iter().enumerate() {
-        match ranking_result {
+    let requests = queries.iter().enumerate().map(|(i, query)| {
+        let rerank_id = rerank_actors[i % num_rerank_actors].clone();
+        let rank_texts =
+            RankTexts::builder().query(query.to_string()).texts(texts.iter().map(|s| s.to_string()).collect()).build();
+        (rerank_id, rank_texts)
+    });
+    let options = GatherOptions::builder()
+        .send(SendOptions::builder().timeout(std::time::Duration::from_secs(100)).build())
+        .build();
+    let all_rankings = relay_ctx.send_each::<Rerank, RankTexts>(requests, options).await;
+
+    for (i, ranking_reply) in all_rankings.replies.into_iter().enumerate() {
+        match ranking_reply.into_result() {
             Ok(ranked_texts) => {
                 info!("Query {}: {}", i, queries[i]);
                 for (j, ranked_text) in ranked_texts.texts.into_iter().take(3).enumerate() {
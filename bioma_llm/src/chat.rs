@@ -103,13 +103,16 @@ impl Schema {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ChatMessages {
+    #[schemars(with = "Vec<serde_json::Value>")]
     pub messages: Vec<ChatMessage>,
     pub restart: bool,
     pub persist: bool,
     pub stream: bool,
+    #[schemars(with = "Option<serde_json::Value>")]
     pub format: Option<Schema>,
+    #[schemars(with = "Option<Vec<serde_json::Value>>")]
     pub tools: Option<Vec<ToolInfo>>,
 }
 
@@ -4,8 +4,10 @@ use bioma_actor::prelude::*;
 use bon::Builder;
 use derive_more::{Deref, Display};
 use lazy_static::lazy_static;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::io::Write;
 use std::path::PathBuf;
@@ -54,9 +56,31 @@ pub enum EmbeddingsError {
     Persist(#[from] tempfile::PersistError),
 }
 
-impl ActorError for EmbeddingsError {}
+impl ActorError for EmbeddingsError {
+    fn code(&self) -> Cow<'static, str> {
+        match self {
+            // Keep the code of the underlying system error
+            EmbeddingsError::System(error) => error.code(),
+            error => variant_name(error),
+        }
+    }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+    fn kind(&self) -> ErrorKind {
+        match self {
+            EmbeddingsError::System(error) => error.kind(),
+            // The model is still loading or its worker is gone, another attempt can succeed
+            EmbeddingsError::TextEmbeddingNotInitialized
+            | EmbeddingsError::SendTextEmbeddings(_)
+            | EmbeddingsError::RecvEmbeddings(_) => ErrorKind::Transient,
+            EmbeddingsError::Url(_) | EmbeddingsError::Base64Decode(_) | EmbeddingsError::ImageFormat(_) => {
+                ErrorKind::Invalid
+            }
+            _ => ErrorKind::Permanent,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum ImageData {
     Path(String),
     Base64(String),
@@ -115,7 +139,7 @@ impl ImageData {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum EmbeddingContent {
     Text(Vec<String>),
     Image(Vec<ImageData>),
@@ -157,7 +181,7 @@ pub struct EmbeddingRequest {
 }
 
 /// Store embeddings for texts or images
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct StoreEmbeddings {
     /// The content to embed (either texts or images)
     pub content: EmbeddingContent,
@@ -166,25 +190,26 @@ pub struct StoreEmbeddings {
 }
 
 /// Generate embeddings for texts or images
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct GenerateEmbeddings {
     /// The content to embed (either texts or images)
     pub content: EmbeddingContent,
 }
 
 /// The generated embeddings
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct GeneratedEmbeddings {
     pub embeddings: Vec<Vec<f32>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct StoredEmbeddings {
+    #[schemars(with = "Vec<Value>")]
     pub ids: Vec<RecordId>,
 }
 
 /// The query to search for
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum Query {
     Embedding(Vec<f32>),
     Text(String),
@@ -192,7 +217,7 @@ pub enum Query {
 }
 
 /// Get the top k similar embeddings to a query
-#[derive(Builder, Debug, Clone, Serialize, Deserialize)]
+#[derive(Builder, Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TopK {
     /// The query to search for
     pub query: Query,
@@ -205,7 +230,7 @@ pub struct TopK {
 }
 
 /// The similarity between a query and an embedding
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Similarity {
     pub text: Option<String>,
     pub similarity: f32,
@@ -257,6 +282,12 @@ impl Message<TopK> for Embeddings {
     type Response = Vec<Similarity>;
 
     async fn handle(&mut self, ctx: &mut ActorContext<Self>, message: &TopK) -> Result<(), EmbeddingsError> {
+        self.handle_shared(ctx, message).await
+    }
+}
+
+impl SharedMessage<TopK> for Embeddings {
+    async fn handle_shared(&self, ctx: &mut ActorContext<Self>, message: &TopK) -> Result<(), EmbeddingsError> {
         let query_embedding = match &message.query {
             Query::Embedding(embedding) => embedding.clone(),
             Query::Text(text) => {
@@ -307,6 +338,16 @@ impl Message<StoreEmbeddings> for Embeddings {
     type Response = StoredEmbeddings;
 
     async fn handle(&mut self, ctx: &mut ActorContext<Self>, message: &StoreEmbeddings) -> Result<(), EmbeddingsError> {
+        self.handle_shared(ctx, message).await
+    }
+}
+
+impl SharedMessage<StoreEmbeddings> for Embeddings {
+    async fn handle_shared(
+        &self,
+        ctx: &mut ActorContext<Self>,
+        message: &StoreEmbeddings,
+    ) -> Result<(), EmbeddingsError> {
         let Some(embedding_tx) = self.embedding_tx.as_ref() else {
             return Err(EmbeddingsError::TextEmbeddingNotInitialized);
         };
@@ -367,6 +408,16 @@ impl Message<GenerateEmbeddings> for Embeddings {
         &mut self,
         ctx: &mut ActorContext<Self>,
         message: &GenerateEmbeddings,
+    ) -> Result<(), EmbeddingsError> {
+        self.handle_shared(ctx, message).await
+    }
+}
+
+impl SharedMessage<GenerateEmbeddings> for Embeddings {
+    async fn handle_shared(
+        &self,
+        ctx: &mut ActorContext<Self>,
+        message: &GenerateEmbeddings,
     ) -> Result<(), EmbeddingsError> {
         let Some(embedding_tx) = self.embedding_tx.as_ref() else {
             return Err(EmbeddingsError::TextEmbeddingNotInitialized);
@@ -434,26 +485,25 @@ impl Actor for Embeddings {
 
         self.init(ctx).await?;
 
-        // Start the message stream
-        let mut stream = ctx.recv().await?;
-        while let Some(Ok(frame)) = stream.next().await {
-            if let Some(input) = frame.is::<StoreEmbeddings>() {
-                let response = self.reply(ctx, &input, &frame).await;
-                if let Err(err) = response {
-                    error!("{} {:?}", ctx.id(), err);
-                }
+        // Start the message stream, requests are handled concurrently when `SpawnOptions::concurrency` is set
+        let stream = ctx.recv().await?;
+        let embeddings = &*self;
+        ctx.handle_concurrently(stream, |mut ctx, frame| async move {
+            let response = if let Some(input) = frame.is::<StoreEmbeddings>() {
+                embeddings.reply_shared(&mut ctx, &input, &frame).await
             } else if let Some(input) = frame.is::<GenerateEmbeddings>() {
-                let response = self.reply(ctx, &input, &frame).await;
-                if let Err(err) = response {
-                    error!("{} {:?}", ctx.id(), err);
-                }
+                embeddings.reply_shared(&mut ctx, &input, &frame).await
             } else if let Some(input) = frame.is::<TopK>() {
-                let response = self.reply(ctx, &input, &frame).await;
-                if let Err(err) = response {
-                    error!("{} {:?}", ctx.id(), err);
-                }
+                embeddings.reply_shared(&mut ctx, &input, &frame).await
+            } else {
+                Ok(())
+            };
+            if let Err(err) = response {
+                error!("{} {:?}", ctx.id(), err);
             }
-        }
+            Ok(())
+        })
+        .await?;
         info!("{} Finished", ctx.id());
         Ok(())
     }
@@ -519,14 +569,10 @@ impl Embeddings {
                     _ => panic!("Invalid model pair"),
                 }
 
-                // Define schema
-                let schema_def = include_str!("../sql/def.surql")
-                    .replace("{prefix}", &self.table_prefix())
-                    .replace("{dim}", &text_model_info.dim.to_string());
+                // Apply the pending schema migrations of the embeddings tables
+                ctx.engine().migrate(&self.migrations(text_model_info.dim)).await?;
 
-                // Execute the schema definition
                 let db = ctx.engine().db();
-                db.lock().await.query(&schema_def).await.map_err(SystemActorError::from)?;
 
                 // Store text model info in database if not already present
                 let model: Result<Option<Record>, _> = db
@@ -657,6 +703,18 @@ impl Embeddings {
         self.shared_embedding = shared_embedding.map(StrongSharedEmbedding);
         self.embedding_tx = self.shared_embedding.as_ref().map(|se| se.embedding_tx.clone());
 
+        // The embedding task drops its receiver if the models fail to load
+        if let Some(embedding_tx) = self.embedding_tx.clone() {
+            ctx.set_health_probe(move || {
+                if embedding_tx.is_closed() {
+                    Readiness::NotReady("Embedding models failed to load".to_string())
+                } else {
+                    Readiness::Ready
+                }
+            })
+            .await?;
+        }
+
         info!("{} Finished", ctx.id());
         Ok(())
     }
@@ -664,4 +722,15 @@ impl Embeddings {
     pub fn table_prefix(&self) -> String {
         self.table_name_prefix.as_ref().unwrap_or(&self.model.to_string()).clone()
     }
+
+    /// Migrations of the embeddings tables, tracked separately for each table prefix
+    pub fn migrations(&self, dim: usize) -> Migrations {
+        let prefix = self.table_prefix();
+        let script = |script: &str| script.replace("{prefix}", &prefix).replace("{dim}", &dim.to_string());
+        Migrations::new(format!("bioma_llm_{}", prefix)).migration(
+            1,
+            "init",
+            script(include_str!("../sql/migrations/0001_init.surql")),
+        )
+    }
 }
@@ -5,6 +5,7 @@ use crate::{
 };
 use bioma_actor::prelude::*;
 use derive_more::Display;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use surrealdb::RecordId;
@@ -53,7 +54,7 @@ pub enum IndexerError {
 
 impl ActorError for IndexerError {}
 
-#[derive(bon::Builder, Debug, Clone, Serialize, Deserialize)]
+#[derive(bon::Builder, Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct IndexGlobs {
     pub globs: Vec<String>,
     #[builder(default = default_chunk_capacity())]
@@ -79,13 +80,13 @@ fn default_chunk_batch_size() -> usize {
     DEFAULT_CHUNK_BATCH_SIZE
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Indexed {
     pub indexed: usize,
     pub cached: usize,
 }
 
-#[derive(Display, Debug, Clone, Serialize, Deserialize)]
+#[derive(Display, Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum CodeLanguage {
     Rust,
     Python,
@@ -94,7 +95,7 @@ pub enum CodeLanguage {
     Html,
 }
 
-#[derive(Display, Debug, Clone, Serialize, Deserialize)]
+#[derive(Display, Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum TextType {
     Pdf,
     Markdown,
@@ -103,13 +104,13 @@ pub enum TextType {
     Text,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TextMetadata {
     pub content: TextType,
     pub chunk_number: usize,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ImageMetadata {
     pub format: String,
     pub dimensions: ImageDimensions,
@@ -118,7 +119,7 @@ pub struct ImageMetadata {
     pub created: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum Metadata {
     Text(TextMetadata),
     Image(ImageMetadata),
@@ -131,24 +132,24 @@ enum IndexResult {
 }
 
 /// The source of the embeddings
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ContentSource {
     pub source: String,
     pub uri: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DeleteSource {
     pub source: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DeletedSource {
     pub deleted_embeddings: usize,
     pub deleted_sources: Vec<ContentSource>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ImageDimensions {
     pub width: u32,
     pub height: u32,
@@ -533,12 +534,6 @@ pub struct Indexer {
     embeddings_id: Option<ActorId>,
     pdf_analyzer_id: Option<ActorId>,
     markitdown_id: Option<ActorId>,
-    #[serde(skip)]
-    pdf_analyzer_handle: Option<tokio::task::JoinHandle<()>>,
-    #[serde(skip)]
-    embeddings_handle: Option<tokio::task::JoinHandle<()>>,
-    #[serde(skip)]
-    markitdown_handle: Option<tokio::task::JoinHandle<()>>,
 }
 
 impl Default for Indexer {
@@ -550,9 +545,6 @@ impl Default for Indexer {
             embeddings_id: None,
             pdf_analyzer_id: None,
             markitdown_id: None,
-            pdf_analyzer_handle: None,
-            embeddings_handle: None,
-            markitdown_handle: None,
         }
     }
 }
@@ -585,71 +577,39 @@ impl Actor for Indexer {
 
 impl Indexer {
     pub async fn init(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), IndexerError> {
-        // Used to namespace child actors
-        let self_id = ctx.id().clone();
-
-        // Generate child id for pdf-analyzer
-        let pdf_analyzer_id = ActorId::of::<PdfAnalyzer>(format!("{}/pdf-analyzer", self_id.name()));
-        self.pdf_analyzer_id = Some(pdf_analyzer_id.clone());
-
-        // Spawn the pdf-analyzer actor
-        let (mut pdf_analyzer_ctx, mut pdf_analyzer_actor) = Actor::spawn(
-            ctx.engine().clone(),
-            pdf_analyzer_id.clone(),
-            self.pdf_analyzer.clone(),
-            SpawnOptions::builder().exists(SpawnExistsOptions::Reset).build(),
-        )
-        .await?;
-
-        // Start the pdf-analyzer actor
-        let pdf_analyzer_handle = tokio::spawn(async move {
+        // Children are namespaced under the indexer, and stop along with it
+        let options = SpawnOptions::builder().exists(SpawnExistsOptions::Reset).build();
+
+        // Spawn and start the pdf-analyzer actor
+        let (mut pdf_analyzer_ctx, mut pdf_analyzer_actor) =
+            ctx.spawn_child("pdf-analyzer", self.pdf_analyzer.clone(), options.clone()).await?;
+        self.pdf_analyzer_id = Some(pdf_analyzer_ctx.id().clone());
+        tokio::spawn(async move {
             if let Err(e) = pdf_analyzer_actor.start(&mut pdf_analyzer_ctx).await {
                 error!("PdfAnalyzer actor error: {}", e);
             }
         });
 
-        // Generate child id for embeddings
-        let embeddings_id = ActorId::of::<Embeddings>(format!("{}/embeddings", self_id.name()));
-        self.embeddings_id = Some(embeddings_id.clone());
-
-        // Spawn the embeddings actor
-        let (mut embeddings_ctx, mut embeddings_actor) = Actor::spawn(
-            ctx.engine().clone(),
-            embeddings_id.clone(),
-            self.embeddings.clone(),
-            SpawnOptions::builder().exists(SpawnExistsOptions::Reset).build(),
-        )
-        .await?;
-
-        // Start the embeddings actor
-        let embeddings_handle = tokio::spawn(async move {
+        // Spawn and start the embeddings actor
+        let (mut embeddings_ctx, mut embeddings_actor) =
+            ctx.spawn_child("embeddings", self.embeddings.clone(), options.clone()).await?;
+        self.embeddings_id = Some(embeddings_ctx.id().clone());
+        tokio::spawn(async move {
             if let Err(e) = embeddings_actor.start(&mut embeddings_ctx).await {
                 error!("Embeddings actor error: {}", e);
             }
         });
 
-        // Generate child id for markitdown
-        let markitdown_id = ActorId::of::<MarkitDown>(format!("{}/markitdown", self_id.name()));
-        self.markitdown_id = Some(markitdown_id.clone());
-
-        let (mut markitdown_ctx, mut markitdown_actor) = Actor::spawn(
-            ctx.engine().clone(),
-            markitdown_id.clone(),
-            self.markitdown.clone(),
-            SpawnOptions::builder().exists(SpawnExistsOptions::Reset).build(),
-        )
-        .await?;
-
-        let markitdown_handle = tokio::spawn(async move {
+        // Spawn and start the markitdown actor
+        let (mut markitdown_ctx, mut markitdown_actor) =
+            ctx.spawn_child("markitdown", self.markitdown.clone(), options).await?;
+        self.markitdown_id = Some(markitdown_ctx.id().clone());
+        tokio::spawn(async move {
             if let Err(e) = markitdown_actor.start(&mut markitdown_ctx).await {
                 error!("MarkitDown actor error: {}", e);
             }
         });
 
-        self.pdf_analyzer_handle = Some(pdf_analyzer_handle);
-        self.embeddings_handle = Some(embeddings_handle);
-        self.markitdown_handle = Some(markitdown_handle);
-
         info!("Indexer ready");
 
         Ok(())
@@ -20,3 +20,22 @@ pub mod prelude {
         images::Image,
     };
 }
+
+/// Registers the messages handled by the LLM actors, with their JSON Schemas
+pub fn register_messages(registry: &bioma_actor::MessageRegistry) {
+    use crate::prelude::*;
+
+    registry.add::<Embeddings, embeddings::TopK>();
+    registry.add::<Embeddings, StoreEmbeddings>();
+    registry.add::<Embeddings, GenerateEmbeddings>();
+    registry.add::<Rerank, RankTexts>();
+    registry.add::<Indexer, IndexGlobs>();
+    registry.add::<Indexer, DeleteSource>();
+    registry.add::<Retriever, RetrieveContext>();
+    registry.add::<pdf_analyzer::PdfAnalyzer, pdf_analyzer::AnalyzePdf>();
+    registry.add::<markitdown::MarkitDown, markitdown::AnalyzeMCFile>();
+
+    // Chat replies are ollama-rs responses, which have no schema
+    let request = serde_json::to_value(schemars::schema_for!(ChatMessages)).unwrap_or(serde_json::Value::Bool(true));
+    registry.add_with_schemas::<Chat, ChatMessages>(request, serde_json::Value::Bool(true));
+}
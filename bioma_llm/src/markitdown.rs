@@ -1,4 +1,5 @@
 use bioma_actor::prelude::*;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use tracing::{error, info};
@@ -9,7 +10,7 @@ struct MarkitDownServerResponse {
     text_content: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct AnalyzeMCFile {
     pub file_path: PathBuf,
 }
@@ -1,4 +1,5 @@
 use bioma_actor::prelude::*;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use tracing::{error, info};
@@ -28,7 +29,7 @@ fn convert_pdf_json_to_markdown(json_data: &Vec<JsonDataFromPdf>) -> Result<Stri
     Ok(markdown)
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct AnalyzePdf {
     pub file_path: PathBuf,
 }
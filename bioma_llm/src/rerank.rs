@@ -3,6 +3,7 @@ use bioma_actor::prelude::*;
 use bon::Builder;
 use derive_more::{Deref, Display};
 use lazy_static::lazy_static;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, Weak};
 use tokio::sync::Mutex;
@@ -27,7 +28,7 @@ pub enum RerankError {
 
 impl ActorError for RerankError {}
 
-#[derive(Builder, Debug, Clone, Serialize, Deserialize)]
+#[derive(Builder, Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct RankTexts {
     /// The query text to compare against the corpus of texts
     ///
@@ -68,7 +69,7 @@ pub struct RankTexts {
     pub truncation_direction: TruncationDirection,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 // #[serde(rename_all = "lowercase")]
 pub enum TruncationDirection {
     Left,
@@ -91,7 +92,7 @@ fn default_truncation_direction() -> TruncationDirection {
     TruncationDirection::Right
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct RankedText {
     pub index: usize,
     pub score: f32,
@@ -99,7 +100,7 @@ pub struct RankedText {
     pub text: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct RankedTexts {
     pub texts: Vec<RankedText>,
 }
@@ -108,6 +109,12 @@ impl Message<RankTexts> for Rerank {
     type Response = RankedTexts;
 
     async fn handle(&mut self, ctx: &mut ActorContext<Self>, rank_texts: &RankTexts) -> Result<(), RerankError> {
+        self.handle_shared(ctx, rank_texts).await
+    }
+}
+
+impl SharedMessage<RankTexts> for Rerank {
+    async fn handle_shared(&self, ctx: &mut ActorContext<Self>, rank_texts: &RankTexts) -> Result<(), RerankError> {
         if rank_texts.texts.is_empty() {
             warn!("No texts to rerank");
             ctx.reply(RankedTexts { texts: vec![] }).await?;
@@ -199,16 +206,19 @@ impl Actor for Rerank {
 
         self.init(ctx).await?;
 
-        // Start the message stream
-        let mut stream = ctx.recv().await?;
-        while let Some(Ok(frame)) = stream.next().await {
+        // Start the message stream, requests are handled concurrently when `SpawnOptions::concurrency` is set
+        let stream = ctx.recv().await?;
+        let rerank = &*self;
+        ctx.handle_concurrently(stream, |mut ctx, frame| async move {
             if let Some(rank_texts) = frame.is::<RankTexts>() {
-                let response = self.reply(ctx, &rank_texts, &frame).await;
+                let response = rerank.reply_shared(&mut ctx, &rank_texts, &frame).await;
                 if let Err(err) = response {
                     error!("{} {:?}", ctx.id(), err);
                 }
             }
-        }
+            Ok(())
+        })
+        .await?;
         info!("{} Finished", ctx.id());
         Ok(())
     }
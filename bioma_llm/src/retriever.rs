@@ -2,6 +2,7 @@ use crate::embeddings::{self, Embeddings, EmbeddingsError};
 use crate::indexer::{ContentSource, Metadata};
 use crate::rerank::{RankTexts, Rerank, RerankError, TruncationDirection};
 use bioma_actor::prelude::*;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use tracing::{error, info};
 
@@ -34,7 +35,7 @@ pub enum RetrieverError {
 
 impl ActorError for RetrieverError {}
 
-#[derive(bon::Builder, Debug, Clone, Serialize, Deserialize)]
+#[derive(bon::Builder, Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct RetrieveContext {
     /// The query to search for
     #[serde(flatten)]
@@ -52,7 +53,7 @@ pub struct RetrieveContext {
     pub source: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "type", content = "query")]
 pub enum RetrieveQuery {
     Text(String),
@@ -66,14 +67,14 @@ fn default_retriever_threshold() -> f32 {
     DEFAULT_RETRIEVER_THRESHOLD
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Context {
     pub text: Option<String>,
     pub source: Option<ContentSource>,
     pub metadata: Option<Metadata>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct RetrievedContext {
     pub context: Vec<Context>,
 }
@@ -239,22 +240,11 @@ pub struct Retriever {
     pub rerank: Rerank,
     embeddings_id: Option<ActorId>,
     rerank_id: Option<ActorId>,
-    #[serde(skip)]
-    embeddings_handle: Option<tokio::task::JoinHandle<()>>,
-    #[serde(skip)]
-    rerank_handle: Option<tokio::task::JoinHandle<()>>,
 }
 
 impl Default for Retriever {
     fn default() -> Self {
-        Self {
-            embeddings: Embeddings::default(),
-            rerank: Rerank::default(),
-            embeddings_id: None,
-            rerank_id: None,
-            embeddings_handle: None,
-            rerank_handle: None,
-        }
+        Self { embeddings: Embeddings::default(), rerank: Rerank::default(), embeddings_id: None, rerank_id: None }
     }
 }
 
@@ -280,48 +270,38 @@ impl Actor for Retriever {
 
 impl Retriever {
     pub async fn init(&mut self, ctx: &mut ActorContext<Self>) -> Result<(), RetrieverError> {
-        let self_id = ctx.id().clone();
-        let embeddings_id = ActorId::of::<Embeddings>(format!("{}/embeddings", self_id.name()));
-        let rerank_id = ActorId::of::<Rerank>(format!("{}/rerank", self_id.name()));
-
-        self.embeddings_id = Some(embeddings_id.clone());
-        self.rerank_id = Some(rerank_id.clone());
-
         // Spawn the embeddings actor
-        let (mut embeddings_ctx, mut embeddings_actor) = Actor::spawn(
-            ctx.engine().clone(),
-            embeddings_id.clone(),
-            self.embeddings.clone(),
-            SpawnOptions::builder().exists(SpawnExistsOptions::Reset).build(),
-        )
-        .await?;
+        let (mut embeddings_ctx, mut embeddings_actor) = ctx
+            .spawn_child(
+                "embeddings",
+                self.embeddings.clone(),
+                SpawnOptions::builder().exists(SpawnExistsOptions::Reset).build(),
+            )
+            .await?;
+        self.embeddings_id = Some(embeddings_ctx.id().clone());
 
         // Spawn the rerank actor
-        let (mut rerank_ctx, mut rerank_actor) = Actor::spawn(
-            ctx.engine().clone(),
-            rerank_id.clone(),
-            self.rerank.clone(),
-            SpawnOptions::builder().exists(SpawnExistsOptions::Reset).build(),
-        )
-        .await?;
-
-        // Start the embeddings actor
-        let embeddings_handle = tokio::spawn(async move {
+        let (mut rerank_ctx, mut rerank_actor) = ctx
+            .spawn_child(
+                "rerank",
+                self.rerank.clone(),
+                SpawnOptions::builder().exists(SpawnExistsOptions::Reset).build(),
+            )
+            .await?;
+        self.rerank_id = Some(rerank_ctx.id().clone());
+
+        // Start the children, they stop along with the retriever
+        tokio::spawn(async move {
             if let Err(e) = embeddings_actor.start(&mut embeddings_ctx).await {
                 error!("Embeddings actor error: {}", e);
             }
         });
-
-        // Start the rerank actor
-        let rerank_handle = tokio::spawn(async move {
+        tokio::spawn(async move {
             if let Err(e) = rerank_actor.start(&mut rerank_ctx).await {
                 error!("Rerank actor error: {}", e);
             }
         });
 
-        self.embeddings_handle = Some(embeddings_handle);
-        self.rerank_handle = Some(rerank_handle);
-
         info!("Retriever ready");
 
         Ok(())
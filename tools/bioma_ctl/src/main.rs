@@ -0,0 +1,351 @@
+use anyhow::{anyhow, Context, Result};
+use bioma_actor::prelude::*;
+use clap::{Parser, Subcommand, ValueEnum};
+use futures::stream::{self, BoxStream};
+use std::borrow::Cow;
+use std::path::PathBuf;
+use std::time::Duration;
+use surrealdb::{Action, Notification, RecordId};
+
+/// Inspect and drive the actors of a running engine
+#[derive(Parser)]
+#[command(name = "bioma-ctl")]
+struct Args {
+    /// JSON file with the `EngineOptions`, or a config with them under `engine` like the cognition server's
+    #[arg(long, short)]
+    config: Option<PathBuf>,
+    /// Database endpoint, overrides the config
+    #[arg(long)]
+    endpoint: Option<String>,
+    /// Database namespace, overrides the config
+    #[arg(long)]
+    namespace: Option<String>,
+    /// Database name, overrides the config
+    #[arg(long)]
+    database: Option<String>,
+    /// Database username, overrides the config
+    #[arg(long)]
+    username: Option<String>,
+    /// Database password, overrides the config
+    #[arg(long)]
+    password: Option<String>,
+    /// Environment variable with the encryption keys, as comma separated `id:key` entries with hex keys, the current one first
+    #[arg(long, conflicts_with = "key_file")]
+    key_env: Option<String>,
+    /// File with the encryption keys, one `id:key` entry with a hex key per line, the current one first
+    #[arg(long)]
+    key_file: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List the actors and their health
+    Actors {
+        /// Only actors whose tag contains this text
+        #[arg(long)]
+        tag: Option<String>,
+        /// Only actors that are not healthy
+        #[arg(long)]
+        unhealthy: bool,
+    },
+    /// Print an actor and the children it spawned, recursively
+    Tree {
+        /// Actor name, e.g. /rag/indexer
+        actor: String,
+    },
+    /// Print messages and replies as they are stored
+    Tail {
+        /// Which frames to print
+        #[arg(long, value_enum, default_value_t = TailKind::All)]
+        kind: TailKind,
+        /// Only frames sent or received by this actor
+        #[arg(long)]
+        actor: Option<String>,
+        /// Only frames whose name contains this text
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Show the messages an actor hasn't replied to yet
+    Pending {
+        /// Actor name, e.g. /rag/indexer
+        actor: String,
+    },
+    /// Send a JSON message to an actor and print the replies
+    Send {
+        /// Actor name, e.g. /rag/indexer
+        actor: String,
+        /// Message name, usually the Rust type name, e.g. bioma_llm::indexer::IndexGlobs
+        name: String,
+        /// Message content as JSON
+        message: String,
+        /// Seconds to wait for each reply
+        #[arg(long, default_value_t = 30)]
+        timeout: u64,
+    },
+    /// Delete the state, health and lease records of an actor and its descendants
+    Kill {
+        /// Actor name, e.g. /rag/indexer
+        actor: String,
+    },
+    /// Delete an actor's state, so it starts over the next time it's spawned
+    Reset {
+        /// Actor name, e.g. /rag/indexer
+        actor: String,
+        /// Also drop the messages it hasn't replied to yet
+        #[arg(long)]
+        pending: bool,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum TailKind {
+    All,
+    Messages,
+    Replies,
+}
+
+impl Args {
+    fn engine_options(&self) -> Result<EngineOptions> {
+        let mut options = match &self.config {
+            Some(path) => {
+                let config = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+                let mut config: serde_json::Value = serde_json::from_str(&config)?;
+                let engine = match config.get_mut("engine") {
+                    Some(engine) => engine.take(),
+                    None => config,
+                };
+                serde_json::from_value::<EngineOptions>(engine)?
+            }
+            None => EngineOptions::builder().endpoint("ws://0.0.0.0:9123".into()).build(),
+        };
+        let overrides = [
+            (&mut options.endpoint, &self.endpoint),
+            (&mut options.namespace, &self.namespace),
+            (&mut options.database, &self.database),
+            (&mut options.username, &self.username),
+            (&mut options.password, &self.password),
+        ];
+        for (option, value) in overrides {
+            if let Some(value) = value {
+                *option = Cow::Owned(value.clone());
+            }
+        }
+        if let Some(keys) = self.keys()? {
+            options.encryption = Some(Encryption::new(keys));
+        }
+        Ok(options)
+    }
+
+    /// Encryption keys from `--key-env` or `--key-file`, if given
+    fn keys(&self) -> Result<Option<StaticKeys>> {
+        let entries = match (&self.key_env, &self.key_file) {
+            (Some(var), _) => std::env::var(var).with_context(|| format!("reading ${}", var))?,
+            (None, Some(path)) => {
+                std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?
+            }
+            (None, None) => return Ok(None),
+        };
+        let mut keys: Option<StaticKeys> = None;
+        for entry in entries.split([',', '\n']).map(str::trim).filter(|entry| !entry.is_empty()) {
+            let (id, key) = entry.split_once(':').ok_or_else(|| anyhow!("key entries must be id:key"))?;
+            let key = parse_key(key).with_context(|| format!("parsing key {}", id))?;
+            keys = Some(match keys {
+                None => StaticKeys::new(id, key),
+                Some(keys) => keys.with_key(id, key),
+            });
+        }
+        keys.map(Some).ok_or_else(|| anyhow!("no encryption keys given"))
+    }
+}
+
+/// Parses a 256-bit key written in hex
+fn parse_key(hex: &str) -> Result<[u8; 32]> {
+    if hex.len() != 64 || !hex.is_ascii() {
+        return Err(anyhow!("expected 64 hex digits"));
+    }
+    let mut key = [0; 32];
+    for (i, byte) in key.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)?;
+    }
+    Ok(key)
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // Keep the output for the command, logs only when asked for
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("warn"));
+    tracing_subscriber::fmt().with_env_filter(filter).with_writer(std::io::stderr).init();
+
+    let args = Args::parse();
+    let engine = Engine::connect(args.engine_options()?).await?;
+
+    match args.command {
+        Command::Actors { tag, unhealthy } => actors(&engine, tag, unhealthy).await,
+        Command::Tree { actor } => {
+            let id = find_actor(&engine, &actor).await?;
+            print_tree(&engine.actor_tree(&id).await?, 0);
+            Ok(())
+        }
+        Command::Tail { kind, actor, name } => tail(&engine, kind, actor, name).await,
+        Command::Pending { actor } => pending(&engine, &actor).await,
+        Command::Send { actor, name, message, timeout } => send(&engine, &actor, name, &message, timeout).await,
+        Command::Kill { actor } => {
+            let id = find_actor(&engine, &actor).await?;
+            engine.kill_actor(&id).await?;
+            println!("killed {}", id);
+            Ok(())
+        }
+        Command::Reset { actor, pending } => reset(&engine, &actor, pending).await,
+    }
+}
+
+/// Finds an actor by name
+async fn find_actor(engine: &Engine, name: &str) -> Result<ActorId> {
+    engine.actors().await?.into_iter().find(|id| id.name() == name).ok_or_else(|| anyhow!("actor not found: {}", name))
+}
+
+async fn actors(engine: &Engine, tag: Option<String>, unhealthy: bool) -> Result<()> {
+    let report = engine.health_report().await?;
+    println!("database: {}", if report.database { "ok" } else { "unreachable" });
+
+    for id in engine.actors().await? {
+        if tag.as_ref().is_some_and(|tag| !id.tag().contains(tag.as_str())) {
+            continue;
+        }
+        let health = report.actors.iter().find(|health| health.id == id);
+        if unhealthy && health.is_none_or(|health| health.is_healthy()) {
+            continue;
+        }
+        let status = match health {
+            None => "unmonitored".to_string(),
+            Some(health) if health.is_healthy() => format!("healthy (last seen {})", health.last_seen),
+            Some(health) if !health.live => format!("down (last seen {})", health.last_seen),
+            Some(health) => format!("not ready: {}", health.reason.as_deref().unwrap_or("no reason given")),
+        };
+        println!("{}  {}  {}", id.name(), id.tag(), status);
+    }
+    Ok(())
+}
+
+fn print_tree(tree: &ActorTree, depth: usize) {
+    println!("{}{}  {}", "  ".repeat(depth), tree.id.name(), tree.id.tag());
+    for child in &tree.children {
+        print_tree(child, depth + 1);
+    }
+}
+
+/// A message or reply being tailed
+enum Frame {
+    Message(FrameMessage),
+    Reply(FrameReply),
+}
+
+async fn tail(engine: &Engine, kind: TailKind, actor: Option<String>, name: Option<String>) -> Result<()> {
+    let actor = match actor {
+        Some(actor) => Some(find_actor(engine, &actor).await?.record_id()),
+        None => None,
+    };
+
+    let mut streams: Vec<BoxStream<'static, Result<Frame>>> = Vec::new();
+    if kind != TailKind::Replies {
+        let mut res = engine.db().lock().await.query("LIVE SELECT * FROM message").await?;
+        let messages = res.stream::<Notification<FrameMessage>>(0)?;
+        streams.push(Box::pin(created(messages).map(|frame| frame.map(Frame::Message))));
+    }
+    if kind != TailKind::Messages {
+        let mut res = engine.db().lock().await.query("LIVE SELECT id.{id, chunk}, * FROM reply").await?;
+        let replies = res.stream::<Notification<FrameReply>>(0)?;
+        streams.push(Box::pin(created(replies).map(|frame| frame.map(Frame::Reply))));
+    }
+
+    let involves = |tx: &RecordId, rx: &RecordId| actor.as_ref().is_none_or(|actor| actor == tx || actor == rx);
+    let named = |frame_name: &str| name.as_ref().is_none_or(|name| frame_name.contains(name.as_str()));
+
+    let mut frames = stream::select_all(streams);
+    while let Some(frame) = frames.next().await {
+        match frame? {
+            Frame::Message(frame) if involves(&frame.tx, &frame.rx) && named(&frame.name) => {
+                let frame = engine.decrypt_message(frame)?;
+                println!("msg   {} {} {} -> {} {}", frame.id(), frame.name, frame.tx, frame.rx, frame.msg);
+            }
+            Frame::Reply(frame) if involves(&frame.tx, &frame.rx) && named(&frame.name) => {
+                let frame = engine.decrypt_reply(frame)?;
+                let chunk = frame.chunk().map(|chunk| chunk.to_string()).unwrap_or_else(|| "final".to_string());
+                let content = if frame.err.is_null() { &frame.msg } else { &frame.err };
+                println!(
+                    "reply {}#{} {} {} -> {} {}",
+                    frame.message_id(),
+                    chunk,
+                    frame.name,
+                    frame.tx,
+                    frame.rx,
+                    content
+                );
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Keeps the records created in a live query
+fn created<T: Send + 'static>(
+    notifications: impl futures::Stream<Item = Result<Notification<T>, surrealdb::Error>> + Send + 'static,
+) -> impl futures::Stream<Item = Result<T>> + Send + 'static {
+    notifications.filter_map(|notification| async move {
+        match notification {
+            Ok(notification) if notification.action == Action::Create => Some(Ok(notification.data)),
+            Ok(_) => None,
+            Err(e) => Some(Err(e.into())),
+        }
+    })
+}
+
+async fn pending(engine: &Engine, actor: &str) -> Result<()> {
+    let id = find_actor(engine, actor).await?;
+    let messages = engine.pending_messages(&id).await?;
+    println!("{} pending messages for {}", messages.len(), id.name());
+    for frame in messages {
+        let frame = engine.decrypt_message(frame)?;
+        println!("{} {} from {} {}", frame.id(), frame.name, frame.tx, frame.msg);
+    }
+    Ok(())
+}
+
+async fn send(engine: &Engine, actor: &str, name: String, message: &str, timeout: u64) -> Result<()> {
+    let id = find_actor(engine, actor).await?;
+    let message: serde_json::Value = serde_json::from_str(message).context("parsing the message")?;
+
+    // Send from a relay of its own, removed once done
+    let relay_id = ActorId::of::<Relay>(format!("/bioma-ctl/{}", ulid::Ulid::new()));
+    let (relay_ctx, _relay) = Actor::spawn(engine.clone(), relay_id, Relay, SpawnOptions::default()).await?;
+
+    let options = SendOptions::builder().timeout(Duration::from_secs(timeout)).build();
+    let result = async {
+        let mut replies = relay_ctx.send_named(name, message, &id, options).await?;
+        while let Some(reply) = replies.next().await {
+            println!("{}", serde_json::to_string_pretty(&reply?)?);
+        }
+        Ok::<_, anyhow::Error>(())
+    }
+    .await;
+
+    relay_ctx.kill().await?;
+    result
+}
+
+async fn reset(engine: &Engine, actor: &str, pending: bool) -> Result<()> {
+    let id = find_actor(engine, actor).await?;
+    if pending {
+        let messages = engine.pending_messages(&id).await?;
+        let ids: Vec<RecordId> = messages.iter().map(|frame| frame.id().clone()).collect();
+        engine.db().lock().await.query("DELETE $ids").bind(("ids", ids)).await?.check()?;
+        println!("dropped {} pending messages", messages.len());
+    }
+    engine.db().lock().await.query("DELETE $id").bind(("id", id.record_id())).await?.check()?;
+    println!("reset {}", id);
+    Ok(())
+}
@@ -1,5 +1,5 @@
 use anyhow::Result;
-use bioma_actor::EngineOptions;
+use bioma_actor::{EngineOptions, ObjectStoreOptions};
 use bioma_tool::client::ClientConfig;
 use clap::Parser;
 use serde::{Deserialize, Serialize};
@@ -150,7 +150,13 @@ impl Args {
         info!("│  ├─ Username: {}", config.engine.username);
         info!("│  ├─ Output Directory: {}", config.engine.output_dir.display());
         info!("│  ├─ Local Store Directory: {}", config.engine.local_store_dir.display());
-        info!("│  └─ HuggingFace Cache Directory: {}", config.engine.hf_cache_dir.display());
+        info!("│  ├─ HuggingFace Cache Directory: {}", config.engine.hf_cache_dir.display());
+        match &config.engine.object_store {
+            ObjectStoreOptions::Local => info!("│  └─ Object Store: local"),
+            ObjectStoreOptions::S3 { endpoint, bucket, .. } => {
+                info!("│  └─ Object Store: s3 {} at {}", bucket, endpoint.as_deref().unwrap_or("AWS"))
+            }
+        }
         info!("├─ RAG Endpoint: {}", config.rag_endpoint);
         info!("├─ Chat Endpoint: {}", config.chat_endpoint);
         info!("├─ Chat Model: {}", config.chat_model);
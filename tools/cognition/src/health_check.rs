@@ -1,3 +1,4 @@
+use bioma_actor::{Engine, HealthReport};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
@@ -13,6 +14,8 @@ pub enum HealthCheckError {
     ParseError(String),
     #[error("OllamaError: {0}")]
     OllamaError(String),
+    #[error("ActorError: {0}")]
+    ActorError(String),
 }
 
 #[derive(Serialize, Clone, Debug, Hash, PartialEq, Eq)]
@@ -27,6 +30,8 @@ pub enum Service {
     Markitdown,
     #[serde(rename = "minio")]
     Minio,
+    #[serde(rename = "actors")]
+    Actors,
 }
 
 #[derive(Serialize, Clone, Debug, Hash, PartialEq, Eq)]
@@ -70,6 +75,11 @@ pub enum Responses {
         #[serde(flatten)]
         status: Status,
     },
+    Actors {
+        #[serde(flatten)]
+        status: Status,
+        health: Option<HealthReport>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Hash, PartialEq, Eq)]
@@ -256,3 +266,36 @@ pub async fn check_surrealdb(endpoint: String) -> Responses {
         Err(e) => return Responses::SurrealDb { status: Status::unhealthy(e) },
     };
 }
+
+pub async fn check_actors(engine: &Engine) -> Responses {
+    let report = match engine.health_report().await {
+        Ok(report) => report,
+        Err(e) => {
+            return Responses::Actors {
+                status: Status::unhealthy(HealthCheckError::ActorError(e.to_string())),
+                health: None,
+            }
+        }
+    };
+
+    if report.is_healthy() {
+        return Responses::Actors { status: Status::healthy(), health: Some(report) };
+    }
+
+    let unhealthy: Vec<String> = report
+        .actors
+        .iter()
+        .filter(|actor| !actor.is_healthy())
+        .map(|actor| match &actor.reason {
+            Some(reason) => format!("{} ({})", actor.id.name(), reason),
+            None => actor.id.name().to_string(),
+        })
+        .collect();
+    let error = if report.database {
+        HealthCheckError::ActorError(format!("Unhealthy actors: {}", unhealthy.join(", ")))
+    } else {
+        HealthCheckError::ActorError("Database unreachable".to_string())
+    };
+
+    Responses::Actors { status: Status::unhealthy(error), health: Some(report) }
+}
@@ -13,7 +13,8 @@ use bioma_llm::{markitdown::MarkitDown, pdf_analyzer::PdfAnalyzer};
 use clap::Parser;
 use cognition::{
     health_check::{
-        check_markitdown, check_minio, check_ollama, check_pdf_analyzer, check_surrealdb, Responses, Service,
+        check_actors, check_markitdown, check_minio, check_ollama, check_pdf_analyzer, check_surrealdb, Responses,
+        Service,
     },
     ChatResponse, ToolsHub, UserActor,
 };
@@ -98,6 +99,9 @@ async fn health(data: web::Data<AppState>) -> impl Responder {
     let minio_url = Url::parse("http://127.0.0.1:9000").unwrap();
     services.insert(Service::Minio, check_minio(minio_url).await);
 
+    // Actors health check
+    services.insert(Service::Actors, check_actors(&data.engine).await);
+
     HttpResponse::Ok().json(services)
 }
 
@@ -1021,6 +1025,59 @@ async fn dashboard() -> impl Responder {
     NamedFile::open_async("assets/dashboard.html").await
 }
 
+#[utoipa::path(
+    get,
+    path = "/messages/schema",
+    description = "JSON Schemas of the messages handled by the actors, by frame name.",
+    responses(
+        (status = 200, description = "Ok"),
+    )
+)]
+async fn messages_schema(data: web::Data<AppState>) -> HttpResponse {
+    HttpResponse::Ok().json(data.engine.messages().export())
+}
+
+#[derive(serde::Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+struct FlowQuery {
+    /// Length of the aggregation window, in milliseconds
+    window_ms: Option<u64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/flow",
+    description = "Streams the message flow between actors as server-sent events, one event per window.",
+    params(FlowQuery),
+    responses(
+        (status = 200, description = "Ok", content_type = "text/event-stream"),
+    )
+)]
+async fn flow(query: web::Query<FlowQuery>, data: web::Data<AppState>) -> HttpResponse {
+    let window = std::time::Duration::from_millis(query.window_ms.unwrap_or(1000).max(100));
+    match data.engine.message_flow(window).await {
+        Ok(flow) => HttpResponse::Ok()
+            .content_type("text/event-stream")
+            .insert_header(("Cache-Control", "no-cache"))
+            .streaming::<_, Box<dyn StdError>>(flow.map(|result| match result {
+            Ok(flow) => {
+                let json = serde_json::to_string(&flow).unwrap_or_default();
+                Ok(web::Bytes::from(format!("data: {}\n\n", json)))
+            }
+            Err(e) => {
+                let error_json = serde_json::json!({
+                    "error": e.to_string()
+                });
+                Ok(web::Bytes::from(format!("event: error\ndata: {}\n\n", error_json)))
+            }
+        })),
+        Err(e) => {
+            error!("Error streaming message flow: {:?}", e);
+            HttpResponse::InternalServerError().body(format!("Error streaming message flow: {}", e))
+        }
+    }
+}
+
 async fn swagger_initializer(data: web::Data<AppState>) -> impl Responder {
     // Get the base URL as a string, without trailing slash
     let endpoint = data.config.rag_endpoint.as_str().trim_end_matches('/');
@@ -1053,7 +1110,23 @@ async fn swagger_initializer(data: web::Data<AppState>) -> impl Responder {
 
 #[derive(OpenApi)]
 #[openapi(
-    paths(health, hello, reset, index, retrieve, ask, chat, think, upload, delete_source, embed, rerank, dashboard),
+    paths(
+        health,
+        hello,
+        reset,
+        index,
+        retrieve,
+        ask,
+        chat,
+        think,
+        upload,
+        delete_source,
+        embed,
+        rerank,
+        dashboard,
+        messages_schema,
+        flow
+    ),
     info(
         title = "Cognition API",
         version = "0.1.0",
@@ -1075,6 +1148,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Initialize engine
     let engine = Engine::connect(config.engine.clone()).await?;
+    bioma_llm::register_messages(engine.messages());
+    bioma_behavior::register_messages(engine.messages());
 
     // Spawn main actors and their relays
     let mut actor_handles = Vec::new();
@@ -1085,7 +1160,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         engine.clone(),
         indexer_id.clone(),
         Indexer::default(),
-        SpawnOptions::builder().exists(SpawnExistsOptions::Restore).build(),
+        SpawnOptions::builder()
+            .exists(SpawnExistsOptions::Restore)
+            .health_config(HealthConfig::builder().build())
+            .build(),
     )
     .await?;
 
@@ -1102,7 +1180,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         engine.clone(),
         retriever_id.clone(),
         Retriever::default(),
-        SpawnOptions::builder().exists(SpawnExistsOptions::Reset).build(),
+        SpawnOptions::builder()
+            .exists(SpawnExistsOptions::Reset)
+            .health_config(HealthConfig::builder().build())
+            .build(),
     )
     .await?;
 
@@ -1118,7 +1199,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         engine.clone(),
         embeddings_id.clone(),
         Embeddings::default(),
-        SpawnOptions::builder().exists(SpawnExistsOptions::Reset).build(),
+        SpawnOptions::builder()
+            .exists(SpawnExistsOptions::Reset)
+            .health_config(HealthConfig::builder().build())
+            .build(),
     )
     .await?;
 
@@ -1134,7 +1218,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         engine.clone(),
         rerank_id.clone(),
         Rerank::default(),
-        SpawnOptions::builder().exists(SpawnExistsOptions::Reset).build(),
+        SpawnOptions::builder()
+            .exists(SpawnExistsOptions::Reset)
+            .health_config(HealthConfig::builder().build())
+            .build(),
     )
     .await?;
 
@@ -1155,7 +1242,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             .messages_number_limit(config.chat_messages_limit)
             .generation_options(GenerationOptions::default().num_ctx(config.chat_context_length))
             .build(),
-        SpawnOptions::builder().exists(SpawnExistsOptions::Reset).build(),
+        SpawnOptions::builder()
+            .exists(SpawnExistsOptions::Reset)
+            .health_config(HealthConfig::builder().build())
+            .build(),
     )
     .await?;
 
@@ -1177,7 +1267,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             .messages_number_limit(config.think_messages_limit)
             .generation_options(GenerationOptions::default().num_ctx(config.think_context_length))
             .build(),
-        SpawnOptions::builder().exists(SpawnExistsOptions::Reset).build(),
+        SpawnOptions::builder()
+            .exists(SpawnExistsOptions::Reset)
+            .health_config(HealthConfig::builder().build())
+            .build(),
     )
     .await?;
 
@@ -1242,6 +1335,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             .route("/delete_source", web::post().to(delete_source))
             .route("/embed", web::post().to(embed))
             .route("/rerank", web::post().to(rerank))
+            .route("/messages/schema", web::get().to(messages_schema))
+            .route("/flow", web::get().to(flow))
             .route(
                 "/api-docs/openapi.json",
                 web::get().to(|data: web::Data<AppState>| async move {